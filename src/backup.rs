@@ -0,0 +1,183 @@
+//! Backups of what the user keeps in adb's directories, for moving a setup to another machine:
+//! the config file (with its saved searches, routes and waypoints), local info and index
+//! snapshots. The index itself is left out, since `adb update` builds it again.
+//!
+//! A backup is a zstd compressed tar archive holding `config.toml`, `local-info.csv` and a
+//! `snapshots` directory, as many of them as there were.
+
+use std::{
+    fs::{self, File},
+    path::{Component, Path, PathBuf},
+};
+
+use crate::{error::Error, snapshot};
+
+const CONFIG: &str = "config.toml";
+const LOCAL_INFO: &str = "local-info.csv";
+
+/// Where the files that go into a backup live.
+pub struct Locations<'a> {
+    pub config: Option<&'a Path>,
+    pub local_info: Option<&'a Path>,
+    pub data_dir: &'a Path,
+}
+
+impl Locations<'_> {
+    /// Where an archive entry is restored to, or `None` if it isn't one of ours.
+    fn target(&self, name: &Path) -> crate::Result<Option<PathBuf>> {
+        let mut components = name.components();
+        let Some(Component::Normal(first)) = components.next() else {
+            return Ok(None);
+        };
+
+        if first == CONFIG || first == LOCAL_INFO {
+            if components.next().is_some() {
+                return Ok(None);
+            }
+            let path = if first == CONFIG {
+                self.config
+            } else {
+                self.local_info
+            };
+            return path
+                .map(|path| Some(path.into()))
+                .ok_or(Error::NoHomeDirectory);
+        }
+
+        if first != snapshot::SNAPSHOTS {
+            return Ok(None);
+        }
+
+        // Only plain names below `snapshots`, so that nothing is written outside it.
+        let mut target = snapshot::dir(self.data_dir);
+        for component in components {
+            match component {
+                Component::Normal(part) => target.push(part),
+                _ => return Ok(None),
+            }
+        }
+        Ok(Some(target))
+    }
+}
+
+/// Writes a backup to `path`, returning what went into it.
+pub fn backup(locations: &Locations, path: &Path) -> crate::Result<Vec<PathBuf>> {
+    let mut archive = tar::Builder::new(zstd::Encoder::new(File::create(path)?, 0)?);
+    let mut saved = Vec::new();
+
+    for (name, source) in [
+        (CONFIG, locations.config),
+        (LOCAL_INFO, locations.local_info),
+    ] {
+        if let Some(source) = source.filter(|source| source.is_file()) {
+            archive.append_path_with_name(source, name)?;
+            saved.push(source.into());
+        }
+    }
+
+    let snapshots = snapshot::dir(locations.data_dir);
+    if snapshots.is_dir() {
+        archive.append_dir_all(snapshot::SNAPSHOTS, &snapshots)?;
+        saved.push(snapshots);
+    }
+
+    archive.into_inner()?.finish()?;
+    Ok(saved)
+}
+
+/// Puts the files in the backup at `path` back where they belong, replacing any already there,
+/// and returns where they went. Entries adb didn't write are skipped.
+pub fn restore(locations: &Locations, path: &Path) -> crate::Result<Vec<PathBuf>> {
+    let mut archive = tar::Archive::new(zstd::Decoder::new(File::open(path)?)?);
+    let mut restored = Vec::new();
+    let snapshots = snapshot::dir(locations.data_dir);
+
+    for entry in archive.entries()? {
+        let mut entry = entry?;
+        let Some(target) = locations.target(&entry.path()?)? else {
+            continue;
+        };
+
+        if entry.header().entry_type().is_dir() {
+            fs::create_dir_all(&target)?;
+        } else if entry.header().entry_type().is_file() {
+            if let Some(parent) = target.parent() {
+                fs::create_dir_all(parent)?;
+            }
+            entry.unpack(&target)?;
+        } else {
+            continue;
+        }
+
+        let restored_as = if target.starts_with(&snapshots) {
+            snapshots.clone()
+        } else {
+            target
+        };
+        if !restored.contains(&restored_as) {
+            restored.push(restored_as);
+        }
+    }
+
+    Ok(restored)
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{
+        fs,
+        path::{Path, PathBuf},
+    };
+
+    use super::{backup, restore, Locations};
+
+    /// Fills `dir` with a config file, local info and a snapshot, as a user's machine would have.
+    fn setup(dir: &Path) -> (PathBuf, PathBuf) {
+        let config = dir.join("config").join("config.toml");
+        let local_info = dir.join("config").join("local-info.csv");
+        let snapshot = dir.join("data").join("snapshots").join("2023-06-15");
+        fs::create_dir_all(config.parent().unwrap()).unwrap();
+        fs::create_dir_all(&snapshot).unwrap();
+        fs::write(&config, "units = \"km\"\n").unwrap();
+        fs::write(&local_info, "ident,note\nKSEA,call ahead\n").unwrap();
+        fs::write(snapshot.join("meta.json"), "{}").unwrap();
+        (config, local_info)
+    }
+
+    #[test]
+    fn restores_what_was_backed_up() {
+        let root = std::env::temp_dir().join(format!("adb-backup-{}", std::process::id()));
+        let (from, to) = (root.join("from"), root.join("to"));
+        let (config, local_info) = setup(&from);
+        let archive = root.join("adb.tar.zst");
+
+        let locations = Locations {
+            config: Some(&config),
+            local_info: Some(&local_info),
+            data_dir: &from.join("data"),
+        };
+        assert_eq!(backup(&locations, &archive).unwrap().len(), 3);
+
+        let (config, local_info) = (to.join("config.toml"), to.join("notes.csv"));
+        let data_dir = to.join("data");
+        let locations = Locations {
+            config: Some(&config),
+            local_info: Some(&local_info),
+            data_dir: &data_dir,
+        };
+        let restored = restore(&locations, &archive).unwrap();
+        assert_eq!(
+            restored,
+            [
+                config.clone(),
+                local_info.clone(),
+                data_dir.join("snapshots")
+            ]
+        );
+        assert_eq!(fs::read_to_string(config).unwrap(), "units = \"km\"\n");
+        assert!(fs::read_to_string(local_info).unwrap().contains("KSEA"));
+        assert!(data_dir.join("snapshots/2023-06-15/meta.json").is_file());
+
+        fs::remove_dir_all(&root).unwrap();
+    }
+}
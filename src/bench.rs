@@ -0,0 +1,122 @@
+//! Timings of the operations users notice when they get slower, for `adb bench`.
+
+use std::{
+    fmt,
+    path::Path,
+    time::{Duration, Instant},
+};
+
+use adb::{
+    database::Database,
+    locale::{format_number, tr, Message},
+    model::Airport,
+    waypoint::Waypoint,
+};
+
+/// Identifiers looked up on each run. A miss costs about as much as a hit, so it doesn't matter
+/// whether the data has all of them.
+const IDENTIFIERS: [&str; 5] = ["KSEA", "EGLL", "EDDF", "RJTT", "YSSY"];
+
+const QUERY: &str = "international";
+
+/// Waypoints in the long route measured by the dist timing.
+const ROUTE_LENGTH: usize = 1000;
+
+/// Repeated timings of each operation.
+#[derive(Debug)]
+pub struct Bench {
+    runs: usize,
+    timings: Vec<(Message, Vec<Duration>)>,
+}
+
+impl Bench {
+    pub fn run(data_dir: &Path, runs: usize) -> crate::Result<Self> {
+        // Open once untimed so that building a missing index doesn't count against the first run.
+        let db = Database::initialize(data_dir)?;
+        let route: Vec<Waypoint> = db
+            .scan::<Airport>()
+            .take(ROUTE_LENGTH)
+            .map(Waypoint::from)
+            .collect();
+
+        let mut open = Vec::with_capacity(runs);
+        let mut lookup = Vec::with_capacity(runs);
+        let mut search = Vec::with_capacity(runs);
+        let mut dist = Vec::with_capacity(runs);
+
+        for _ in 0..runs {
+            let start = Instant::now();
+            Database::initialize(data_dir)?;
+            open.push(start.elapsed());
+
+            let start = Instant::now();
+            for identifier in IDENTIFIERS {
+                db.by_identifier(identifier)?;
+            }
+            lookup.push(start.elapsed());
+
+            let start = Instant::now();
+            db.search(QUERY, 25)?;
+            search.push(start.elapsed());
+
+            let start = Instant::now();
+            let meters: f64 = route
+                .windows(2)
+                .map(|pair| pair[0].distance_to(&pair[1]).meters())
+                .sum();
+            dist.push(start.elapsed());
+            std::hint::black_box(meters);
+        }
+
+        Ok(Bench {
+            runs,
+            timings: vec![
+                (Message::BenchOpen, open),
+                (Message::BenchLookup, lookup),
+                (Message::Search, search),
+                (Message::BenchDist, dist),
+            ],
+        })
+    }
+}
+
+impl fmt::Display for Bench {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let ms =
+            |duration: Duration| format!("{}ms", format_number(duration.as_secs_f64() * 1e3, 2));
+
+        writeln!(
+            f,
+            "adb {} ({} {})\n",
+            env!("CARGO_PKG_VERSION"),
+            self.runs,
+            tr(Message::Runs)
+        )?;
+        writeln!(
+            f,
+            "{:<22} {:>10} {:>10} {:>10} {:>10}",
+            "",
+            "min",
+            tr(Message::Median),
+            tr(Message::Mean),
+            "max"
+        )?;
+
+        for (label, timings) in &self.timings {
+            let mut sorted = timings.clone();
+            sorted.sort();
+            let mean = sorted.iter().sum::<Duration>() / sorted.len() as u32;
+            writeln!(
+                f,
+                "{:<22} {:>10} {:>10} {:>10} {:>10}",
+                tr(*label),
+                ms(sorted[0]),
+                ms(sorted[sorted.len() / 2]),
+                ms(mean),
+                ms(sorted[sorted.len() - 1])
+            )?;
+        }
+
+        Ok(())
+    }
+}
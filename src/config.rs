@@ -0,0 +1,165 @@
+use std::{
+    collections::BTreeMap,
+    fs, io,
+    path::{Path, PathBuf},
+};
+
+use serde::Deserialize;
+use toml_edit::{value, Array, DocumentMut, Item, Table};
+
+use adb::{error::Error, model::Country, units::DistanceUnit};
+
+use crate::Backend;
+
+/// Settings read from `config.toml` in the config directory.
+///
+/// Each setting can also be given as a command line flag or an `ADB_*` environment variable.
+/// Flags take precedence over environment variables, which take precedence over the file.
+#[derive(Debug, Default, Deserialize)]
+#[serde(default, rename_all = "kebab-case", deny_unknown_fields)]
+pub struct Config {
+    /// directory holding the index (`--data-dir`, `ADB_DATA_DIR`)
+    pub data_dir: Option<PathBuf>,
+
+    /// where airport data comes from (`--backend`, `ADB_BACKEND`)
+    pub backend: Option<Backend>,
+
+    /// language for human-readable output (`--locale`, `ADB_LOCALE`)
+    pub locale: Option<String>,
+
+    /// whether to page long output (`--pager`, `--no-pager`, `ADB_NO_PAGER`)
+    pub pager: Option<bool>,
+
+    /// maximum expansion of wildcard lookups (`--limit`, `ADB_LIMIT`)
+    pub limit: Option<usize>,
+
+    /// external program to render lookups with (`--renderer`, `ADB_RENDERER`)
+    pub renderer: Option<String>,
+
+    /// CSV file of the user's own notes per airport (`--local-info`, `ADB_LOCAL_INFO`)
+    pub local_info: Option<PathBuf>,
+
+    /// unit distances are shown in (`--units`, `ADB_UNITS`)
+    pub units: Option<DistanceUnit>,
+
+    /// country whose airports win when a code names airports in several countries
+    /// (`--prefer-country`, `ADB_PREFER_COUNTRY`)
+    pub prefer_country: Option<Country>,
+
+    /// whether to list closed airports and show closed runways (`--include-closed`,
+    /// `ADB_INCLUDE_CLOSED`)
+    pub include_closed: Option<bool>,
+
+    /// named searches (`adb search --saved NAME`), saved with `adb search --save NAME`
+    pub searches: BTreeMap<String, SavedSearch>,
+
+    /// named routes (`adb dist --route NAME`), saved with `adb route save NAME`
+    pub routes: BTreeMap<String, Vec<String>>,
+
+    /// named positions as `lat lon`, usable anywhere a waypoint is, e.g. `HOME = "47.5 -122.3"`
+    pub waypoints: BTreeMap<String, String>,
+}
+
+/// A search query and filter saved under a name, so it doesn't have to be retyped.
+#[derive(Clone, Debug, Deserialize)]
+#[serde(rename_all = "kebab-case", deny_unknown_fields)]
+pub struct SavedSearch {
+    pub query: String,
+    #[serde(rename = "where")]
+    pub filter: Option<String>,
+    #[serde(default)]
+    pub phonetic: bool,
+    #[serde(default)]
+    pub fuzzy: bool,
+    #[serde(default)]
+    pub regex: bool,
+    pub country: Option<Country>,
+    #[serde(default)]
+    pub aoe: bool,
+    #[serde(default)]
+    pub lighted: bool,
+    #[serde(default)]
+    pub no_boost: bool,
+}
+
+impl Config {
+    /// Reads the config file, treating a missing file as an empty one.
+    pub fn load(path: &Path) -> crate::Result<Self> {
+        match fs::read_to_string(path) {
+            Ok(text) => {
+                toml::from_str(&text).map_err(|e| Error::Config(path.into(), e.to_string()))
+            }
+            Err(e) if e.kind() == io::ErrorKind::NotFound => Ok(Config::default()),
+            Err(e) => Err(e.into()),
+        }
+    }
+
+    /// Adds or replaces a saved search in the config file.
+    pub fn save_search(path: &Path, name: &str, search: &SavedSearch) -> crate::Result<()> {
+        let mut entry = Table::new();
+        entry.insert("query", value(&search.query));
+        if let Some(filter) = &search.filter {
+            entry.insert("where", value(filter));
+        }
+        if search.phonetic {
+            entry.insert("phonetic", value(true));
+        }
+        if search.fuzzy {
+            entry.insert("fuzzy", value(true));
+        }
+        if search.regex {
+            entry.insert("regex", value(true));
+        }
+        if let Some(country) = &search.country {
+            entry.insert("country", value(country.as_str()));
+        }
+        if search.aoe {
+            entry.insert("aoe", value(true));
+        }
+        if search.lighted {
+            entry.insert("lighted", value(true));
+        }
+        if search.no_boost {
+            entry.insert("no-boost", value(true));
+        }
+        save_entry(path, "searches", name, Item::Table(entry))
+    }
+
+    /// Adds or replaces a named route in the config file.
+    pub fn save_route(path: &Path, name: &str, waypoints: &[String]) -> crate::Result<()> {
+        let waypoints: Array = waypoints.iter().collect();
+        save_entry(path, "routes", name, value(waypoints))
+    }
+}
+
+/// Sets `table.name` in the config file.
+///
+/// The file is edited in place rather than rewritten, so comments and the order of existing
+/// settings survive.
+fn save_entry(path: &Path, table: &str, name: &str, item: Item) -> crate::Result<()> {
+    let text = match fs::read_to_string(path) {
+        Ok(text) => text,
+        Err(e) if e.kind() == io::ErrorKind::NotFound => String::new(),
+        Err(e) => return Err(e.into()),
+    };
+    let mut document: DocumentMut = text
+        .parse()
+        .map_err(|e: toml_edit::TomlError| Error::Config(path.into(), e.to_string()))?;
+
+    let entries = document
+        .entry(table)
+        .or_insert_with(|| {
+            let mut table = Table::new();
+            table.set_implicit(true);
+            Item::Table(table)
+        })
+        .as_table_mut()
+        .ok_or_else(|| Error::Config(path.into(), format!("`{table}` must be a table")))?;
+    entries.insert(name, item);
+
+    if let Some(parent) = path.parent() {
+        fs::create_dir_all(parent)?;
+    }
+    fs::write(path, document.to_string())?;
+    Ok(())
+}
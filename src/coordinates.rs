@@ -0,0 +1,236 @@
+//! A table of every airport's position and elevation, keyed by identifier, for jobs that look up
+//! thousands of airports only to measure between them.
+//!
+//! The table is a file of fixed-size records sorted by identifier, saved beside the index and
+//! mapped into memory, so a lookup is a binary search over the file rather than a trip through the
+//! index and a JSON parse. Like the spatial index, it's stamped with the segments it was built
+//! from and built again when they change.
+
+use std::{cmp::Ordering, fs::File, io, ops::Deref, path::Path};
+
+use memmap2::Mmap;
+
+use crate::{
+    model::{Coords, Country},
+    units::Feet,
+};
+
+/// Written ahead of the stamp, so that a table saved in an older layout is built again rather than
+/// misread.
+const FORMAT: &str = "2";
+
+/// Bytes given to an identifier, padded with zeros. Identifiers are at most seven characters in
+/// the published data; any longer are left out and found through the index instead.
+const IDENT_LEN: usize = 12;
+
+/// Bytes per record: identifier, latitude, longitude, elevation and country.
+const RECORD: usize = IDENT_LEN + 8 + 8 + 4 + 2;
+
+/// Stored in place of an elevation for airports that haven't published one.
+const NO_ELEVATION: i32 = i32::MIN;
+
+/// Where an airport is, as read from the table.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Position {
+    pub coords: Coords,
+    pub elevation_ft: Option<Feet>,
+    pub country: Country,
+}
+
+pub struct CoordinateTable {
+    bytes: Bytes,
+
+    /// where the records start, just past the stamp
+    start: usize,
+}
+
+enum Bytes {
+    Mapped(Mmap),
+
+    /// a table that couldn't be saved, as when the index directory is read-only
+    Owned(Vec<u8>),
+}
+
+impl Deref for Bytes {
+    type Target = [u8];
+
+    fn deref(&self) -> &[u8] {
+        match self {
+            Bytes::Mapped(map) => map,
+            Bytes::Owned(bytes) => bytes,
+        }
+    }
+}
+
+impl CoordinateTable {
+    /// Maps a table saved for the segments `stamp` names, or `None` if there isn't one.
+    pub(crate) fn open(path: &Path, stamp: &str) -> Option<Self> {
+        let file = File::open(path).ok()?;
+
+        // Safety: the file is only ever replaced whole, never written in place, and a table whose
+        // file is replaced under it keeps the old contents mapped.
+        let map = unsafe { Mmap::map(&file) }.ok()?;
+        Self::from_bytes(Bytes::Mapped(map), stamp)
+    }
+
+    /// Builds a table from airports' identifiers and positions and saves it to `path`, mapping the
+    /// saved file, or holding the table in memory if it can't be saved.
+    pub(crate) fn build(
+        path: &Path,
+        stamp: &str,
+        positions: impl IntoIterator<Item = (String, Position)>,
+    ) -> Self {
+        let bytes = encode(stamp, positions);
+        if save(path, &bytes).is_ok() {
+            if let Some(table) = Self::open(path, stamp) {
+                return table;
+            }
+        }
+        Self::from_bytes(Bytes::Owned(bytes), stamp).expect("a table just encoded has its stamp")
+    }
+
+    fn from_bytes(bytes: Bytes, stamp: &str) -> Option<Self> {
+        let newline = bytes.iter().position(|&b| b == b'\n')?;
+        if bytes[..newline] != *header(stamp).as_bytes()
+            || !(bytes.len() - newline - 1).is_multiple_of(RECORD)
+        {
+            return None;
+        }
+        Some(Self {
+            bytes,
+            start: newline + 1,
+        })
+    }
+
+    /// Finds an airport by identifier, ignoring case.
+    pub fn get(&self, identifier: &str) -> Option<Position> {
+        let key = key(identifier)?;
+        let records = &self.bytes[self.start..];
+        let count = records.len() / RECORD;
+
+        let (mut low, mut high) = (0, count);
+        while low < high {
+            let mid = (low + high) / 2;
+            let record = &records[mid * RECORD..(mid + 1) * RECORD];
+            match record[..IDENT_LEN].cmp(&key) {
+                Ordering::Less => low = mid + 1,
+                Ordering::Greater => high = mid,
+                Ordering::Equal => return Some(decode(record)),
+            }
+        }
+        None
+    }
+
+    pub fn len(&self) -> usize {
+        (self.bytes.len() - self.start) / RECORD
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+}
+
+/// An identifier as stored: uppercased and padded, or `None` if it's too long to store.
+fn key(identifier: &str) -> Option<[u8; IDENT_LEN]> {
+    let identifier = identifier.trim().to_ascii_uppercase();
+    if identifier.len() > IDENT_LEN {
+        return None;
+    }
+    let mut key = [0; IDENT_LEN];
+    key[..identifier.len()].copy_from_slice(identifier.as_bytes());
+    Some(key)
+}
+
+/// The first line of a table: its format and the segments it's for.
+fn header(stamp: &str) -> String {
+    format!("{FORMAT} {stamp}")
+}
+
+fn encode(stamp: &str, positions: impl IntoIterator<Item = (String, Position)>) -> Vec<u8> {
+    let mut records: Vec<_> = positions
+        .into_iter()
+        .filter_map(|(ident, position)| Some((key(&ident)?, position)))
+        .collect();
+    records.sort_by_key(|record| record.0);
+    records.dedup_by(|a, b| a.0 == b.0);
+
+    let header = header(stamp);
+    let mut bytes = Vec::with_capacity(header.len() + 1 + records.len() * RECORD);
+    bytes.extend_from_slice(header.as_bytes());
+    bytes.push(b'\n');
+    for (key, position) in records {
+        bytes.extend_from_slice(&key);
+        bytes.extend_from_slice(&position.coords.latitude.to_le_bytes());
+        bytes.extend_from_slice(&position.coords.longitude.to_le_bytes());
+        let elevation = position.elevation_ft.map_or(NO_ELEVATION, |feet| feet.0);
+        bytes.extend_from_slice(&elevation.to_le_bytes());
+        bytes.extend_from_slice(position.country.as_str().as_bytes());
+    }
+    bytes
+}
+
+fn decode(record: &[u8]) -> Position {
+    let f64_at = |at: usize| f64::from_le_bytes(record[at..at + 8].try_into().unwrap());
+    let elevation = i32::from_le_bytes(record[IDENT_LEN + 16..IDENT_LEN + 20].try_into().unwrap());
+    // Only ever written from a country's two letters.
+    let country = std::str::from_utf8(&record[IDENT_LEN + 20..RECORD])
+        .unwrap()
+        .parse()
+        .unwrap();
+    Position {
+        coords: Coords {
+            latitude: f64_at(IDENT_LEN),
+            longitude: f64_at(IDENT_LEN + 8),
+        },
+        elevation_ft: (elevation != NO_ELEVATION).then_some(Feet(elevation)),
+        country,
+    }
+}
+
+/// Writes the table beside its final name and moves it into place, so that a table mapped by
+/// another run is never written under it.
+fn save(path: &Path, bytes: &[u8]) -> io::Result<()> {
+    let temporary = path.with_extension("tmp");
+    std::fs::write(&temporary, bytes)?;
+    std::fs::rename(&temporary, path)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{encode, Bytes, CoordinateTable, Position};
+    use crate::{model::Coords, units::Feet};
+
+    #[test]
+    fn finds_positions_by_identifier() {
+        let position = |latitude, longitude, elevation_ft, country: &str| Position {
+            coords: Coords {
+                latitude,
+                longitude,
+            },
+            elevation_ft,
+            country: country.parse().unwrap(),
+        };
+        let seattle = position(47.449, -122.309, Some(Feet(433)), "US");
+        let positions = [
+            ("KSEA".to_string(), seattle),
+            (
+                "EDDF".to_string(),
+                position(50.033, 8.571, Some(Feet(364)), "DE"),
+            ),
+            ("K0S9".to_string(), position(48.054, -122.810, None, "US")),
+            ("TOO-LONG-IDENT".to_string(), position(0.0, 0.0, None, "US")),
+        ];
+
+        let table =
+            CoordinateTable::from_bytes(Bytes::Owned(encode("stamp", positions)), "stamp").unwrap();
+        assert_eq!(table.len(), 3);
+        assert_eq!(table.get("ksea"), Some(seattle));
+        assert_eq!(table.get("K0S9").unwrap().elevation_ft, None);
+        assert_eq!(table.get("EDDF").unwrap().country.as_str(), "DE");
+        assert_eq!(table.get("KPAE"), None);
+        assert_eq!(table.get("TOO-LONG-IDENT"), None);
+
+        let bytes = encode("stamp", []);
+        assert!(CoordinateTable::from_bytes(Bytes::Owned(bytes), "other").is_none());
+    }
+}
@@ -0,0 +1,152 @@
+//! The airport data compiled into adb, and where adb keeps its files.
+//!
+//! Every backend is built from this data: the tantivy index, the SQLite file and the in-memory
+//! [`Embedded`](crate::embedded::Embedded) data alike.
+
+use std::io;
+
+use csv::Reader;
+use directories::ProjectDirs;
+use hashbrown::HashMap;
+
+use crate::{
+    error::Error,
+    locale::{tr, Message},
+    model::{Airport, AirportKind, AirportTemplate, Runway, RunwayTemplate},
+};
+
+pub(crate) static AIRPORTS: &str = include_str!("../resource/airports.csv");
+pub(crate) static RUNWAYS: &str = include_str!("../resource/runways.csv");
+
+/// Where adb keeps its index, cache and settings by default, or an error if there's no home
+/// directory to put them in, as in some containers and CI environments.
+pub fn project_dirs() -> crate::Result<ProjectDirs> {
+    ProjectDirs::from("org", "Hack Commons", "airdatabase").ok_or(Error::NoHomeDirectory)
+}
+
+/// The airports compiled into adb, with their runways and keywords, as the index is built from
+/// them.
+pub(crate) fn embedded_airports() -> impl Iterator<Item = (Airport, String)> {
+    load_airports(AIRPORTS, RUNWAYS)
+}
+
+/// The text searched for an airport: its identifier, name, town, region, country and keywords.
+#[cfg(any(feature = "search", feature = "sqlite"))]
+pub(crate) fn description(airport: &Airport, keywords: &str) -> String {
+    let municipality = airport.municipality.as_deref().unwrap_or_default();
+    format!(
+        "{} {}, {municipality}, {}, {} {keywords}",
+        airport.ident, airport.name, airport.iso_region, airport.iso_country
+    )
+}
+
+/// Reads airports and their runways from the CSV sources, one airport per identifier, each with
+/// its upstream keywords.
+pub(crate) fn load_airports(
+    airports: &str,
+    runways: &str,
+) -> impl Iterator<Item = (Airport, String)> {
+    let mut source = airports.as_bytes();
+    let mut reader = Reader::from_reader(&mut source);
+    let mut runways = load_runways(runways).unwrap();
+    let templates = reader
+        .deserialize::<AirportTemplate>()
+        .map(Result::unwrap)
+        .collect();
+
+    dedup(templates).into_iter().map(move |template| {
+        let keywords = template.keywords().to_string();
+        let mut airport = Airport::from_template(template);
+
+        // For my next trick, when available, I'm going to pull runways for each airport.
+        // ...Since I'm doing it this way, ICAO identifiers better be unique; `dedup` sees to that.
+        if let Some(runways) = runways.remove(&airport.ident) {
+            airport.runways = runways;
+        }
+        (airport, keywords)
+    })
+}
+
+/// Sorts airports by identifier and keeps one per identifier, warning about any others.
+///
+/// Upstream identifiers are meant to be unique, and lookups and runway matching both rely on it.
+/// Of two airports with the same identifier, the more significant kind wins (closed airports
+/// last), and then whichever comes first in the data.
+fn dedup(mut templates: Vec<AirportTemplate>) -> Vec<AirportTemplate> {
+    // A stable sort, so that ties keep their order in the data.
+    templates.sort_by(|a, b| {
+        a.ident()
+            .cmp(b.ident())
+            .then(precedence(a.kind()).cmp(&precedence(b.kind())))
+    });
+
+    let mut kept: Vec<AirportTemplate> = Vec::with_capacity(templates.len());
+    for template in templates {
+        match kept.last() {
+            Some(winner) if winner.ident() == template.ident() => eprintln!(
+                "{}: {}; {} {:?}, {} {:?}",
+                template.ident(),
+                tr(Message::DuplicateIdentifier),
+                tr(Message::Kept),
+                winner.name(),
+                tr(Message::Dropped),
+                template.name()
+            ),
+            _ => kept.push(template),
+        }
+    }
+    kept
+}
+
+/// How significant a kind of airport is, most significant first.
+pub(crate) fn precedence(kind: AirportKind) -> u8 {
+    match kind {
+        AirportKind::LargeAirport => 0,
+        AirportKind::MediumAirport => 1,
+        AirportKind::SmallAirport => 2,
+        AirportKind::SeaplaneBase => 3,
+        AirportKind::Heliport => 4,
+        AirportKind::Balloonport => 5,
+        AirportKind::Closed => 6,
+    }
+}
+
+fn load_runways(runways: &str) -> io::Result<HashMap<String, Vec<Runway>>> {
+    let mut source = runways.as_bytes();
+    let mut reader = Reader::from_reader(&mut source);
+    let mut map: HashMap<_, Vec<_>> = HashMap::new();
+
+    for runway in reader.deserialize::<RunwayTemplate>() {
+        let runway: Runway = runway?.into();
+        map.entry(runway.airport.clone()).or_default().push(runway);
+    }
+
+    Ok(map)
+}
+
+#[cfg(test)]
+mod tests {
+    use csv::Reader;
+
+    use crate::model::AirportTemplate;
+
+    #[test]
+    fn keeps_one_airport_per_identifier() {
+        let csv = r#""ident","type","name","latitude_deg","longitude_deg","elevation_ft","continent","iso_country","iso_region","municipality","gps_code","iata_code","local_code","keywords"
+"KTWO","closed","Old Field",47.0,-122.0,,"NA","US","US-WA","","","","",""
+"KONE","small_airport","One Field",47.0,-122.0,,"NA","US","US-WA","","","","",""
+"KTWO","small_airport","New Field",47.0,-122.0,,"NA","US","US-WA","","","","",""
+"KTWO","heliport","Hospital Heliport",47.0,-122.0,,"NA","US","US-WA","","","","",""
+"#;
+        let templates = Reader::from_reader(csv.as_bytes())
+            .deserialize::<AirportTemplate>()
+            .map(Result::unwrap)
+            .collect();
+
+        let kept: Vec<_> = super::dedup(templates)
+            .into_iter()
+            .map(|template| template.name().to_string())
+            .collect();
+        assert_eq!(kept, ["One Field", "New Field"]);
+    }
+}
@@ -1,61 +1,1045 @@
+use std::{
+    ops::Bound,
+    path::{Path, PathBuf},
+    sync::{Arc, Mutex},
+    vec,
+};
+
+use hashbrown::HashSet;
+use serde::{de::DeserializeOwned, Deserialize};
 use tantivy::{
-    collector::TopDocs,
-    query::{Query, QueryParser},
-    schema::Value,
-    Index, IndexReader, TantivyDocument,
+    collector::{Collector, DocSetCollector, TopDocs},
+    query::{
+        AllQuery, BooleanQuery, FuzzyTermQuery, Occur, Query, QueryParser, RangeQuery, RegexQuery,
+        TermQuery,
+    },
+    schema::{Facet, Field, IndexRecordOption, Value},
+    DocAddress, DocId, Index, IndexReader, IndexWriter, Score, Searcher, SegmentReader,
+    TantivyDocument, Term,
 };
 
 use crate::{
-    model::Airport,
+    coordinates::{CoordinateTable, Position},
+    data,
+    geo::BoundingBox,
+    lookup::{self, Code, Lookup},
+    model::{Airport, AirportKind, AirportSummary, Coords, Country},
+    phonetic,
+    query::AirportQuery,
     search::{self, Fields},
+    spatial::SpatialIndex,
+    units::{Feet, Meters},
 };
 
+/// Words shorter than this have to be spelled right in a fuzzy search; one letter off in a short
+/// word matches too many others.
+const FUZZY_MIN_LEN: usize = 5;
+
+/// Results in the first page fetched by [`Database::search_iter`]. Each page after is twice the
+/// size of the one before, since a caller that's come back for more is likely to want a lot more.
+const FIRST_PAGE: usize = 100;
+
+/// The file beside the index holding its spatial index. It goes when the index is rebuilt, along
+/// with the rest of the index's files, and is built again on first use.
+const SPATIAL_INDEX: &str = "spatial.bin";
+
+/// The file beside the index holding its [`CoordinateTable`], which comes and goes the same way.
+const COORDINATE_TABLE: &str = "coordinates.bin";
+
 pub struct Database {
     index: Index,
     reader: IndexReader,
     fields: Fields,
+
+    /// parsers for free text and phonetic queries, built once rather than per query
+    parser: QueryParser,
+    phonetic_parser: QueryParser,
+
+    /// whether free text search ranks large and scheduled-service airports higher
+    boost: bool,
+
+    /// the country whose airports win codes shared across countries
+    prefer_country: Option<Country>,
+
+    /// the index directory
+    path: PathBuf,
+
+    /// the spatial index last used, and the segments it's for
+    spatial: Mutex<Option<(String, Arc<SpatialIndex>)>>,
+
+    /// the coordinate table last used, and the segments it's for
+    coordinates: Mutex<Option<(String, Arc<CoordinateTable>)>>,
 }
 
 impl Database {
-    pub fn initialize() -> tantivy::Result<Self> {
-        let (index, fields) = search::initialize(false)?;
-        let reader = index.reader()?;
+    pub fn initialize(path: &Path) -> crate::Result<Self> {
+        let (index, fields) = search::initialize(path, false)?;
+        Self::open(index, fields, path)
+    }
+
+    /// Opens a snapshot kept by `adb update --keep`; see [`search::open_snapshot`].
+    pub fn open_snapshot(path: &Path) -> crate::Result<Self> {
+        let (index, fields) = search::open_snapshot(path)?;
+        Self::open(index, fields, path)
+    }
 
+    /// Opens the index in `path` without building it; see [`search::open_existing`].
+    pub fn open_existing(path: &Path) -> crate::Result<Option<Self>> {
+        match search::open_existing(path)? {
+            Some((index, fields)) => Self::open(index, fields, path).map(Some),
+            None => Ok(None),
+        }
+    }
+
+    fn open(index: Index, fields: Fields, path: &Path) -> crate::Result<Self> {
+        let reader = index.reader()?;
+        let parser = QueryParser::for_index(&index, vec![fields.description]);
+        let phonetic_parser = QueryParser::for_index(&index, vec![fields.phonetic]);
         Ok(Self {
             index,
             reader,
             fields,
+            parser,
+            phonetic_parser,
+            boost: true,
+            prefer_country: None,
+            path: path.into(),
+            spatial: Mutex::new(None),
+            coordinates: Mutex::new(None),
         })
     }
 
+    /// Sets whether free text search ranks large and scheduled-service airports above others that
+    /// match as well. It does unless told otherwise.
+    pub fn set_boost(&mut self, boost: bool) {
+        self.boost = boost;
+    }
+
+    /// Sets the country whose airports win when a code looked up by
+    /// [`by_identifier`](Self::by_identifier) names airports in more than one.
+    pub fn set_prefer_country(&mut self, country: Option<Country>) {
+        self.prefer_country = country;
+    }
+
+    /// Finds an airport by identifier, or failing that by one of its other codes; see
+    /// [`resolve`](Self::resolve).
     pub fn by_identifier(&self, identifier: &str) -> tantivy::Result<Option<Airport>> {
-        let query = QueryParser::for_index(&self.index, vec![self.fields.identifier])
-            .parse_query(identifier)?;
+        self.resolve(&self.reader.searcher(), &identifier.to_lowercase())
+    }
 
-        Ok(self.materialize_query(&query, 1)?.into_iter().next())
+    /// Where the airport [`by_identifier`](Self::by_identifier) would find is, read from the
+    /// [`coordinate_table`](Self::coordinate_table) when the code is an open airport's identifier
+    /// in the preferred country, and only otherwise looked up whole.
+    pub fn coordinates(&self, identifier: &str) -> tantivy::Result<Option<Coords>> {
+        let preferred = |country| {
+            self.prefer_country
+                .is_none_or(|preferred| preferred == country)
+        };
+        match self.coordinate_table().get(identifier) {
+            Some(position) if preferred(position.country) => Ok(Some(position.coords)),
+            _ => Ok(self
+                .by_identifier(identifier)?
+                .map(|airport| airport.coordinates)),
+        }
     }
 
-    pub fn search(&self, query: &str) -> tantivy::Result<Vec<Airport>> {
-        let query = QueryParser::for_index(&self.index, vec![self.fields.description])
-            .parse_query(query)?;
+    /// Finds an airport by its IATA code, as `AUS` for Austin. Codes are sometimes shared with
+    /// closed airports or heliports, so the most significant airport holding it wins.
+    pub fn by_iata(&self, code: &str) -> tantivy::Result<Option<Airport>> {
+        const CANDIDATES: usize = 10;
 
-        self.materialize_query(&query, 25)
+        let term = Term::from_field_text(self.fields.iata, &code.to_lowercase());
+        let query = TermQuery::new(term, IndexRecordOption::Basic);
+        let candidates = self.materialize_query(&query, CANDIDATES, false)?;
+        Ok(candidates
+            .into_iter()
+            .min_by_key(|airport| data::precedence(airport.kind)))
     }
 
-    fn materialize_query(&self, query: &dyn Query, limit: usize) -> tantivy::Result<Vec<Airport>> {
+    /// Looks up a lowercased code the way every backend does; see [`lookup::resolve`].
+    fn resolve(&self, searcher: &Searcher, code: &str) -> tantivy::Result<Option<Airport>> {
+        // Codes other than identifiers can be shared, so each field can match several airports.
+        const CANDIDATES: usize = 10;
+
+        lookup::resolve(code, self.prefer_country, |kind| {
+            let field = match kind {
+                Code::Identifier => self.fields.identifier,
+                Code::Gps => self.fields.gps_code,
+                Code::Local => self.fields.local_code,
+                Code::Iata => self.fields.iata,
+            };
+            // Codes are indexed whole and lowercased, so an exact lookup is a single term.
+            let term = Term::from_field_text(field, code);
+            let query = TermQuery::new(term, IndexRecordOption::Basic);
+            Ok(searcher
+                .search(&query, &TopDocs::with_limit(CANDIDATES))?
+                .into_iter()
+                .filter_map(|(_, address)| self.materialize::<Airport>(searcher, address))
+                .collect())
+        })
+    }
+
+    /// Looks up many identifiers at once, giving for each, in order, its airport or `None`.
+    ///
+    /// Every lookup reads from the same searcher and lowercases into the same buffer, which adds
+    /// up over a batch of thousands.
+    pub fn lookup_many<S: AsRef<str>>(
+        &self,
+        identifiers: &[S],
+    ) -> tantivy::Result<Vec<Option<Airport>>> {
         let searcher = self.reader.searcher();
-        let candidates: Vec<_> = searcher
-            .search(query, &TopDocs::with_limit(limit))?
-            .into_iter()
-            .filter_map(|(_, address)| searcher.doc(address).ok())
-            .filter_map(|document: TantivyDocument| {
-                document
-                    .get_first(self.fields.object)?
-                    .as_str()
-                    .and_then(|s| serde_json::from_str(s).ok())
+        let mut lowercased = String::new();
+
+        identifiers
+            .iter()
+            .map(|identifier| {
+                lowercased.clear();
+                lowercased.extend(identifier.as_ref().chars().flat_map(char::to_lowercase));
+                self.resolve(&searcher, &lowercased)
+            })
+            .collect()
+    }
+
+    /// Finds airports whose identifier matches a glob pattern such as `K0*` or `EG??`.
+    ///
+    /// At most `limit` of the airports `keep` accepts are returned, the first by identifier.
+    pub fn by_pattern(
+        &self,
+        pattern: &str,
+        limit: usize,
+        keep: impl Fn(&Airport) -> bool,
+    ) -> tantivy::Result<Vec<Airport>> {
+        let mut regex = String::with_capacity(pattern.len() * 2);
+        for c in pattern.to_lowercase().chars() {
+            match c {
+                '*' => regex.push_str(".*"),
+                '?' => regex.push('.'),
+                c if c.is_alphanumeric() => regex.push(c),
+                c => {
+                    regex.push('\\');
+                    regex.push(c);
+                }
+            }
+        }
+
+        // Every match is read before any are dropped: the first `limit` by score would be an
+        // arbitrary few, not the first by identifier, and some of those might not be kept.
+        let query = RegexQuery::from_pattern(&regex, self.fields.identifier)?;
+        let searcher = self.reader.searcher();
+        let mut airports: Vec<Airport> = searcher
+            .search(&query, &DocSetCollector)?
+            .into_iter()
+            .filter_map(|address| self.materialize(&searcher, address))
+            .filter(|airport| keep(airport))
+            .collect();
+        airports.sort_by(|a, b| a.ident.cmp(&b.ident));
+        airports.truncate(limit);
+        Ok(airports)
+    }
+
+    pub fn search(&self, query: &str, limit: usize) -> tantivy::Result<Vec<Airport>> {
+        let query = self.parser.parse_query(query)?;
+        self.materialize_query(&query, limit, self.boost)
+    }
+
+    /// Like [`search`](Self::search), but hands each match `keep` accepts to `visit` as an
+    /// [`AirportSummary`] borrowed straight from the stored document, so listing results builds
+    /// no [`Airport`]s.
+    pub fn search_summaries(
+        &self,
+        query: &str,
+        limit: usize,
+        keep: impl Fn(&AirportSummary) -> bool,
+        mut visit: impl FnMut(AirportSummary<'_>),
+    ) -> tantivy::Result<()> {
+        let query = self.parser.parse_query(query)?;
+        let searcher = self.reader.searcher();
+        let mut offset = 0;
+        let mut visited = 0;
+
+        while visited < limit {
+            let page = self.top_docs(limit, offset, self.boost);
+            let found = searcher.search(&query, &page)?;
+            offset += found.len();
+
+            for (_, address) in &found {
+                let document: TantivyDocument = searcher.doc(*address)?;
+                let summary = document
+                    .get_first(self.fields.object)
+                    .and_then(|object| object.as_str())
+                    .and_then(|object| serde_json::from_str(object).ok())
+                    .filter(&keep);
+                if let Some(summary) = summary {
+                    visit(summary);
+                    visited += 1;
+                    if visited == limit {
+                        break;
+                    }
+                }
+            }
+
+            if found.len() < limit {
+                break;
+            }
+        }
+        Ok(())
+    }
+
+    /// Like [`search_iter`](Self::search_iter), but only for airports in one country, which the
+    /// index narrows to before ranking rather than after.
+    pub fn search_country(
+        &self,
+        query: &str,
+        country: &Country,
+    ) -> tantivy::Result<impl Iterator<Item = tantivy::Result<Airport>> + '_> {
+        self.query(&AirportQuery::new().text(query).country(country.as_str()))
+    }
+
+    /// Like [`search`](Self::search), but yields matches best first for as long as the caller
+    /// keeps asking. Results are fetched a page at a time, so a query matching every airport
+    /// never has them all in memory at once.
+    pub fn search_iter(&self, query: &str) -> tantivy::Result<SearchIter<'_>> {
+        let query = self.parser.parse_query(query)?;
+
+        Ok(self.stream(query, self.boost))
+    }
+
+    /// Like [`search_iter`](Self::search_iter), but forgiving of typos: each word of five letters
+    /// or more also matches words one edit away, a swap of two letters counting as one, so
+    /// "Heathorw" finds Heathrow. Exact matches still rank first.
+    pub fn search_fuzzy(&self, query: &str) -> tantivy::Result<SearchIter<'_>> {
+        let mut clauses: Vec<(Occur, Box<dyn Query>)> =
+            vec![(Occur::Should, self.parser.parse_query(query)?)];
+
+        let mut analyzer = self.index.tokenizer_for_field(self.fields.description)?;
+        let mut words = analyzer.token_stream(query);
+        while let Some(word) = words.next() {
+            if word.text.chars().count() < FUZZY_MIN_LEN {
+                continue;
+            }
+            let term = Term::from_field_text(self.fields.description, &word.text);
+            clauses.push((Occur::Should, Box::new(FuzzyTermQuery::new(term, 1, true))));
+        }
+
+        Ok(self.stream(Box::new(BooleanQuery::new(clauses)), self.boost))
+    }
+
+    /// Finds airports whose name or town matches a regular expression, ignoring case, such as
+    /// `^Mount .*` for every name beginning with "Mount".
+    ///
+    /// A pattern finds names containing a match, as a regex would anywhere else; anchor it with
+    /// `^` or `$` to match the start or end of the name.
+    pub fn search_regex(&self, pattern: &str) -> tantivy::Result<SearchIter<'_>> {
+        let pattern = whole_term(pattern);
+        let clauses: Vec<(Occur, Box<dyn Query>)> = vec![
+            (
+                Occur::Should,
+                Box::new(RegexQuery::from_pattern(&pattern, self.fields.name)?),
+            ),
+            (
+                Occur::Should,
+                Box::new(RegexQuery::from_pattern(
+                    &pattern,
+                    self.fields.municipality,
+                )?),
+            ),
+        ];
+        Ok(self.stream(Box::new(BooleanQuery::new(clauses)), self.boost))
+    }
+
+    /// Runs a typed query, best matches first when it has text, nearest first when it's only
+    /// `near`, and in index order otherwise.
+    pub fn query<'a>(
+        &'a self,
+        query: &AirportQuery,
+    ) -> tantivy::Result<impl Iterator<Item = tantivy::Result<Airport>> + 'a> {
+        let searcher = self.reader.searcher();
+        let near = match query.near {
+            Some((coords, meters)) => {
+                Some(self.spatial(&searcher)?.within(&coords, Meters(meters)))
+            }
+            None => None,
+        };
+
+        let mut clauses: Vec<(Occur, Box<dyn Query>)> = Vec::new();
+        if let Some(text) = &query.text {
+            clauses.push((Occur::Must, self.parser.parse_query(text)?));
+        }
+
+        // Facets are `/country/region/...`, and a facet term matches everything beneath it.
+        let region = query.region.as_ref().map(|region| {
+            let country = region.split('-').next().unwrap_or_default();
+            format!("/{country}/{region}")
+        });
+        let country = query.country.as_ref().map(|country| format!("/{country}"));
+        for facet in country.into_iter().chain(region) {
+            let term = Term::from_facet(self.fields.facet, &Facet::from(facet.as_str()));
+            let clause = TermQuery::new(term, IndexRecordOption::Basic);
+            clauses.push((Occur::Must, Box::new(clause)));
+        }
+        if let Some(kind) = query.kind {
+            let term = Term::from_field_text(self.fields.kind, kind.as_str());
+            let clause = TermQuery::new(term, IndexRecordOption::Basic);
+            clauses.push((Occur::Must, Box::new(clause)));
+        }
+        if let Some(feet) = query.min_runway_ft {
+            let field = self.field_name(self.fields.longest_runway);
+            let clause =
+                RangeQuery::new_i64_bounds(field, Bound::Included(feet.into()), Bound::Unbounded);
+            clauses.push((Occur::Must, Box::new(clause)));
+        }
+
+        let airports: Box<dyn Iterator<Item = tantivy::Result<Airport>> + 'a> = match near {
+            // Nothing else to ask the index, so the airports in range are the answer.
+            Some(near) if clauses.is_empty() => Box::new(
+                near.into_iter()
+                    .filter_map(move |address| self.materialize(&searcher, address))
+                    .map(Ok),
+            ),
+            near => {
+                let compiled: Box<dyn Query> = if clauses.is_empty() {
+                    Box::new(AllQuery)
+                } else {
+                    Box::new(BooleanQuery::new(clauses))
+                };
+                let boost = self.boost && query.text.is_some();
+                let mut airports = self.stream_with(searcher, compiled, boost);
+                airports.only = near.map(|near| near.into_iter().collect());
+                Box::new(airports)
+            }
+        };
+        Ok(airports)
+    }
+
+    /// Searches airport names and towns by how they sound rather than how they're spelled.
+    ///
+    /// Sound-alike matches are ranked by how closely they're spelled to the query.
+    pub fn search_phonetic(&self, query: &str, limit: usize) -> tantivy::Result<Vec<Airport>> {
+        const CANDIDATES: usize = 1000;
+
+        let codes = phonetic::encode(query);
+        if codes.is_empty() {
+            return Ok(Vec::new());
+        }
+        let parsed = self.phonetic_parser.parse_query(&codes)?;
+
+        let mut candidates: Vec<_> = self
+            .materialize_query(&parsed, CANDIDATES.max(limit), false)?
+            .into_iter()
+            .map(|airport| {
+                let municipality = airport.municipality.as_deref().unwrap_or_default();
+                let text = format!("{} {municipality}", airport.name);
+                (phonetic::similarity(query, &text), airport)
+            })
+            .collect();
+        candidates.sort_by(|a, b| b.0.total_cmp(&a.0));
+        Ok(candidates
+            .into_iter()
+            .take(limit)
+            .map(|(_, airport)| airport)
+            .collect())
+    }
+
+    /// Finds the airports closest to a point, nearest first.
+    ///
+    /// Candidates come from the spatial index, so only the airports actually returned are
+    /// deserialized.
+    pub fn nearest(&self, coords: &Coords, limit: usize) -> tantivy::Result<Vec<Airport>> {
+        let searcher = self.reader.searcher();
+        let spatial = self.spatial(&searcher)?;
+        Ok(spatial
+            .nearest(coords)
+            .take(limit)
+            .filter_map(|address| self.materialize(&searcher, address))
+            .collect())
+    }
+
+    /// Finds every airport within `radius` of a point, nearest first.
+    pub fn within(&self, coords: &Coords, radius: Meters) -> tantivy::Result<Vec<Airport>> {
+        let searcher = self.reader.searcher();
+        let spatial = self.spatial(&searcher)?;
+        Ok(spatial
+            .within(coords, radius)
+            .into_iter()
+            .filter_map(|address| self.materialize(&searcher, address))
+            .collect())
+    }
+
+    /// Finds every airport inside a bounding box, sorted by identifier.
+    pub fn in_bounds(&self, bounds: &BoundingBox) -> tantivy::Result<Vec<Airport>> {
+        let searcher = self.reader.searcher();
+        let spatial = self.spatial(&searcher)?;
+        let mut airports: Vec<Airport> = spatial
+            .in_bounds(bounds)
+            .into_iter()
+            .filter_map(|address| self.materialize(&searcher, address))
+            .collect();
+        airports.sort_by(|a, b| a.ident.cmp(&b.ident));
+        Ok(airports)
+    }
+
+    /// The spatial index for the segments `searcher` reads: the one already loaded, the one saved
+    /// beside the index, or failing those, a new one built from the coordinate fast fields and
+    /// saved for next time if the index directory can be written to.
+    fn spatial(&self, searcher: &Searcher) -> tantivy::Result<Arc<SpatialIndex>> {
+        let stamp = stamp(searcher);
+
+        let mut cached = self.spatial.lock().unwrap();
+        if let Some((cached_stamp, spatial)) = &*cached {
+            if *cached_stamp == stamp {
+                return Ok(spatial.clone());
+            }
+        }
+
+        let path = self.path.join(SPATIAL_INDEX);
+        let spatial = match SpatialIndex::load(&path, &stamp) {
+            Some(spatial) => spatial,
+            None => {
+                let spatial = SpatialIndex::new(self.positions(searcher)?);
+                // A shared index may be read-only, in which case it's built again each run.
+                let _ = spatial.save(&path, &stamp);
+                spatial
+            }
+        };
+
+        let spatial = Arc::new(spatial);
+        *cached = Some((stamp, spatial.clone()));
+        Ok(spatial)
+    }
+
+    /// Every open airport's position and elevation by identifier, for bulk distance jobs that
+    /// would otherwise look up each airport whole. The table is mapped from a file beside the
+    /// index, built on first use like the spatial index.
+    ///
+    /// Closed airports are left out, since a lookup only lands on one when nothing open holds the
+    /// code, which the table can't tell.
+    pub fn coordinate_table(&self) -> Arc<CoordinateTable> {
+        /// Just what the table holds, so that building it doesn't deserialize runways and all.
+        #[derive(Deserialize)]
+        struct Located {
+            ident: String,
+            kind: AirportKind,
+            iso_country: Country,
+            coordinates: Coords,
+            elevation_ft: Option<Feet>,
+        }
+
+        let searcher = self.reader.searcher();
+        let stamp = stamp(&searcher);
+
+        let mut cached = self.coordinates.lock().unwrap();
+        if let Some((cached_stamp, table)) = &*cached {
+            if *cached_stamp == stamp {
+                return table.clone();
+            }
+        }
+
+        let path = self.path.join(COORDINATE_TABLE);
+        let table = CoordinateTable::open(&path, &stamp).unwrap_or_else(|| {
+            let positions = self
+                .scan::<Located>()
+                .filter(|airport| airport.kind != AirportKind::Closed)
+                .map(|airport| {
+                    let position = Position {
+                        coords: airport.coordinates,
+                        elevation_ft: airport.elevation_ft,
+                        country: airport.iso_country,
+                    };
+                    (airport.ident, position)
+                });
+            CoordinateTable::build(&path, &stamp, positions)
+        });
+
+        let table = Arc::new(table);
+        *cached = Some((stamp, table.clone()));
+        table
+    }
+
+    /// Every airport's position and address, read from the coordinate fast fields so that nothing
+    /// is deserialized.
+    fn positions(&self, searcher: &Searcher) -> tantivy::Result<Vec<(Coords, DocAddress)>> {
+        let schema = searcher.schema();
+        let mut positions = Vec::new();
+
+        for (ord, segment) in searcher.segment_readers().iter().enumerate() {
+            let fast_fields = segment.fast_fields();
+            let latitude = fast_fields.f64(schema.get_field_name(self.fields.latitude))?;
+            let longitude = fast_fields.f64(schema.get_field_name(self.fields.longitude))?;
+
+            for doc in segment.doc_ids_alive() {
+                let (Some(latitude), Some(longitude)) = (latitude.first(doc), longitude.first(doc))
+                else {
+                    continue;
+                };
+
+                let coords = Coords {
+                    latitude,
+                    longitude,
+                };
+                positions.push((coords, DocAddress::new(ord as u32, doc)));
+            }
+        }
+
+        Ok(positions)
+    }
+
+    pub fn airport_count(&self) -> u64 {
+        self.reader.searcher().num_docs()
+    }
+
+    pub fn segment_count(&self) -> usize {
+        self.reader.searcher().segment_readers().len()
+    }
+
+    /// Merges all segments into one and removes the files left behind by earlier updates.
+    pub fn compact(&self) -> tantivy::Result<()> {
+        const WRITER_MEMORY: usize = 50_000_000;
+
+        let segments = self.index.searchable_segment_ids()?;
+        let mut writer: IndexWriter = self.index.writer(WRITER_MEMORY)?;
+        if segments.len() > 1 {
+            writer.merge(&segments).wait()?;
+        }
+        writer.garbage_collect_files().wait()?;
+        writer.wait_merging_threads()?;
+        self.reader.reload()
+    }
+
+    /// Visits every document in the index, deserializing each into `T`.
+    ///
+    /// `T` can be a lighter projection of [`Airport`]; unknown fields are skipped.
+    pub fn scan<T: DeserializeOwned>(&self) -> impl Iterator<Item = T> + '_ {
+        let searcher = self.reader.searcher();
+        let addresses: Vec<_> = searcher
+            .segment_readers()
+            .iter()
+            .enumerate()
+            .flat_map(|(ord, segment)| {
+                segment
+                    .doc_ids_alive()
+                    .map(move |doc| DocAddress::new(ord as u32, doc))
+            })
+            .collect();
+
+        addresses
+            .into_iter()
+            .filter_map(move |address| self.materialize(&searcher, address))
+    }
+
+    fn stream(&self, query: Box<dyn Query>, boost: bool) -> SearchIter<'_> {
+        self.stream_with(self.reader.searcher(), query, boost)
+    }
+
+    fn stream_with(
+        &self,
+        searcher: Searcher,
+        query: Box<dyn Query>,
+        boost: bool,
+    ) -> SearchIter<'_> {
+        SearchIter {
+            db: self,
+            searcher,
+            query,
+            boost,
+            only: None,
+            offset: 0,
+            page_size: FIRST_PAGE,
+            page: Vec::new().into_iter(),
+            done: false,
+        }
+    }
+
+    fn field_name(&self, field: Field) -> String {
+        self.index.schema().get_field_name(field).to_string()
+    }
+
+    /// Collects the best `limit` matches after `offset`, with each score scaled by the airport's
+    /// boost if `boost` is set.
+    fn top_docs(
+        &self,
+        limit: usize,
+        offset: usize,
+        boost: bool,
+    ) -> impl Collector<Fruit = Vec<(Score, DocAddress)>> {
+        let field = self.field_name(self.fields.boost);
+        TopDocs::with_limit(limit)
+            .and_offset(offset)
+            .tweak_score(move |segment: &SegmentReader| {
+                let factors = segment.fast_fields().f64(&field).ok().filter(|_| boost);
+                move |doc: DocId, score: Score| match &factors {
+                    Some(factors) => score * factors.first(doc).unwrap_or(1.0) as Score,
+                    None => score,
+                }
             })
+    }
+
+    fn materialize_query(
+        &self,
+        query: &dyn Query,
+        limit: usize,
+        boost: bool,
+    ) -> tantivy::Result<Vec<Airport>> {
+        let searcher = self.reader.searcher();
+        let candidates: Vec<_> = searcher
+            .search(query, &self.top_docs(limit, 0, boost))?
+            .into_iter()
+            .filter_map(|(_, address)| self.materialize(&searcher, address))
             .collect();
 
         Ok(candidates)
     }
+
+    fn materialize<T: DeserializeOwned>(
+        &self,
+        searcher: &Searcher,
+        address: DocAddress,
+    ) -> Option<T> {
+        let document: TantivyDocument = searcher.doc(address).ok()?;
+        document
+            .get_first(self.fields.object)?
+            .as_str()
+            .and_then(|s| serde_json::from_str(s).ok())
+    }
+}
+
+/// Turns a regex as it's usually written, finding a match anywhere unless anchored, into one the
+/// index can run: the index matches whole terms and knows nothing of anchors, so an anchored end
+/// is simply left alone and an unanchored one is padded to match anything. Terms are lowercased,
+/// so the pattern ignores case.
+fn whole_term(pattern: &str) -> String {
+    let (start, pattern) = match pattern.strip_prefix('^') {
+        Some(rest) => ("", rest),
+        None => (".*", pattern),
+    };
+    let (pattern, end) = match pattern.strip_suffix('$') {
+        Some(rest) if !rest.ends_with('\\') => (rest, ""),
+        _ => (pattern, ".*"),
+    };
+    format!("(?i){start}(?:{pattern}){end}")
+}
+
+/// True for the name of a file that belongs to the index in its directory: tantivy's metadata,
+/// lock files and segment files, which are named for their segment's id, and the tables saved
+/// beside them. Anything else, like a config file in a directory shared with settings, isn't.
+pub fn is_index_file(name: &str) -> bool {
+    let is_segment_id = |id: &str| id.len() == 32 && id.bytes().all(|b| b.is_ascii_hexdigit());
+    // A table is written under a temporary name and moved into place.
+    let is_table = |table: &str| {
+        let path = Path::new(name);
+        path == Path::new(table) || path == Path::new(table).with_extension("tmp")
+    };
+
+    matches!(name, "meta.json" | ".managed.json" | ".adb-write-probe")
+        || (name.starts_with(".tantivy-") && name.ends_with(".lock"))
+        || name
+            .split_once('.')
+            .is_some_and(|(id, _)| is_segment_id(id))
+        || is_table(SPATIAL_INDEX)
+        || is_table(COORDINATE_TABLE)
+}
+
+/// Names the segments a searcher reads, so that files built from them can tell when they're stale.
+fn stamp(searcher: &Searcher) -> String {
+    let segments: Vec<_> = searcher
+        .segment_readers()
+        .iter()
+        .map(|segment| segment.segment_id().uuid_string())
+        .collect();
+    segments.join(",")
+}
+
+impl Lookup for Database {
+    fn by_identifier(&self, identifier: &str) -> crate::Result<Option<Airport>> {
+        Ok(Database::by_identifier(self, identifier)?)
+    }
+
+    fn coordinates(&self, identifier: &str) -> crate::Result<Option<Coords>> {
+        Ok(Database::coordinates(self, identifier)?)
+    }
+
+    fn search(&self, query: &str, limit: usize) -> crate::Result<Vec<Airport>> {
+        Ok(Database::search(self, query, limit)?)
+    }
+
+    fn nearest(&self, coords: &Coords, limit: usize) -> crate::Result<Vec<Airport>> {
+        Ok(Database::nearest(self, coords, limit)?)
+    }
+}
+
+/// Search results streamed from [`Database::search_iter`].
+pub struct SearchIter<'a> {
+    db: &'a Database,
+
+    /// held for the life of the iterator so every page comes from the same snapshot of the index
+    searcher: Searcher,
+    query: Box<dyn Query>,
+    boost: bool,
+
+    /// if set, the only documents to yield, as for airports near a point
+    only: Option<HashSet<DocAddress>>,
+    offset: usize,
+    page_size: usize,
+    page: vec::IntoIter<Airport>,
+    done: bool,
+}
+
+impl Iterator for SearchIter<'_> {
+    type Item = tantivy::Result<Airport>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        loop {
+            if let Some(airport) = self.page.next() {
+                return Some(Ok(airport));
+            }
+            if self.done {
+                return None;
+            }
+
+            let collector = self.db.top_docs(self.page_size, self.offset, self.boost);
+            let addresses = match self.searcher.search(&self.query, &collector) {
+                Ok(addresses) => addresses,
+                Err(e) => {
+                    self.done = true;
+                    return Some(Err(e));
+                }
+            };
+
+            self.done = addresses.len() < self.page_size;
+            self.offset += addresses.len();
+            self.page_size *= 2;
+            self.page = addresses
+                .into_iter()
+                .filter(|(_, address)| self.only.as_ref().is_none_or(|only| only.contains(address)))
+                .filter_map(|(_, address)| self.db.materialize(&self.searcher, address))
+                .collect::<Vec<_>>()
+                .into_iter();
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{fs, path::PathBuf};
+
+    use super::{is_index_file, whole_term, Database, SearchIter};
+    use crate::{
+        model::{AirportKind, Coords},
+        query::AirportQuery,
+        search,
+    };
+
+    /// Airports sharing codes the way real ones do: Austin's old airport holds `AUS` as its
+    /// identifier, a strip is known by a local code that isn't its identifier, and two airports in
+    /// different countries share a local code.
+    const AIRPORTS: &str = r#""ident","type","name","latitude_deg","longitude_deg","elevation_ft","continent","iso_country","iso_region","municipality","gps_code","iata_code","local_code","keywords"
+"AUS","closed","Austin Robert Mueller Municipal Airport",30.3,-97.7,632,"NA","US","US-TX","Austin","","","","KAUS, AUS"
+"KAUS","large_airport","Austin Bergstrom International Airport",30.2,-97.66,542,"NA","US","US-TX","Austin","KAUS","AUS","AUS",""
+"EGLL","large_airport","London Heathrow Airport",51.47,-0.46,83,"EU","GB","GB-ENG","London","EGLL","LHR","",""
+"US-0421","small_airport","Fair Weather Field",29.94,-96.04,175,"NA","US","US-TX","Monaville","","","TX42",""
+"US-1001","small_airport","Border Strip",49.0,-122.0,100,"NA","US","US-WA","","","","BW1",""
+"CA-1001","medium_airport","Border Field",49.1,-122.0,100,"NA","CA","CA-BC","","","","BW1",""
+"#;
+    const RUNWAYS: &str = r#""id","airport_ref","airport_ident","length_ft","width_ft","surface","lighted","closed","le_ident","le_latitude_deg","le_longitude_deg","le_elevation_ft","le_heading_degT","le_displaced_threshold_ft","he_ident","he_latitude_deg","he_longitude_deg","he_elevation_ft","he_heading_degT","he_displaced_threshold_ft"
+1,1,"KAUS",12250,150,"CON",1,0,"18R",,,,,,"36L",,,,,
+2,2,"US-0421",2000,60,"TURF",0,0,"17",,,,,,"35",,,,,
+3,3,"US-1001",9000,100,"ASPH",0,1,"09",,,,,,"27",,,,,
+"#;
+
+    /// Builds an index of the fixture airports in a directory of its own, named for `test`.
+    fn fixture(test: &str) -> (Database, PathBuf) {
+        let path = std::env::temp_dir().join(format!("adb-{test}-{}", std::process::id()));
+        let (index, fields) =
+            search::initialize_with_source(AIRPORTS, RUNWAYS, &path, true).unwrap();
+        (Database::open(index, fields, &path).unwrap(), path)
+    }
+
+    /// The identifiers of the airports found by a search, sorted.
+    fn idents(results: tantivy::Result<SearchIter<'_>>) -> Vec<String> {
+        let mut idents: Vec<_> = results
+            .unwrap()
+            .map(|airport| airport.unwrap().ident)
+            .collect();
+        idents.sort();
+        idents
+    }
+
+    #[test]
+    fn resolves_codes_other_than_identifiers() {
+        let (mut db, path) = fixture("resolve");
+
+        let ident =
+            |db: &Database, code| db.by_identifier(code).unwrap().map(|airport| airport.ident);
+        assert_eq!(ident(&db, "AUS").as_deref(), Some("KAUS"));
+        assert_eq!(ident(&db, "lhr").as_deref(), Some("EGLL"));
+        assert_eq!(ident(&db, "TX42").as_deref(), Some("US-0421"));
+        assert_eq!(ident(&db, "BW1").as_deref(), Some("CA-1001"));
+        assert_eq!(ident(&db, "XXX"), None);
+
+        db.set_prefer_country(Some("US".parse().unwrap()));
+        assert_eq!(ident(&db, "BW1").as_deref(), Some("US-1001"));
+        assert_eq!(ident(&db, "AUS").as_deref(), Some("KAUS"));
+
+        drop(db);
+        fs::remove_dir_all(&path).unwrap();
+    }
+
+    #[test]
+    fn finds_positions_where_lookups_do() {
+        let (mut db, path) = fixture("coordinates");
+
+        // Closed airports aren't in the table, so `AUS` is left to the lookup, which finds KAUS.
+        let table = db.coordinate_table();
+        assert_eq!(table.len(), 5);
+        assert!(table.get("AUS").is_none());
+
+        let codes = ["AUS", "KAUS", "lhr", "TX42", "BW1", "CA-1001", "XXX"];
+        for prefer_country in [None, Some("US".parse().unwrap())] {
+            db.set_prefer_country(prefer_country);
+            for code in codes {
+                let airport = db.by_identifier(code).unwrap();
+                assert_eq!(
+                    db.coordinates(code).unwrap(),
+                    airport.map(|airport| airport.coordinates),
+                    "{code}"
+                );
+            }
+        }
+
+        drop((db, table));
+        fs::remove_dir_all(&path).unwrap();
+    }
+
+    #[test]
+    fn answers_typed_queries_from_the_index() {
+        let (db, path) = fixture("query");
+        let idents = |query: AirportQuery| {
+            let mut idents: Vec<_> = db
+                .query(&query)
+                .unwrap()
+                .map(|airport| airport.unwrap().ident)
+                .collect();
+            idents.sort();
+            idents
+        };
+        let austin = Coords {
+            latitude: 30.2,
+            longitude: -97.66,
+        };
+
+        let small = AirportQuery::new().kind(AirportKind::SmallAirport);
+        assert_eq!(idents(small.clone()), ["US-0421", "US-1001"]);
+        // The strip's only long runway is closed.
+        assert_eq!(idents(AirportQuery::new().min_runway_ft(5000)), ["KAUS"]);
+        assert_eq!(
+            idents(AirportQuery::new().near(austin, 20.0)),
+            ["AUS", "KAUS"]
+        );
+        assert_eq!(idents(small.near(austin, 200.0)), ["US-0421"]);
+
+        // With nothing else to ask, airports near a point come nearest first.
+        let nearest: Vec<_> = db
+            .query(&AirportQuery::new().near(austin, 200.0))
+            .unwrap()
+            .map(|airport| airport.unwrap().ident)
+            .collect();
+        assert_eq!(nearest, ["KAUS", "AUS", "US-0421"]);
+
+        drop(db);
+        fs::remove_dir_all(&path).unwrap();
+    }
+
+    #[test]
+    fn expands_patterns_in_identifier_order() {
+        let (db, path) = fixture("pattern");
+        let idents = |pattern, limit, include_closed: bool| {
+            db.by_pattern(pattern, limit, |airport| {
+                include_closed || !airport.is_closed()
+            })
+            .unwrap()
+            .into_iter()
+            .map(|airport| airport.ident)
+            .collect::<Vec<_>>()
+        };
+
+        assert_eq!(idents("us-*", 1, true), ["US-0421"]);
+        assert_eq!(idents("*-1001", 5, true), ["CA-1001", "US-1001"]);
+        assert_eq!(idents("?AUS", 5, true), ["KAUS"]);
+        assert_eq!(idents("*AUS", 1, true), ["AUS"]);
+        assert_eq!(idents("*AUS", 1, false), ["KAUS"]);
+
+        drop(db);
+        fs::remove_dir_all(&path).unwrap();
+    }
+
+    #[test]
+    fn tells_index_files_from_others() {
+        for name in [
+            "meta.json",
+            ".managed.json",
+            ".tantivy-writer.lock",
+            "0f3c9a8e6b2d4e1f9a7c5b3d1e2f4a6b.idx",
+            "0f3c9a8e6b2d4e1f9a7c5b3d1e2f4a6b.12.del",
+            "coordinates.bin",
+            "coordinates.tmp",
+        ] {
+            assert!(is_index_file(name), "{name}");
+        }
+        for name in ["config.toml", "local-info.csv", "snapshots", "notes.idx"] {
+            assert!(!is_index_file(name), "{name}");
+        }
+    }
+
+    #[test]
+    fn opens_only_indexes_that_exist() {
+        let (db, path) = fixture("existing");
+        drop(db);
+
+        let db = Database::open_existing(&path).unwrap().unwrap();
+        assert_eq!(db.airport_count(), 6);
+        drop(db);
+
+        fs::remove_dir_all(&path).unwrap();
+        assert!(Database::open_existing(&path).unwrap().is_none());
+        fs::create_dir_all(&path).unwrap();
+        assert!(Database::open_existing(&path).unwrap().is_none());
+        assert_eq!(fs::read_dir(&path).unwrap().count(), 0);
+        fs::remove_dir_all(&path).unwrap();
+    }
+
+    #[test]
+    fn fuzzy_search_forgives_typos_in_longer_words() {
+        let (db, path) = fixture("fuzzy");
+
+        assert_eq!(idents(db.search_fuzzy("Heathorw")), ["EGLL"]);
+        assert!(idents(db.search_iter("Heathorw")).is_empty());
+
+        // Short words are too easily one edit from something else.
+        assert!(idents(db.search_fuzzy("Fiar")).is_empty());
+        assert_eq!(idents(db.search_fuzzy("Fair")), ["US-0421"]);
+
+        drop(db);
+        fs::remove_dir_all(&path).unwrap();
+    }
+
+    #[test]
+    fn regex_search_matches_names_and_towns_ignoring_case() {
+        let (db, path) = fixture("regex");
+
+        assert_eq!(idents(db.search_regex("^Austin .*")), ["AUS", "KAUS"]);
+        assert_eq!(idents(db.search_regex("^AUSTIN BERG")), ["KAUS"]);
+        assert_eq!(idents(db.search_regex("(?i)heathrow")), ["EGLL"]);
+        assert_eq!(idents(db.search_regex("^mona")), ["US-0421"]);
+        assert!(idents(db.search_regex("^Bergstrom")).is_empty());
+
+        drop(db);
+        fs::remove_dir_all(&path).unwrap();
+    }
+
+    #[test]
+    fn regexes_match_anywhere_unless_anchored() {
+        assert_eq!(whole_term("^Mount .*"), "(?i)(?:Mount .*).*");
+        assert_eq!(whole_term("field$"), "(?i).*(?:field)");
+        assert_eq!(whole_term("lake|bay"), "(?i).*(?:lake|bay).*");
+        assert_eq!(whole_term(r"US\$"), r"(?i).*(?:US\$).*");
+    }
 }
@@ -0,0 +1,157 @@
+//! What changed between two versions of the data, for the summary after `adb update`.
+
+use std::fmt;
+
+use hashbrown::HashMap;
+use serde_json::Value;
+
+use crate::{
+    locale::{format_number, tr, Message},
+    model::Airport,
+};
+
+/// Identifiers listed for each of added and removed before the rest are only counted.
+const LISTED: usize = 10;
+
+/// Differences between two sets of stored airport records, matched by identifier.
+///
+/// Records are read as stored, so an index written by an older version of adb can still be
+/// compared with the current one, then normalized by reading each as an [`Airport`] and writing it
+/// back out, so a field that's only been added, renamed or reshaped since doesn't make every
+/// airport look changed. A record this version can't read is compared as stored.
+#[derive(Debug, Default)]
+pub struct DataDiff {
+    pub added: Vec<String>,
+    pub removed: Vec<String>,
+
+    /// airports with any detail other than their runways changed
+    pub changed: usize,
+
+    /// airports with any runway added, removed or changed
+    pub runways_changed: usize,
+}
+
+impl DataDiff {
+    pub fn new(old: impl IntoIterator<Item = Value>, new: impl IntoIterator<Item = Value>) -> Self {
+        let mut old: HashMap<String, (Value, Value)> = old.into_iter().filter_map(split).collect();
+        let mut diff = DataDiff::default();
+
+        for (ident, (details, runways)) in new.into_iter().filter_map(split) {
+            match old.remove(&ident) {
+                Some((old_details, old_runways)) => {
+                    diff.changed += (details != old_details) as usize;
+                    diff.runways_changed += (runways != old_runways) as usize;
+                }
+                None => diff.added.push(ident),
+            }
+        }
+
+        diff.removed = old.into_keys().collect();
+        diff.added.sort();
+        diff.removed.sort();
+        diff
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.added.is_empty()
+            && self.removed.is_empty()
+            && self.changed == 0
+            && self.runways_changed == 0
+    }
+}
+
+/// Separates a record, normalized, into its identifier, its runways, and everything else.
+fn split(record: Value) -> Option<(String, (Value, Value))> {
+    let mut record = normalize(record);
+    let object = record.as_object_mut()?;
+    let runways = object.remove("runways").unwrap_or_default();
+    let ident = object.get("ident")?.as_str()?.to_string();
+    Some((ident, (record, runways)))
+}
+
+/// The record as this version of adb would store it, or as it is if this version can't read it.
+fn normalize(record: Value) -> Value {
+    serde_json::from_value::<Airport>(record.clone())
+        .and_then(serde_json::to_value)
+        .unwrap_or(record)
+}
+
+impl fmt::Display for DataDiff {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        if self.is_empty() {
+            return writeln!(f, "{}", tr(Message::NoChanges));
+        }
+
+        let count = |n: usize| format_number(n as f64, 0);
+        for (label, idents) in [
+            (Message::AirportsAdded, &self.added),
+            (Message::AirportsRemoved, &self.removed),
+        ] {
+            write!(f, "{:<22} {:>7}", tr(label), count(idents.len()))?;
+            if !idents.is_empty() {
+                write!(f, "  {}", idents[..idents.len().min(LISTED)].join(" "))?;
+                if idents.len() > LISTED {
+                    f.write_str(" …")?;
+                }
+            }
+            writeln!(f)?;
+        }
+        writeln!(
+            f,
+            "{:<22} {:>7}",
+            tr(Message::AirportsChanged),
+            count(self.changed)
+        )?;
+        writeln!(
+            f,
+            "{:<22} {:>7}",
+            tr(Message::RunwaysChanged),
+            count(self.runways_changed)
+        )
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use serde_json::{json, Value};
+
+    use super::DataDiff;
+    use crate::golden::{airport_records, airports};
+
+    #[test]
+    fn counts_changes_by_identifier() {
+        let old = vec![
+            json!({ "ident": "KSEA", "name": "Seattle", "runways": [{ "name": "16L/34R" }] }),
+            json!({ "ident": "KBFI", "name": "Boeing Field", "runways": [] }),
+            json!({ "ident": "S43", "name": "Harvey Field", "runways": [] }),
+        ];
+        let new = vec![
+            json!({ "ident": "KSEA", "name": "Seattle", "runways": [{ "name": "16R/34L" }] }),
+            json!({ "ident": "KBFI", "name": "King County", "runways": [] }),
+            json!({ "ident": "KPAE", "name": "Paine Field", "runways": [] }),
+        ];
+
+        let diff = DataDiff::new(old, new);
+        assert_eq!(diff.added, ["KPAE"]);
+        assert_eq!(diff.removed, ["S43"]);
+        assert_eq!(diff.changed, 1);
+        assert_eq!(diff.runways_changed, 1);
+        assert!(DataDiff::new(Vec::new(), Vec::new()).is_empty());
+    }
+
+    #[test]
+    fn ignores_changes_in_how_records_are_stored() {
+        // The fixture predates runway ends, as an index written by an older adb would.
+        let old: Vec<Value> = airport_records()
+            .lines()
+            .map(|line| serde_json::from_str(line).unwrap())
+            .collect();
+        let new: Vec<Value> = airports()
+            .iter()
+            .map(|airport| serde_json::to_value(airport).unwrap())
+            .collect();
+
+        assert_ne!(old, new);
+        assert!(DataDiff::new(old, new).is_empty());
+    }
+}
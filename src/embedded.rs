@@ -0,0 +1,143 @@
+//! The airport data compiled into adb, held in memory, for answering lookups without an index.
+//!
+//! Nothing is read from or written to disk, so this works on a read-only system or one with no
+//! home directory, at the cost of parsing the data on every run and of having no text search.
+
+use std::convert::Infallible;
+
+use hashbrown::HashMap;
+
+use crate::{
+    data,
+    error::Error,
+    lookup::{self, Code, Lookup},
+    model::{Airport, Coords, Country},
+};
+
+pub struct Embedded {
+    airports: Vec<Airport>,
+
+    /// positions in `airports`, keyed by each kind of code and the uppercased code
+    by_code: HashMap<(Code, String), Vec<usize>>,
+
+    /// the country whose airports win codes shared across countries
+    prefer_country: Option<Country>,
+}
+
+impl Embedded {
+    pub fn load() -> Self {
+        Self::from_airports(
+            data::embedded_airports()
+                .map(|(airport, _)| airport)
+                .collect(),
+        )
+    }
+
+    pub(crate) fn from_airports(airports: Vec<Airport>) -> Self {
+        let mut by_code: HashMap<_, Vec<_>> = HashMap::new();
+        for (idx, airport) in airports.iter().enumerate() {
+            for kind in Code::ALL {
+                if let Some(code) = kind.of(airport) {
+                    by_code
+                        .entry((kind, code.to_uppercase()))
+                        .or_default()
+                        .push(idx);
+                }
+            }
+        }
+        Self {
+            airports,
+            by_code,
+            prefer_country: None,
+        }
+    }
+
+    /// Sets the country whose airports win when a code names airports in more than one.
+    pub fn set_prefer_country(&mut self, country: Option<Country>) {
+        self.prefer_country = country;
+    }
+
+    /// Finds an airport by identifier, or failing that by one of its other codes, as every
+    /// backend does; see [`lookup::resolve`].
+    pub fn by_identifier(&self, identifier: &str) -> Option<&Airport> {
+        let code = identifier.to_uppercase();
+        let Ok(found) = lookup::resolve(&code, self.prefer_country, |kind| {
+            let holders = self.by_code.get(&(kind, code.clone()));
+            Ok::<_, Infallible>(
+                holders
+                    .into_iter()
+                    .flatten()
+                    .map(|&idx| &self.airports[idx])
+                    .collect(),
+            )
+        });
+        found
+    }
+
+    /// Finds the airports closest to a point, nearest first, among those `keep` accepts.
+    pub fn nearest(
+        &self,
+        coords: &Coords,
+        limit: usize,
+        keep: impl Fn(&Airport) -> bool,
+    ) -> Vec<&Airport> {
+        let mut candidates: Vec<_> = self
+            .airports
+            .iter()
+            .filter(|airport| keep(airport))
+            .map(|airport| (coords.distance_to(&airport.coordinates).meters(), airport))
+            .collect();
+
+        candidates.sort_by(|a, b| a.0.total_cmp(&b.0));
+        candidates
+            .into_iter()
+            .take(limit)
+            .map(|(_, airport)| airport)
+            .collect()
+    }
+
+    pub fn iter(&self) -> impl Iterator<Item = &Airport> {
+        self.airports.iter()
+    }
+}
+
+impl Lookup for Embedded {
+    fn by_identifier(&self, identifier: &str) -> crate::Result<Option<Airport>> {
+        Ok(Embedded::by_identifier(self, identifier).cloned())
+    }
+
+    /// There's no text index to search, so this is always [`Error::NeedsIndex`].
+    fn search(&self, _query: &str, _limit: usize) -> crate::Result<Vec<Airport>> {
+        Err(Error::NeedsIndex)
+    }
+
+    fn nearest(&self, coords: &Coords, limit: usize) -> crate::Result<Vec<Airport>> {
+        let nearest = Embedded::nearest(self, coords, limit, |_| true);
+        Ok(nearest.into_iter().cloned().collect())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::Embedded;
+    use crate::{golden::airports, model::Coords};
+
+    #[test]
+    fn finds_airports_by_identifier_and_position() {
+        let data = Embedded::from_airports(airports());
+
+        assert_eq!(data.by_identifier("kpae").unwrap().ident, "KPAE");
+        assert!(data.by_identifier("KXXX").is_none());
+
+        let everett = Coords {
+            latitude: 47.9,
+            longitude: -122.3,
+        };
+        let nearest: Vec<_> = data
+            .nearest(&everett, 2, |airport| airport.ident != "KPAE")
+            .into_iter()
+            .map(|airport| airport.ident.as_str())
+            .collect();
+        assert_eq!(nearest, ["K0S9", "KSEA"]);
+    }
+}
@@ -1,11 +1,37 @@
 use core::fmt;
-use std::io;
+use std::{io, path::PathBuf};
+
+#[cfg(feature = "search")]
+use tantivy::directory::error::{OpenDirectoryError, OpenReadError};
+
+use crate::locale::{tr, Message};
 
 #[derive(Debug)]
 pub enum Error {
     UnknownIdentifier(String),
+    ReadOnlyIndex(PathBuf),
+    UnknownLocale(String),
+    LocaleInUse,
+    Renderer(String),
+    Config(PathBuf, String),
+    UnknownSearch(String),
+    UnknownRoute(String),
+    SplitOutsideRoute(String),
+    NoFuelStop(String),
+    NoTrackPoints(PathBuf),
+    SearchCheck(String),
+    NoHomeDirectory,
+    NoSnapshot(String),
+    OutdatedSnapshot(PathBuf),
+    NeedsIndex,
+    CustomDataDir(PathBuf),
     IO(io::Error),
+    Json(serde_json::Error),
+    Csv(csv::Error),
+    #[cfg(feature = "search")]
     Tantivy(tantivy::TantivyError),
+    #[cfg(feature = "sqlite")]
+    Sqlite(rusqlite::Error),
 }
 
 impl Error {
@@ -20,18 +46,87 @@ impl From<io::Error> for Error {
     }
 }
 
+impl From<serde_json::Error> for Error {
+    fn from(v: serde_json::Error) -> Self {
+        Self::Json(v)
+    }
+}
+
+impl From<csv::Error> for Error {
+    fn from(v: csv::Error) -> Self {
+        Self::Csv(v)
+    }
+}
+
+#[cfg(feature = "search")]
 impl From<tantivy::TantivyError> for Error {
     fn from(v: tantivy::TantivyError) -> Self {
         Self::Tantivy(v)
     }
 }
 
+#[cfg(feature = "sqlite")]
+impl From<rusqlite::Error> for Error {
+    fn from(v: rusqlite::Error) -> Self {
+        Self::Sqlite(v)
+    }
+}
+
+#[cfg(feature = "search")]
+impl From<OpenDirectoryError> for Error {
+    fn from(v: OpenDirectoryError) -> Self {
+        Self::Tantivy(v.into())
+    }
+}
+
+#[cfg(feature = "search")]
+impl From<OpenReadError> for Error {
+    fn from(v: OpenReadError) -> Self {
+        Self::Tantivy(v.into())
+    }
+}
+
 impl fmt::Display for Error {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
         match self {
-            Error::UnknownIdentifier(ident) => write!(f, "unknown identifier: {ident}"),
+            Error::UnknownIdentifier(ident) => {
+                write!(f, "{}: {ident}", tr(Message::UnknownIdentifier))
+            }
+            Error::ReadOnlyIndex(path) => {
+                write!(f, "{}: {}", tr(Message::ReadOnlyIndex), path.display())
+            }
+            Error::UnknownLocale(locale) => write!(f, "{}: {locale}", tr(Message::UnknownLocale)),
+            Error::LocaleInUse => f.write_str(tr(Message::LocaleInUse)),
+            Error::Renderer(command) => write!(f, "{}: {command}", tr(Message::RendererFailed)),
+            Error::Config(path, e) => write!(f, "{}: {e}", path.display()),
+            Error::UnknownSearch(name) => write!(f, "{}: {name}", tr(Message::UnknownSearch)),
+            Error::UnknownRoute(name) => write!(f, "{}: {name}", tr(Message::UnknownRoute)),
+            Error::NoHomeDirectory => f.write_str(tr(Message::NoHomeDirectory)),
+            Error::NoSnapshot(as_of) => write!(f, "{}: {as_of}", tr(Message::NoSnapshot)),
+            Error::OutdatedSnapshot(path) => {
+                write!(f, "{}: {}", tr(Message::OutdatedSnapshot), path.display())
+            }
+            Error::NeedsIndex => f.write_str(tr(Message::NeedsIndex)),
+            Error::CustomDataDir(path) => {
+                write!(f, "{}: {}", tr(Message::CustomDataDir), path.display())
+            }
+            Error::SearchCheck(ident) => {
+                write!(f, "{} ({ident})", tr(Message::SearchCheckFailed))
+            }
+            Error::SplitOutsideRoute(split) => {
+                write!(f, "{}: {split}", tr(Message::SplitOutsideRoute))
+            }
+            Error::NoFuelStop(from) => write!(f, "{} {from}", tr(Message::NoFuelStop)),
+            Error::NoTrackPoints(path) => {
+                write!(f, "{} {}", tr(Message::NoTrackPoints), path.display())
+            }
             Error::IO(e) => e.fmt(f),
+            Error::Json(e) => e.fmt(f),
+            Error::Csv(e) => e.fmt(f),
+            #[cfg(feature = "search")]
             Error::Tantivy(e) => e.fmt(f),
+            #[cfg(feature = "sqlite")]
+            Error::Sqlite(e) => e.fmt(f),
         }
     }
 }
@@ -0,0 +1,299 @@
+//! Routes and airports in formats other programs can read, for checking a plan on a map.
+
+use std::fmt::Write;
+
+use serde_json::{json, Value};
+
+use crate::{
+    model::{Airport, Coords},
+    route::Route,
+    waypoint::Waypoint,
+};
+
+/// A GeoJSON `FeatureCollection` with the route as a `LineString` followed by a `Point` for each
+/// waypoint, ready to drop onto geojson.io or a Leaflet map.
+pub fn geojson(route: &Route) -> Value {
+    let positions: Vec<_> = route
+        .waypoints
+        .iter()
+        .map(|waypoint| position(waypoint.coordinates()))
+        .collect();
+    let line = json!({
+        "type": "Feature",
+        "geometry": {
+            "type": "LineString",
+            "coordinates": positions,
+        },
+        "properties": {
+            "distance_nm": route.distance().to_nautical_miles().0,
+        },
+    });
+
+    let points = route.waypoints.iter().map(|waypoint| {
+        let properties = match waypoint {
+            Waypoint::Airport(airport) => json!({ "ident": airport.ident, "name": airport.name }),
+            _ => json!({ "name": waypoint.name().to_string() }),
+        };
+
+        json!({
+            "type": "Feature",
+            "geometry": {
+                "type": "Point",
+                "coordinates": position(waypoint.coordinates()),
+            },
+            "properties": properties,
+        })
+    });
+
+    json!({
+        "type": "FeatureCollection",
+        "features": std::iter::once(line).chain(points).collect::<Vec<_>>(),
+    })
+}
+
+/// A GPX 1.1 file holding the route as an `<rte>`, for handheld GPS units and EFB apps.
+///
+/// Airports are named by identifier and described by name. A waypoint given only as coordinates
+/// has no name of its own, and most units want one, so it gets one from [`point_names`].
+pub fn gpx(route: &Route) -> String {
+    let mut out = String::from("<?xml version=\"1.0\" encoding=\"UTF-8\"?>\n");
+    out += "<gpx version=\"1.1\" creator=\"adb\" xmlns=\"http://www.topografix.com/GPX/1/1\">\n";
+    out += "  <rte>\n";
+
+    let names = point_names(route);
+    writeln!(out, "    <name>{}</name>", escape(&names.join(" "))).unwrap();
+
+    for (waypoint, name) in route.waypoints.iter().zip(&names) {
+        let Coords {
+            latitude,
+            longitude,
+        } = waypoint.coordinates();
+        writeln!(out, "    <rtept lat=\"{latitude}\" lon=\"{longitude}\">").unwrap();
+        writeln!(out, "      <name>{}</name>", escape(name)).unwrap();
+        if let Waypoint::Airport(airport) = waypoint {
+            writeln!(out, "      <desc>{}</desc>", escape(&airport.name)).unwrap();
+        }
+        out += "    </rtept>\n";
+    }
+
+    out += "  </rte>\n</gpx>\n";
+    out
+}
+
+/// A KML document with the route as a path and a placemark for each waypoint, for Google Earth.
+/// Airports are described as `adb` would print them, runways and all.
+pub fn kml_route(route: &Route) -> String {
+    let names = point_names(route);
+    let mut body = String::new();
+
+    let path: Vec<_> = route
+        .waypoints
+        .iter()
+        .map(|waypoint| kml_position(waypoint.coordinates()))
+        .collect();
+    body += "    <Placemark>\n";
+    writeln!(body, "      <name>{}</name>", escape(&names.join(" "))).unwrap();
+    body += "      <LineString>\n        <tessellate>1</tessellate>\n";
+    writeln!(
+        body,
+        "        <coordinates>{}</coordinates>",
+        path.join(" ")
+    )
+    .unwrap();
+    body += "      </LineString>\n    </Placemark>\n";
+
+    for (waypoint, name) in route.waypoints.iter().zip(&names) {
+        let airport = match waypoint {
+            Waypoint::Airport(airport) => Some(airport),
+            _ => None,
+        };
+        placemark(&mut body, name, airport, waypoint.coordinates());
+    }
+
+    kml_document(&names.join(" "), &body)
+}
+
+/// A KML document with a placemark for each airport, described by its details and runways.
+pub fn kml_airports<'a>(airports: impl IntoIterator<Item = &'a Airport>) -> String {
+    let mut body = String::new();
+    let mut idents = Vec::new();
+    for airport in airports {
+        placemark(
+            &mut body,
+            &airport.ident,
+            Some(airport),
+            airport.coordinates,
+        );
+        idents.push(airport.ident.as_str());
+    }
+    kml_document(&idents.join(" "), &body)
+}
+
+fn kml_document(name: &str, body: &str) -> String {
+    let mut out = String::from("<?xml version=\"1.0\" encoding=\"UTF-8\"?>\n");
+    out += "<kml xmlns=\"http://www.opengis.net/kml/2.2\">\n  <Document>\n";
+    writeln!(out, "    <name>{}</name>", escape(name)).unwrap();
+    out += body;
+    out += "  </Document>\n</kml>\n";
+    out
+}
+
+fn placemark(out: &mut String, name: &str, airport: Option<&Airport>, coords: Coords) {
+    *out += "    <Placemark>\n";
+    writeln!(out, "      <name>{}</name>", escape(name)).unwrap();
+    if let Some(airport) = airport {
+        // Google Earth shows the description as HTML, which would run the lines together.
+        let description = escape(&airport.to_string());
+        writeln!(
+            out,
+            "      <description><![CDATA[<pre>{description}</pre>]]></description>"
+        )
+        .unwrap();
+    }
+    writeln!(
+        out,
+        "      <Point><coordinates>{}</coordinates></Point>",
+        kml_position(coords)
+    )
+    .unwrap();
+    *out += "    </Placemark>\n";
+}
+
+/// KML, like GeoJSON, puts longitude first, and wants an altitude.
+fn kml_position(coords: Coords) -> String {
+    format!("{},{},0", coords.longitude, coords.latitude)
+}
+
+/// Names for each point of the route as most GPS units and map programs want them: airports by
+/// identifier, and a waypoint given only as coordinates as `WP` and its place in the route.
+fn point_names(route: &Route) -> Vec<String> {
+    route
+        .waypoints
+        .iter()
+        .enumerate()
+        .map(|(idx, waypoint)| match waypoint {
+            Waypoint::Coords(_) => format!("WP{}", idx + 1),
+            _ => waypoint.name().to_string(),
+        })
+        .collect()
+}
+
+fn escape(text: &str) -> String {
+    text.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('"', "&quot;")
+}
+
+/// GeoJSON puts longitude first.
+fn position(coords: Coords) -> [f64; 2] {
+    [coords.longitude, coords.latitude]
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{
+        golden::{airports, assert_golden},
+        locale::Locale,
+        model::Coords,
+        route::Route,
+        waypoint::Waypoint,
+    };
+
+    #[test]
+    fn exports_match_golden_files() {
+        // Airport descriptions in KML are rendered text, only stable in one locale.
+        Locale::set_for_thread(Locale::English);
+
+        let airports = airports();
+        let route = Route::new(vec![
+            Waypoint::from(airports[0].clone()),
+            Waypoint::from(Coords {
+                latitude: 47.2,
+                longitude: -122.9,
+            }),
+            Waypoint::Named(
+                "T&D".into(),
+                Coords {
+                    latitude: 47.75,
+                    longitude: -122.6,
+                },
+            ),
+            Waypoint::from(airports[2].clone()),
+        ]);
+
+        let geojson = serde_json::to_string_pretty(&super::geojson(&route)).unwrap() + "\n";
+        assert_golden("route.geojson", &geojson);
+        assert_golden("route.gpx", &super::gpx(&route));
+        assert_golden("route.kml", &super::kml_route(&route));
+        assert_golden("airports.kml", &super::kml_airports(&airports));
+    }
+
+    #[test]
+    fn geojson_has_the_route_and_each_waypoint() {
+        let route = Route::new(vec![
+            Waypoint::from(Coords {
+                latitude: 47.45,
+                longitude: -122.31,
+            }),
+            Waypoint::Named(
+                "TOD".into(),
+                Coords {
+                    latitude: 46.0,
+                    longitude: -122.5,
+                },
+            ),
+        ]);
+
+        let geojson = super::geojson(&route);
+        let features = geojson["features"].as_array().unwrap();
+        assert_eq!(features.len(), 3);
+        assert_eq!(features[0]["geometry"]["type"], "LineString");
+        assert_eq!(
+            features[0]["geometry"]["coordinates"][0],
+            serde_json::json!([-122.31, 47.45])
+        );
+        assert_eq!(features[2]["properties"]["name"], "TOD");
+    }
+
+    #[test]
+    fn gpx_names_every_route_point() {
+        let route = Route::new(vec![
+            Waypoint::from(Coords {
+                latitude: 47.45,
+                longitude: -122.31,
+            }),
+            Waypoint::Named(
+                "T&D".into(),
+                Coords {
+                    latitude: 46.0,
+                    longitude: -122.5,
+                },
+            ),
+        ]);
+
+        let gpx = super::gpx(&route);
+        assert!(gpx.contains("<rtept lat=\"47.45\" lon=\"-122.31\">\n      <name>WP1</name>"));
+        assert!(gpx.contains("<name>T&amp;D</name>"));
+        assert!(gpx.ends_with("</rte>\n</gpx>\n"));
+    }
+
+    #[test]
+    fn kml_has_a_path_and_placemarks() {
+        let route = Route::new(vec![
+            Waypoint::from(Coords {
+                latitude: 47.45,
+                longitude: -122.31,
+            }),
+            Waypoint::from(Coords {
+                latitude: 46.0,
+                longitude: -122.5,
+            }),
+        ]);
+
+        let kml = super::kml_route(&route);
+        assert!(kml.contains("<coordinates>-122.31,47.45,0 -122.5,46,0</coordinates>"));
+        assert_eq!(kml.matches("<Placemark>").count(), 3);
+        assert!(kml.contains("<name>WP2</name>"));
+    }
+}
@@ -0,0 +1,404 @@
+//! A small expression language for filtering airports, e.g.
+//!
+//! ```text
+//! elevation_ft > 5000 && runways.any(length > 7000 && is_lighted)
+//! ```
+//!
+//! Fields are looked up by name on the airport's JSON form, so anything `--json` would show can
+//! be filtered on. `list.any(expr)` and `list.all(expr)` evaluate `expr` against each element of
+//! a list, and `list.len()` counts them. Comparisons against missing values are false.
+
+use std::{cmp::Ordering, fmt, str::FromStr};
+
+use serde::Serialize;
+use serde_json::Value;
+
+use crate::locale::{tr, Message};
+
+#[derive(Clone, Debug)]
+pub struct Filter {
+    source: String,
+    expr: Expr,
+}
+
+impl Filter {
+    /// True if the item satisfies the filter.
+    pub fn matches(&self, item: &impl Serialize) -> bool {
+        let value = serde_json::to_value(item).unwrap_or(Value::Null);
+        truthy(&self.expr.eval(&value))
+    }
+}
+
+impl FromStr for Filter {
+    type Err = ParseFilterError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let tokens = tokenize(s)?;
+        let mut parser = Parser { tokens, idx: 0 };
+        let expr = parser.expr()?;
+
+        match parser.peek() {
+            None => Ok(Filter {
+                source: s.into(),
+                expr,
+            }),
+            Some(token) => Err(ParseFilterError::Unexpected(token.to_string())),
+        }
+    }
+}
+
+impl fmt::Display for Filter {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(&self.source)
+    }
+}
+
+#[derive(Debug)]
+pub enum ParseFilterError {
+    Unexpected(String),
+    UnexpectedEnd,
+    UnknownMethod(String),
+}
+
+impl fmt::Display for ParseFilterError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ParseFilterError::Unexpected(token) => {
+                write!(f, "{}: {token}", tr(Message::FilterUnexpected))
+            }
+            ParseFilterError::UnexpectedEnd => f.write_str(tr(Message::FilterUnexpectedEnd)),
+            ParseFilterError::UnknownMethod(name) => {
+                write!(f, "{}: {name}", tr(Message::FilterUnknownMethod))
+            }
+        }
+    }
+}
+
+impl std::error::Error for ParseFilterError {}
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+enum Op {
+    Eq,
+    Ne,
+    Lt,
+    Le,
+    Gt,
+    Ge,
+}
+
+#[derive(Clone, Debug)]
+enum Expr {
+    Or(Box<Expr>, Box<Expr>),
+    And(Box<Expr>, Box<Expr>),
+    Not(Box<Expr>),
+    Compare(Box<Expr>, Op, Box<Expr>),
+    Field(Vec<String>),
+    Any(Vec<String>, Box<Expr>),
+    All(Vec<String>, Box<Expr>),
+    Len(Vec<String>),
+    Literal(Value),
+}
+
+impl Expr {
+    fn eval(&self, context: &Value) -> Value {
+        match self {
+            Expr::Or(a, b) => Value::Bool(truthy(&a.eval(context)) || truthy(&b.eval(context))),
+            Expr::And(a, b) => Value::Bool(truthy(&a.eval(context)) && truthy(&b.eval(context))),
+            Expr::Not(a) => Value::Bool(!truthy(&a.eval(context))),
+            Expr::Compare(a, op, b) => {
+                Value::Bool(compare(&a.eval(context), *op, &b.eval(context)))
+            }
+            Expr::Field(path) => lookup(context, path).clone(),
+            Expr::Any(path, predicate) => {
+                Value::Bool(elements(context, path).any(|item| truthy(&predicate.eval(item))))
+            }
+            Expr::All(path, predicate) => {
+                Value::Bool(elements(context, path).all(|item| truthy(&predicate.eval(item))))
+            }
+            Expr::Len(path) => Value::from(elements(context, path).count()),
+            Expr::Literal(value) => value.clone(),
+        }
+    }
+}
+
+fn lookup<'a>(context: &'a Value, path: &[String]) -> &'a Value {
+    path.iter()
+        .try_fold(context, |value, key| value.get(key))
+        .unwrap_or(&Value::Null)
+}
+
+fn elements<'a>(context: &'a Value, path: &[String]) -> impl Iterator<Item = &'a Value> {
+    lookup(context, path).as_array().into_iter().flatten()
+}
+
+fn truthy(value: &Value) -> bool {
+    match value {
+        Value::Null => false,
+        Value::Bool(b) => *b,
+        Value::Number(n) => n.as_f64().is_some_and(|n| n != 0.0),
+        Value::String(s) => !s.is_empty(),
+        Value::Array(a) => !a.is_empty(),
+        Value::Object(_) => true,
+    }
+}
+
+fn compare(a: &Value, op: Op, b: &Value) -> bool {
+    let ordering = match (a, b) {
+        (Value::Number(a), Value::Number(b)) => a.as_f64().partial_cmp(&b.as_f64()),
+        (Value::String(a), Value::String(b)) => Some(a.to_lowercase().cmp(&b.to_lowercase())),
+        (Value::Bool(a), Value::Bool(b)) => Some(a.cmp(b)),
+        _ => None,
+    };
+
+    let Some(ordering) = ordering else {
+        return false;
+    };
+
+    match op {
+        Op::Eq => ordering == Ordering::Equal,
+        Op::Ne => ordering != Ordering::Equal,
+        Op::Lt => ordering == Ordering::Less,
+        Op::Le => ordering != Ordering::Greater,
+        Op::Gt => ordering == Ordering::Greater,
+        Op::Ge => ordering != Ordering::Less,
+    }
+}
+
+#[derive(Clone, Debug, PartialEq)]
+enum Token {
+    Ident(String),
+    Number(f64),
+    String(String),
+    Op(Op),
+    And,
+    Or,
+    Not,
+    Dot,
+    Open,
+    Close,
+}
+
+impl fmt::Display for Token {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Token::Ident(ident) => f.write_str(ident),
+            Token::Number(n) => n.fmt(f),
+            Token::String(s) => write!(f, "{s:?}"),
+            Token::Op(op) => f.write_str(match op {
+                Op::Eq => "==",
+                Op::Ne => "!=",
+                Op::Lt => "<",
+                Op::Le => "<=",
+                Op::Gt => ">",
+                Op::Ge => ">=",
+            }),
+            Token::And => f.write_str("&&"),
+            Token::Or => f.write_str("||"),
+            Token::Not => f.write_str("!"),
+            Token::Dot => f.write_str("."),
+            Token::Open => f.write_str("("),
+            Token::Close => f.write_str(")"),
+        }
+    }
+}
+
+fn tokenize(s: &str) -> Result<Vec<Token>, ParseFilterError> {
+    let mut tokens = Vec::new();
+    let mut chars = s.char_indices().peekable();
+
+    while let Some((start, c)) = chars.next() {
+        let mut next_is = |expected: char| chars.next_if(|&(_, c)| c == expected).is_some();
+        let token = match c {
+            c if c.is_whitespace() => continue,
+            '(' => Token::Open,
+            ')' => Token::Close,
+            '.' => Token::Dot,
+            '&' if next_is('&') => Token::And,
+            '|' if next_is('|') => Token::Or,
+            '=' if next_is('=') => Token::Op(Op::Eq),
+            '!' if next_is('=') => Token::Op(Op::Ne),
+            '!' => Token::Not,
+            '<' if next_is('=') => Token::Op(Op::Le),
+            '<' => Token::Op(Op::Lt),
+            '>' if next_is('=') => Token::Op(Op::Ge),
+            '>' => Token::Op(Op::Gt),
+            '"' | '\'' => {
+                let mut text = String::new();
+                loop {
+                    match chars.next() {
+                        Some((_, q)) if q == c => break,
+                        Some((_, q)) => text.push(q),
+                        None => return Err(ParseFilterError::UnexpectedEnd),
+                    }
+                }
+                Token::String(text)
+            }
+            c if c.is_ascii_digit() || c == '-' => {
+                let mut end = start + c.len_utf8();
+                while let Some((idx, c)) =
+                    chars.next_if(|&(_, c)| c.is_ascii_digit() || c == '.' || c == '_')
+                {
+                    end = idx + c.len_utf8();
+                }
+                let text = s[start..end].replace('_', "");
+                let number = text
+                    .parse()
+                    .map_err(|_| ParseFilterError::Unexpected(text))?;
+                Token::Number(number)
+            }
+            c if c.is_alphabetic() || c == '_' => {
+                let mut end = start + c.len_utf8();
+                while let Some((idx, c)) = chars.next_if(|&(_, c)| c.is_alphanumeric() || c == '_')
+                {
+                    end = idx + c.len_utf8();
+                }
+                Token::Ident(s[start..end].to_string())
+            }
+            c => return Err(ParseFilterError::Unexpected(c.to_string())),
+        };
+        tokens.push(token);
+    }
+
+    Ok(tokens)
+}
+
+struct Parser {
+    tokens: Vec<Token>,
+    idx: usize,
+}
+
+impl Parser {
+    fn peek(&self) -> Option<&Token> {
+        self.tokens.get(self.idx)
+    }
+
+    fn next(&mut self) -> Result<Token, ParseFilterError> {
+        let token = self
+            .tokens
+            .get(self.idx)
+            .cloned()
+            .ok_or(ParseFilterError::UnexpectedEnd)?;
+        self.idx += 1;
+        Ok(token)
+    }
+
+    fn eat(&mut self, token: &Token) -> bool {
+        if self.peek() == Some(token) {
+            self.idx += 1;
+            true
+        } else {
+            false
+        }
+    }
+
+    fn expect(&mut self, token: &Token) -> Result<(), ParseFilterError> {
+        match self.next()? {
+            t if &t == token => Ok(()),
+            t => Err(ParseFilterError::Unexpected(t.to_string())),
+        }
+    }
+
+    fn expr(&mut self) -> Result<Expr, ParseFilterError> {
+        let mut left = self.and()?;
+        while self.eat(&Token::Or) {
+            left = Expr::Or(Box::new(left), Box::new(self.and()?));
+        }
+        Ok(left)
+    }
+
+    fn and(&mut self) -> Result<Expr, ParseFilterError> {
+        let mut left = self.unary()?;
+        while self.eat(&Token::And) {
+            left = Expr::And(Box::new(left), Box::new(self.unary()?));
+        }
+        Ok(left)
+    }
+
+    fn unary(&mut self) -> Result<Expr, ParseFilterError> {
+        if self.eat(&Token::Not) {
+            return Ok(Expr::Not(Box::new(self.unary()?)));
+        }
+
+        let left = self.primary()?;
+        if let Some(&Token::Op(op)) = self.peek() {
+            self.idx += 1;
+            let right = self.primary()?;
+            return Ok(Expr::Compare(Box::new(left), op, Box::new(right)));
+        }
+        Ok(left)
+    }
+
+    fn primary(&mut self) -> Result<Expr, ParseFilterError> {
+        match self.next()? {
+            Token::Open => {
+                let expr = self.expr()?;
+                self.expect(&Token::Close)?;
+                Ok(expr)
+            }
+            Token::Number(n) => Ok(Expr::Literal(Value::from(n))),
+            Token::String(s) => Ok(Expr::Literal(Value::from(s))),
+            Token::Ident(ident) if ident == "true" => Ok(Expr::Literal(Value::Bool(true))),
+            Token::Ident(ident) if ident == "false" => Ok(Expr::Literal(Value::Bool(false))),
+            Token::Ident(ident) => self.path(ident),
+            token => Err(ParseFilterError::Unexpected(token.to_string())),
+        }
+    }
+
+    fn path(&mut self, first: String) -> Result<Expr, ParseFilterError> {
+        let mut path = vec![first];
+        while self.eat(&Token::Dot) {
+            let Token::Ident(name) = self.next()? else {
+                return Err(ParseFilterError::Unexpected(".".into()));
+            };
+
+            if !self.eat(&Token::Open) {
+                path.push(name);
+                continue;
+            }
+
+            let expr = match name.as_str() {
+                "any" => Expr::Any(path, Box::new(self.expr()?)),
+                "all" => Expr::All(path, Box::new(self.expr()?)),
+                "len" => Expr::Len(path),
+                _ => return Err(ParseFilterError::UnknownMethod(name)),
+            };
+            self.expect(&Token::Close)?;
+            return Ok(expr);
+        }
+        Ok(Expr::Field(path))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use serde_json::json;
+
+    use super::Filter;
+
+    #[test]
+    fn can_filter_on_fields_and_lists() {
+        let airport = json!({
+            "ident": "KSEA",
+            "elevation_ft": 433,
+            "iso_country": "US",
+            "runways": [
+                { "length": 11900, "is_lighted": true },
+                { "length": null, "is_lighted": false },
+            ],
+        });
+
+        let matches = |filter: &str| filter.parse::<Filter>().unwrap().matches(&airport);
+
+        assert!(matches("elevation_ft > 400 && runways.any(length > 7000)"));
+        assert!(!matches("elevation_ft > 5000 || runways.all(is_lighted)"));
+        assert!(matches("iso_country == 'us' && runways.len() == 2"));
+        assert!(matches("!(missing_field > 0)"));
+    }
+
+    #[test]
+    fn rejects_bad_filters() {
+        assert!("elevation_ft >".parse::<Filter>().is_err());
+        assert!("runways.some(length > 1)".parse::<Filter>().is_err());
+        assert!("(a == 1".parse::<Filter>().is_err());
+    }
+}
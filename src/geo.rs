@@ -0,0 +1,357 @@
+use std::str::FromStr;
+
+#[cfg(feature = "magnetic")]
+use world_magnetic_model::{
+    time::{Date, Month},
+    uom::si::{
+        angle::degree,
+        f32::{Angle, Length},
+        length::meter,
+    },
+    GeomagneticField,
+};
+
+use crate::{
+    locale::{tr, Message},
+    model::Coords,
+};
+
+/// The smallest latitude/longitude rectangle containing a set of points.
+///
+/// `west` may be greater than `east` when the box crosses the antimeridian.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct BoundingBox {
+    pub south: f64,
+    pub west: f64,
+    pub north: f64,
+    pub east: f64,
+}
+
+impl BoundingBox {
+    pub fn from_points(points: &[Coords]) -> Option<Self> {
+        let south = points.iter().map(|p| p.latitude).min_by(f64::total_cmp)?;
+        let north = points.iter().map(|p| p.latitude).max_by(f64::total_cmp)?;
+
+        let mut longitudes: Vec<_> = points.iter().map(|p| p.longitude).collect();
+        longitudes.sort_by(f64::total_cmp);
+
+        // The box spans everything except the widest gap between neighboring longitudes. Usually
+        // that's the gap that wraps around the antimeridian, in which case we get the plain
+        // min/max; routes across the Pacific are the exception.
+        let first = longitudes[0];
+        let last = longitudes[longitudes.len() - 1];
+        let mut west = first;
+        let mut east = last;
+        let mut widest = first + 360.0 - last;
+
+        for pair in longitudes.windows(2) {
+            let gap = pair[1] - pair[0];
+            if gap > widest {
+                widest = gap;
+                west = pair[1];
+                east = pair[0];
+            }
+        }
+
+        Some(BoundingBox {
+            south,
+            west,
+            north,
+            east,
+        })
+    }
+
+    /// True if a point is inside the box or on its edge.
+    pub fn contains(&self, coords: &Coords) -> bool {
+        let longitude = if self.west <= self.east {
+            (self.west..=self.east).contains(&coords.longitude)
+        } else {
+            coords.longitude >= self.west || coords.longitude <= self.east
+        };
+        (self.south..=self.north).contains(&coords.latitude) && longitude
+    }
+
+    pub fn south_west(&self) -> Coords {
+        Coords {
+            latitude: self.south,
+            longitude: self.west,
+        }
+    }
+
+    pub fn north_east(&self) -> Coords {
+        Coords {
+            latitude: self.north,
+            longitude: self.east,
+        }
+    }
+}
+
+impl FromStr for BoundingBox {
+    type Err = String;
+
+    /// Reads two opposite corners as `lat,lon,lat,lon`, in either order. A box read this way
+    /// never crosses the antimeridian.
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let bad = || tr(Message::BadBoundingBox).to_string();
+        let values = s
+            .split(',')
+            .map(|value| value.trim().parse::<f64>().map_err(|_| bad()))
+            .collect::<Result<Vec<_>, _>>()?;
+        let &[lat1, lon1, lat2, lon2] = values.as_slice() else {
+            return Err(bad());
+        };
+        if [lat1, lat2].iter().any(|lat| lat.abs() > 90.0)
+            || [lon1, lon2].iter().any(|lon| lon.abs() > 180.0)
+        {
+            return Err(bad());
+        }
+
+        Ok(BoundingBox {
+            south: lat1.min(lat2),
+            west: lon1.min(lon2),
+            north: lat1.max(lat2),
+            east: lon1.max(lon2),
+        })
+    }
+}
+
+/// The geographic midpoint of a set of points, i.e. the mean of their positions on the sphere.
+pub fn center(points: &[Coords]) -> Option<Coords> {
+    if points.is_empty() {
+        return None;
+    }
+
+    let (x, y, z) = points.iter().fold((0.0, 0.0, 0.0), |(x, y, z), p| {
+        let lat = p.latitude.to_radians();
+        let lon = p.longitude.to_radians();
+        (
+            x + lat.cos() * lon.cos(),
+            y + lat.cos() * lon.sin(),
+            z + lat.sin(),
+        )
+    });
+
+    let n = points.len() as f64;
+    let (x, y, z) = (x / n, y / n, z / n);
+    Some(Coords {
+        latitude: z.atan2(x.hypot(y)).to_degrees(),
+        longitude: y.atan2(x).to_degrees(),
+    })
+}
+
+/// The point `fraction` of the way from `a` to `b` along the great circle between them.
+pub fn intermediate(a: &Coords, b: &Coords, fraction: f64) -> Coords {
+    let (lat1, lon1) = (a.latitude.to_radians(), a.longitude.to_radians());
+    let (lat2, lon2) = (b.latitude.to_radians(), b.longitude.to_radians());
+
+    // Angular distance by haversine, which stays accurate for short legs.
+    let h = ((lat2 - lat1) / 2.0).sin().powi(2)
+        + lat1.cos() * lat2.cos() * ((lon2 - lon1) / 2.0).sin().powi(2);
+    let delta = 2.0 * h.sqrt().asin();
+    if delta == 0.0 {
+        return *a;
+    }
+
+    let wa = ((1.0 - fraction) * delta).sin() / delta.sin();
+    let wb = (fraction * delta).sin() / delta.sin();
+    let x = wa * lat1.cos() * lon1.cos() + wb * lat2.cos() * lon2.cos();
+    let y = wa * lat1.cos() * lon1.sin() + wb * lat2.cos() * lon2.sin();
+    let z = wa * lat1.sin() + wb * lat2.sin();
+
+    Coords {
+        latitude: z.atan2(x.hypot(y)).to_degrees(),
+        longitude: y.atan2(x).to_degrees(),
+    }
+}
+
+/// How far from `a` to `b`, as a fraction of the leg, the point on the leg closest to `p` lies.
+///
+/// This is 0 or 1 when the closest point is an end of the leg rather than abeam `p`.
+pub fn closest_fraction(a: &Coords, b: &Coords, p: &Coords) -> f64 {
+    let distance = |t: f64| intermediate(a, b, t).distance_to(p).meters();
+
+    // Distance to the point along a leg has a single minimum, so a ternary search finds it.
+    let (mut low, mut high) = (0.0, 1.0);
+    for _ in 0..60 {
+        let m1 = low + (high - low) / 3.0;
+        let m2 = high - (high - low) / 3.0;
+        if distance(m1) < distance(m2) {
+            high = m2;
+        } else {
+            low = m1;
+        }
+    }
+    (low + high) / 2.0
+}
+
+/// Mean radius of the earth, for the spherical calculations here.
+const EARTH_RADIUS_M: f64 = 6_371_008.8;
+
+/// The point reached by travelling `meters` from `start` along the great circle with initial
+/// course `bearing` degrees true.
+pub fn destination(start: &Coords, bearing: f64, meters: f64) -> Coords {
+    let (lat, lon) = (start.latitude.to_radians(), start.longitude.to_radians());
+    let bearing = bearing.to_radians();
+    let delta = meters / EARTH_RADIUS_M;
+
+    let lat2 = (lat.sin() * delta.cos() + lat.cos() * delta.sin() * bearing.cos()).asin();
+    let lon2 =
+        lon + (bearing.sin() * delta.sin() * lat.cos()).atan2(delta.cos() - lat.sin() * lat2.sin());
+
+    Coords {
+        latitude: lat2.to_degrees(),
+        longitude: (lon2.to_degrees() + 540.0).rem_euclid(360.0) - 180.0,
+    }
+}
+
+/// Magnetic variation at a point on a date, given as year, month and day like
+/// [`snapshot::today`](crate::snapshot::today), in degrees with east positive, from the World
+/// Magnetic Model. Subtract it from a true course to get the magnetic course.
+///
+/// `None` outside the years the bundled model covers, where a stale figure would be worse than
+/// none.
+#[cfg(feature = "magnetic")]
+pub fn variation(coords: &Coords, (year, month, day): (i64, u32, u32)) -> Option<f64> {
+    let date = Date::from_calendar_date(
+        year.try_into().ok()?,
+        Month::try_from(u8::try_from(month).ok()?).ok()?,
+        day.try_into().ok()?,
+    )
+    .ok()?;
+
+    // The field changes by well under a tenth of a degree between sea level and any altitude a
+    // light aircraft will see, so sea level will do.
+    let field = GeomagneticField::new(
+        Length::new::<meter>(0.0),
+        Angle::new::<degree>(coords.latitude as f32),
+        Angle::new::<degree>(coords.longitude as f32),
+        date,
+    )
+    .ok()?;
+    Some(field.declination().get::<degree>().into())
+}
+
+#[cfg(test)]
+mod tests {
+    use proptest::prelude::*;
+
+    use super::{closest_fraction, destination, intermediate, BoundingBox};
+    use crate::model::Coords;
+
+    fn coords(latitude: f64, longitude: f64) -> Coords {
+        Coords {
+            latitude,
+            longitude,
+        }
+    }
+
+    #[cfg(feature = "magnetic")]
+    #[test]
+    fn variation_comes_from_the_model() {
+        use super::variation;
+
+        // Seattle's variation is a little over 15°E and shrinking by a fraction of a degree a
+        // year.
+        let seattle = variation(&coords(47.45, -122.31), (2025, 1, 1)).unwrap();
+        assert!((14.5..16.5).contains(&seattle), "{seattle}");
+
+        assert!(variation(&coords(47.45, -122.31), (2040, 1, 1)).is_none());
+        assert!(variation(&coords(47.45, -122.31), (2025, 13, 1)).is_none());
+    }
+
+    #[test]
+    fn reads_bounding_box_corners() {
+        let bounds: BoundingBox = "48, -122, 47,-123".parse().unwrap();
+        assert_eq!((bounds.south, bounds.west), (47.0, -123.0));
+        assert_eq!((bounds.north, bounds.east), (48.0, -122.0));
+        assert!(bounds.contains(&coords(47.45, -122.31)));
+        assert!("47,-122,48".parse::<BoundingBox>().is_err());
+        assert!("47,-122,91,-123".parse::<BoundingBox>().is_err());
+    }
+
+    #[test]
+    fn bounding_box_crosses_antimeridian() {
+        let points = [
+            coords(21.3, -157.9),
+            coords(-33.9, 151.2),
+            coords(13.5, 144.8),
+        ];
+        let bounds = BoundingBox::from_points(&points).unwrap();
+
+        assert_eq!(bounds.west, 144.8);
+        assert!(bounds.contains(&coords(0.0, 180.0)));
+        assert!(!bounds.contains(&coords(0.0, 0.0)));
+        assert_eq!(bounds.east, -157.9);
+        assert_eq!(bounds.south, -33.9);
+        assert_eq!(bounds.north, 21.3);
+    }
+
+    #[test]
+    fn bounding_box_without_wrap() {
+        let points = [
+            coords(47.4, -122.3),
+            coords(45.6, -122.6),
+            coords(46.6, -120.5),
+        ];
+        let bounds = BoundingBox::from_points(&points).unwrap();
+
+        assert_eq!(bounds.west, -122.6);
+        assert_eq!(bounds.east, -120.5);
+    }
+
+    #[test]
+    fn intermediate_point_on_equator() {
+        let midpoint = intermediate(&coords(0.0, 10.0), &coords(0.0, 20.0), 0.5);
+        assert!(midpoint.latitude.abs() < 1e-9);
+        assert!((midpoint.longitude - 15.0).abs() < 1e-9);
+
+        let start = intermediate(&coords(47.4, -122.3), &coords(45.6, -122.6), 0.0);
+        assert!((start.latitude - 47.4).abs() < 1e-9);
+    }
+
+    #[test]
+    fn destination_due_north() {
+        // One degree of latitude is sixty nautical miles, near enough.
+        let end = destination(&coords(10.0, 179.9), 0.0, 60.0 * 1852.0);
+        assert!((end.latitude - 11.0).abs() < 0.01);
+        assert!((end.longitude - 179.9).abs() < 1e-9);
+
+        let wrapped = destination(&coords(0.0, 179.9), 90.0, 20.0 * 1852.0);
+        assert!(wrapped.longitude < -179.0);
+    }
+
+    proptest! {
+        #[test]
+        fn destination_keeps_its_bearing(
+            latitude in -80.0..80.0,
+            longitude in -180.0..180.0,
+            bearing in 0.0..360.0,
+            meters in 1_000.0..2_000_000.0,
+        ) {
+            let start = coords(latitude, longitude);
+            let end = destination(&start, bearing, meters);
+
+            let error = (start.bearing_to(&end) - bearing + 540.0).rem_euclid(360.0) - 180.0;
+            prop_assert!(error.abs() < 1e-6, "bearing off by {error}");
+            prop_assert!((-180.0..=180.0).contains(&end.longitude));
+        }
+
+        #[test]
+        fn closest_point_is_no_farther_than_either_end(
+            a in (-60.0..60.0, -170.0..170.0),
+            b in (-5.0..5.0, -5.0..5.0),
+            p in (-5.0..5.0, -5.0..5.0),
+        ) {
+            let a = coords(a.0, a.1);
+            let b = coords(a.latitude + b.0, a.longitude + b.1);
+            let p = coords(a.latitude + p.0, a.longitude + p.1);
+
+            let t = closest_fraction(&a, &b, &p);
+            prop_assert!((0.0..=1.0).contains(&t));
+
+            let closest = intermediate(&a, &b, t).distance_to(&p).meters();
+            prop_assert!(closest <= a.distance_to(&p).meters() + 1.0);
+            prop_assert!(closest <= b.distance_to(&p).meters() + 1.0);
+        }
+    }
+}
@@ -0,0 +1,45 @@
+//! Fixtures and golden files shared by the tests of the modules that write output.
+
+use std::{
+    env, fs,
+    path::{Path, PathBuf},
+};
+
+use crate::model::Airport;
+
+pub fn testdata(name: &str) -> PathBuf {
+    Path::new(env!("CARGO_MANIFEST_DIR"))
+        .join("testdata")
+        .join(name)
+}
+
+/// The airports in `testdata/airports.jsonl`: KSEA, KPAE, K0S9 and EDDF, in that order.
+pub fn airports() -> Vec<Airport> {
+    airport_records()
+        .lines()
+        .map(|line| serde_json::from_str(line).unwrap())
+        .collect()
+}
+
+/// `testdata/airports.jsonl` as written, one JSON record per line, for tests that care how the
+/// records are stored rather than what they say.
+pub fn airport_records() -> String {
+    fs::read_to_string(testdata("airports.jsonl")).unwrap()
+}
+
+/// Compares output with its golden file in `testdata/golden`. Run the tests with
+/// `UPDATE_GOLDEN=1` to rewrite the files after a deliberate change, and review the diff.
+pub fn assert_golden(name: &str, actual: &str) {
+    let path = testdata("golden").join(name);
+    if env::var_os("UPDATE_GOLDEN").is_some() {
+        fs::write(&path, actual).unwrap();
+        return;
+    }
+
+    let expected = fs::read_to_string(&path).unwrap_or_default();
+    assert!(
+        actual == expected,
+        "output differs from {}; rerun with UPDATE_GOLDEN=1 if that's intended\n\n{actual}",
+        path.display()
+    );
+}
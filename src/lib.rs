@@ -0,0 +1,45 @@
+//! Airport lookups, search and route calculations over the OurAirports data, as used by the `adb`
+//! command line tool.
+
+pub mod backup;
+#[cfg(feature = "search")]
+pub mod coordinates;
+pub mod data;
+#[cfg(feature = "search")]
+pub mod database;
+pub mod diff;
+pub mod embedded;
+pub mod error;
+#[cfg(feature = "export")]
+pub mod export;
+pub mod filter;
+pub mod geo;
+#[cfg(test)]
+mod golden;
+pub mod local_info;
+pub mod locale;
+pub mod logbook;
+pub mod lookup;
+pub mod model;
+pub mod pairs;
+pub mod phonetic;
+pub mod prefixes;
+pub mod quality;
+#[cfg(feature = "search")]
+pub mod query;
+pub mod render;
+pub mod route;
+#[cfg(feature = "search")]
+pub mod search;
+pub mod snapshot;
+#[cfg(feature = "search")]
+mod spatial;
+#[cfg(feature = "sqlite")]
+pub mod sqlite;
+pub mod track;
+pub mod units;
+pub mod waypoint;
+
+pub type Result<T, E = error::Error> = std::result::Result<T, E>;
+
+pub const METERS_PER_NAUTICAL_MILE: f64 = 1852.001;
@@ -0,0 +1,127 @@
+use std::{io, path::Path};
+
+use csv::ReaderBuilder;
+use hashbrown::HashMap;
+
+/// Operational details OurAirports doesn't carry, such as attended hours or fuel, kept by the user
+/// in a CSV file with an `ident` column and whatever other columns they like:
+///
+/// ```text
+/// ident,hours,fuel,notes
+/// KPLU,0800-1700,100LL,self serve after hours
+/// ```
+#[derive(Debug, Default)]
+pub struct LocalInfo {
+    columns: Vec<String>,
+    rows: HashMap<String, Vec<String>>,
+}
+
+impl LocalInfo {
+    /// Reads the file, treating a missing file as an empty one.
+    pub fn load(path: &Path) -> crate::Result<Self> {
+        let mut reader = match ReaderBuilder::new().flexible(true).from_path(path) {
+            Ok(reader) => reader,
+            Err(e) if matches!(e.kind(), csv::ErrorKind::Io(e) if e.kind() == io::ErrorKind::NotFound) => {
+                return Ok(LocalInfo::default())
+            }
+            Err(e) => return Err(e.into()),
+        };
+
+        let headers: Vec<String> = reader
+            .headers()?
+            .iter()
+            .map(|h| h.trim().to_lowercase())
+            .collect();
+        let Some(ident) = headers.iter().position(|h| h == "ident") else {
+            return Ok(LocalInfo::default());
+        };
+
+        let mut rows = HashMap::new();
+        for record in reader.records() {
+            let record = record?;
+            let Some(key) = record.get(ident) else {
+                continue;
+            };
+            let values = (0..headers.len())
+                .map(|idx| record.get(idx).unwrap_or_default().trim().to_string())
+                .collect();
+            rows.insert(key.trim().to_uppercase(), values);
+        }
+
+        Ok(LocalInfo {
+            columns: headers,
+            rows,
+        })
+    }
+
+    /// The non-empty details recorded for an airport, as `(column, value)` pairs in file order.
+    pub fn details<'a>(&'a self, ident: &str) -> impl Iterator<Item = (&'a str, &'a str)> {
+        let values = self.rows.get(&ident.to_uppercase());
+        self.columns
+            .iter()
+            .zip(values.into_iter().flatten())
+            .filter(|(column, value)| *column != "ident" && !value.is_empty())
+            .map(|(column, value)| (column.as_str(), value.as_str()))
+    }
+
+    /// A single detail for an airport.
+    pub fn get(&self, ident: &str, column: &str) -> Option<&str> {
+        self.details(ident)
+            .find(|(name, _)| name.eq_ignore_ascii_case(column))
+            .map(|(_, value)| value)
+    }
+
+    /// Every airport with an entry, by identifier.
+    pub fn idents(&self) -> impl Iterator<Item = &str> {
+        self.rows.keys().map(String::as_str)
+    }
+
+    /// True if a yes/no column like `aoe` is set for the airport.
+    pub fn is_set(&self, ident: &str, column: &str) -> bool {
+        self.get(ident, column).is_some_and(|value| {
+            ["yes", "y", "true", "x", "1"]
+                .iter()
+                .any(|yes| value.eq_ignore_ascii_case(yes))
+        })
+    }
+
+    /// True if the `fuel` column lists `grade` for the airport.
+    ///
+    /// Grades may be separated by commas, slashes or spaces, and are compared ignoring case and
+    /// punctuation, so `jeta` matches "Jet-A".
+    pub fn has_fuel(&self, ident: &str, grade: &str) -> bool {
+        let normalize = |s: &str| -> String {
+            s.chars()
+                .filter(char::is_ascii_alphanumeric)
+                .map(|c| c.to_ascii_uppercase())
+                .collect()
+        };
+
+        let grade = normalize(grade);
+        self.get(ident, "fuel").is_some_and(|fuel| {
+            fuel.split([',', '/', ';', ' '])
+                .any(|listed| normalize(listed) == grade)
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::fs;
+
+    use super::LocalInfo;
+
+    #[test]
+    fn can_match_fuel_grades() {
+        let path = std::env::temp_dir().join(format!("adb-local-info-{}.csv", std::process::id()));
+        fs::write(&path, "ident,fuel\nkplu,100LL/Jet-A\nKPAE,\n").unwrap();
+        let info = LocalInfo::load(&path).unwrap();
+        fs::remove_file(&path).unwrap();
+
+        assert!(info.has_fuel("KPLU", "100ll"));
+        assert!(info.has_fuel("kplu", "JETA"));
+        assert!(!info.has_fuel("KPAE", "100LL"));
+        assert!(!info.has_fuel("KSEA", "100LL"));
+        assert!(!info.is_set("KPLU", "fuel"));
+    }
+}
@@ -0,0 +1,516 @@
+use std::{cell::Cell, env, sync::OnceLock};
+
+use crate::error::Error;
+
+static CURRENT: OnceLock<Locale> = OnceLock::new();
+
+thread_local! {
+    /// A locale for this thread only, taking precedence over the process-wide one. Tests use it
+    /// so that they don't depend on the environment or on each other.
+    static OVERRIDE: Cell<Option<Locale>> = const { Cell::new(None) };
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Locale {
+    English,
+    German,
+}
+
+impl Locale {
+    /// The locale used for human-readable output, selected from the environment on first use.
+    pub fn current() -> Locale {
+        OVERRIDE
+            .with(Cell::get)
+            .unwrap_or_else(|| *CURRENT.get_or_init(Locale::from_env))
+    }
+
+    /// Overrides the locale taken from the environment. This fails once the locale has been
+    /// read or set, since whatever was translated before would be in a different language from
+    /// what follows.
+    pub fn set(locale: Locale) -> crate::Result<()> {
+        CURRENT.set(locale).map_err(|_| Error::LocaleInUse)
+    }
+
+    /// Overrides the locale for the current thread only, whether or not it has been read.
+    #[cfg(test)]
+    pub(crate) fn set_for_thread(locale: Locale) {
+        OVERRIDE.with(|current| current.set(Some(locale)));
+    }
+
+    /// Reads the locale the same way libc would: `LC_ALL`, then `LC_MESSAGES`, then `LANG`.
+    pub fn from_env() -> Locale {
+        ["LC_ALL", "LC_MESSAGES", "LANG"]
+            .into_iter()
+            .filter_map(|name| env::var(name).ok())
+            .find(|value| !value.is_empty())
+            .and_then(|value| Locale::from_tag(&value))
+            .unwrap_or(Locale::English)
+    }
+
+    /// Parses a POSIX or BCP 47 style tag such as `de_DE.UTF-8` or `en-US`.
+    pub fn from_tag(tag: &str) -> Option<Locale> {
+        let language = tag.split(['_', '-', '.', '@']).next()?;
+        match language.to_ascii_lowercase().as_str() {
+            "en" | "c" | "posix" => Some(Locale::English),
+            "de" => Some(Locale::German),
+            _ => None,
+        }
+    }
+
+    /// The thousands separator and decimal mark used by this locale.
+    fn separators(self) -> (char, char) {
+        match self {
+            Locale::English => (',', '.'),
+            Locale::German => ('.', ','),
+        }
+    }
+}
+
+/// Formats a number for human-readable output in the current locale, e.g. `11,901` or `1.234,5`.
+pub fn format_number(value: f64, precision: usize) -> String {
+    format_number_in(value, precision, Locale::current())
+}
+
+pub fn format_number_in(value: f64, precision: usize, locale: Locale) -> String {
+    let (thousands, decimal) = locale.separators();
+    let digits = format!("{:.*}", precision, value.abs());
+    let (integer, fraction) = match digits.split_once('.') {
+        Some((integer, fraction)) => (integer, Some(fraction)),
+        None => (digits.as_str(), None),
+    };
+
+    let mut formatted = String::with_capacity(digits.len() + integer.len() / 3 + 1);
+    if value < 0.0 {
+        formatted.push('-');
+    }
+
+    for (idx, digit) in integer.chars().enumerate() {
+        if idx > 0 && (integer.len() - idx) % 3 == 0 {
+            formatted.push(thousands);
+        }
+        formatted.push(digit);
+    }
+
+    if let Some(fraction) = fraction {
+        formatted.push(decimal);
+        formatted.push_str(fraction);
+    }
+
+    formatted
+}
+
+#[derive(Clone, Copy, Debug)]
+pub enum Message {
+    Feet,
+    Runways,
+    Waypoints,
+    Coordinates,
+    Bounds,
+    Center,
+    Unknown,
+    NotFound,
+    DidYouMean,
+    DuplicateIdentifier,
+    Kept,
+    Dropped,
+    Found,
+    Missing,
+    FilteredOut,
+    TruncatedTo,
+    Airports,
+    MissingElevation,
+    MissingCoordinates,
+    MissingIata,
+    MissingRunways,
+    RunwayLengths,
+    Removed,
+    BackedUp,
+    Restored,
+    Index,
+    Cache,
+    Config,
+    Segments,
+    NotBuilt,
+    TotalDistance,
+    ZeroLengthLeg,
+    UnknownIdentifier,
+    ReadOnlyIndex,
+    UnknownLocale,
+    LocaleInUse,
+    RendererFailed,
+    BadCoordinateFormat,
+    BadCoordinateValue,
+    BadCountryCode,
+    FilterUnexpected,
+    FilterUnexpectedEnd,
+    FilterUnknownMethod,
+    UnknownSearch,
+    UnknownRoute,
+    Saved,
+    SharedWaypoints,
+    Difference,
+    SplitPoint,
+    AlongRoute,
+    NearestAirport,
+    SplitOutsideRoute,
+    FuelStops,
+    AddedDistance,
+    NoFuelStop,
+    BadDistance,
+    Flown,
+    Direct,
+    NoTrackPoints,
+    Flights,
+    Unresolved,
+    LongestLeg,
+    UniqueAirports,
+    UniqueCountries,
+    MostVisited,
+    LocalInfo,
+    AltitudeCheck,
+    Magnetic,
+    True,
+    UseOddThousands,
+    UseEvenThousands,
+    BadVariation,
+    Left,
+    Right,
+    LocalInfoFile,
+    AirportCount,
+    Search,
+    Ok,
+    SearchCheckFailed,
+    Completions,
+    NoHomeDirectory,
+    BenchOpen,
+    BenchLookup,
+    BenchDist,
+    Runs,
+    Median,
+    Mean,
+    NoSnapshot,
+    OutdatedSnapshot,
+    BadDate,
+    KeptSnapshot,
+    NoChanges,
+    AirportsAdded,
+    AirportsRemoved,
+    AirportsChanged,
+    RunwaysChanged,
+    BadUnit,
+    MagneticCourses,
+    BadBackend,
+    BadKind,
+    NeedsIndex,
+    NoSqlite,
+    BadBoundingBox,
+    Closed,
+    CustomDataDir,
+    NoSearch,
+}
+
+/// Translates a message into the current locale.
+pub fn tr(message: Message) -> &'static str {
+    message.text(Locale::current())
+}
+
+impl Message {
+    pub fn text(self, locale: Locale) -> &'static str {
+        match locale {
+            Locale::English => match self {
+                Message::Feet => "feet",
+                Message::Runways => "Runways",
+                Message::Waypoints => "Waypoints",
+                Message::Coordinates => "coordinates",
+                Message::Bounds => "Bounds",
+                Message::Center => "Center",
+                Message::Unknown => "unknown",
+                Message::NotFound => "not found",
+                Message::DidYouMean => "did you mean",
+                Message::DuplicateIdentifier => "duplicate identifier",
+                Message::Kept => "kept",
+                Message::Dropped => "dropped",
+                Message::Found => "found",
+                Message::FilteredOut => "left out by --where",
+                Message::Missing => "missing",
+                Message::TruncatedTo => "too many matches, showing the first",
+                Message::Airports => "Airports",
+                Message::MissingElevation => "missing elevation",
+                Message::MissingCoordinates => "missing coordinates",
+                Message::MissingIata => "missing IATA code",
+                Message::MissingRunways => "missing runway data",
+                Message::RunwayLengths => "Runway lengths",
+                Message::Removed => "removed",
+                Message::BackedUp => "backed up",
+                Message::Restored => "restored",
+                Message::Index => "index",
+                Message::Cache => "cache",
+                Message::Config => "config",
+                Message::Segments => "segments",
+                Message::NotBuilt => "not built",
+                Message::TotalDistance => "Total distance",
+                Message::ZeroLengthLeg => "zero-length leg",
+                Message::UnknownIdentifier => "unknown identifier",
+                Message::ReadOnlyIndex => {
+                    "index is missing or out of date and can't be rebuilt in a read-only directory; \
+                     run `adb update` as its owner"
+                }
+                Message::UnknownLocale => "unknown locale",
+                Message::LocaleInUse => {
+                    "the language can't be changed once output has been translated"
+                }
+                Message::RendererFailed => "renderer failed",
+                Message::BadCoordinateFormat => "bad coordinate format",
+                Message::BadCoordinateValue => "bad coordinate value",
+                Message::BadCountryCode => "bad ISO 3166 country code",
+                Message::FilterUnexpected => "unexpected token in filter",
+                Message::FilterUnexpectedEnd => "unexpected end of filter",
+                Message::FilterUnknownMethod => "unknown filter method (expected any, all or len)",
+                Message::UnknownSearch => "no saved search named",
+                Message::UnknownRoute => "no saved route named",
+                Message::Saved => "saved",
+                Message::SharedWaypoints => "Shared waypoints",
+                Message::Difference => "Difference",
+                Message::SplitPoint => "Split point",
+                Message::AlongRoute => "along the route",
+                Message::NearestAirport => "Nearest airport",
+                Message::SplitOutsideRoute => "split point is not on the route",
+                Message::FuelStops => "Fuel stops",
+                Message::AddedDistance => "Added distance",
+                Message::NoFuelStop => "no suitable fuel stop within range of",
+                Message::BadDistance => "bad distance",
+                Message::Flown => "flown",
+                Message::Direct => "direct",
+                Message::NoTrackPoints => "no track points in",
+                Message::Flights => "Flights",
+                Message::Unresolved => "with unknown airports",
+                Message::LongestLeg => "Longest leg",
+                Message::UniqueAirports => "Airports visited",
+                Message::UniqueCountries => "Countries visited",
+                Message::MostVisited => "Most visited (arrivals)",
+                Message::LocalInfo => "Local info",
+                Message::AltitudeCheck => "VFR cruising altitude",
+                Message::Magnetic => "magnetic",
+                Message::True => "true; give --variation or --magnetic for magnetic",
+                Message::UseOddThousands => "eastbound: use odd thousands + 500",
+                Message::UseEvenThousands => "westbound: use even thousands + 500",
+                Message::BadVariation => "bad magnetic variation (expected e.g. 15E or 4.5W)",
+                Message::Left => "left",
+                Message::Right => "right",
+                Message::LocalInfoFile => "local info",
+                Message::AirportCount => "airports",
+                Message::Search => "search",
+                Message::Ok => "ok",
+                Message::SearchCheckFailed => {
+                    "search can't find an airport by its own name; rebuild the index with \
+                     `adb update`"
+                }
+                Message::Completions => "completions",
+                Message::NoHomeDirectory => {
+                    "no home directory to keep adb's files in; give the index a place with \
+                     --data-dir (and settings with --config)"
+                }
+                Message::BenchOpen => "open index",
+                Message::BenchLookup => "lookup (5 airports)",
+                Message::BenchDist => "dist (1000 waypoints)",
+                Message::Runs => "runs",
+                Message::Median => "median",
+                Message::Mean => "mean",
+                Message::NoSnapshot => "no snapshot kept on or before",
+                Message::OutdatedSnapshot => {
+                    "snapshot was written by another version of adb and can't be read"
+                }
+                Message::BadDate => "expected a date like 2023-06-15, 2023-06 or 2023",
+                Message::KeptSnapshot => "kept snapshot",
+                Message::NoChanges => "No changes to the data",
+                Message::AirportsAdded => "Airports added",
+                Message::AirportsRemoved => "Airports removed",
+                Message::AirportsChanged => "Airports changed",
+                Message::RunwaysChanged => "Runway changes",
+                Message::BadUnit => "expected nm, km or mi",
+                Message::MagneticCourses => "Magnetic courses",
+                Message::BadBackend => "expected index, static or sqlite",
+                Message::BadKind => {
+                    "expected large_airport, medium_airport, small_airport, heliport, \
+                     seaplane_base, balloonport or closed"
+                }
+                Message::NeedsIndex => "this needs the index (`--backend index`)",
+                Message::NoSqlite => "this adb was built without the sqlite feature",
+                Message::BadBoundingBox => "expected two corners as lat,lon,lat,lon",
+                Message::Closed => "closed",
+                Message::CustomDataDir => {
+                    "won't remove a data directory adb didn't create; remove it by hand"
+                }
+                Message::NoSearch => "this adb was built without the search feature",
+            },
+            Locale::German => match self {
+                Message::Feet => "Fuß",
+                Message::Runways => "Pisten",
+                Message::Waypoints => "Wegpunkte",
+                Message::Coordinates => "Koordinaten",
+                Message::Bounds => "Begrenzung",
+                Message::Center => "Mittelpunkt",
+                Message::Unknown => "unbekannt",
+                Message::NotFound => "nicht gefunden",
+                Message::DidYouMean => "meinten Sie",
+                Message::DuplicateIdentifier => "doppelte Kennung",
+                Message::Kept => "behalten",
+                Message::Dropped => "verworfen",
+                Message::Found => "gefunden",
+                Message::FilteredOut => "durch --where ausgelassen",
+                Message::Missing => "fehlend",
+                Message::TruncatedTo => "zu viele Treffer, es werden nur angezeigt:",
+                Message::Airports => "Flugplätze",
+                Message::MissingElevation => "ohne Höhe",
+                Message::MissingCoordinates => "ohne Koordinaten",
+                Message::MissingIata => "ohne IATA-Code",
+                Message::MissingRunways => "ohne Pistendaten",
+                Message::RunwayLengths => "Pistenlängen",
+                Message::Removed => "entfernt:",
+                Message::BackedUp => "gesichert:",
+                Message::Restored => "wiederhergestellt:",
+                Message::Index => "Index",
+                Message::Cache => "Cache",
+                Message::Config => "Konfig.",
+                Message::Segments => "Segmente",
+                Message::NotBuilt => "nicht erstellt",
+                Message::TotalDistance => "Gesamtentfernung",
+                Message::ZeroLengthLeg => "Teilstrecke ohne Länge",
+                Message::UnknownIdentifier => "unbekannte Kennung",
+                Message::ReadOnlyIndex => {
+                    "Index fehlt oder ist veraltet und kann in einem schreibgeschützten Verzeichnis \
+                     nicht neu erstellt werden; `adb update` als Eigentümer ausführen"
+                }
+                Message::UnknownLocale => "unbekannte Sprache",
+                Message::LocaleInUse => {
+                    "die Sprache kann nicht mehr geändert werden, wenn schon übersetzt wurde"
+                }
+                Message::RendererFailed => "Ausgabeprogramm fehlgeschlagen",
+                Message::BadCoordinateFormat => "ungültiges Koordinatenformat",
+                Message::BadCoordinateValue => "ungültiger Koordinatenwert",
+                Message::BadCountryCode => "ungültiger ISO-3166-Ländercode",
+                Message::FilterUnexpected => "unerwartetes Zeichen im Filter",
+                Message::FilterUnexpectedEnd => "unerwartetes Ende des Filters",
+                Message::FilterUnknownMethod => "unbekannte Filtermethode (erwartet: any, all oder len)",
+                Message::UnknownSearch => "keine gespeicherte Suche mit dem Namen",
+                Message::UnknownRoute => "keine gespeicherte Route mit dem Namen",
+                Message::Saved => "gespeichert:",
+                Message::SharedWaypoints => "Gemeinsame Wegpunkte",
+                Message::Difference => "Differenz",
+                Message::SplitPoint => "Teilungspunkt",
+                Message::AlongRoute => "entlang der Route",
+                Message::NearestAirport => "Nächster Flugplatz",
+                Message::SplitOutsideRoute => "Teilungspunkt liegt nicht auf der Route",
+                Message::FuelStops => "Tankstopps",
+                Message::AddedDistance => "Zusätzliche Strecke",
+                Message::NoFuelStop => "kein geeigneter Tankstopp in Reichweite von",
+                Message::BadDistance => "ungültige Entfernung",
+                Message::Flown => "geflogen",
+                Message::Direct => "direkt",
+                Message::NoTrackPoints => "keine Trackpunkte in",
+                Message::Flights => "Flüge",
+                Message::Unresolved => "mit unbekannten Flugplätzen",
+                Message::LongestLeg => "Längste Teilstrecke",
+                Message::UniqueAirports => "Besuchte Flugplätze",
+                Message::UniqueCountries => "Besuchte Länder",
+                Message::MostVisited => "Am häufigsten besucht (Landungen)",
+                Message::LocalInfo => "Lokale Informationen",
+                Message::AltitudeCheck => "VFR-Reiseflughöhe",
+                Message::Magnetic => "missweisend",
+                Message::True => "rechtweisend; --variation oder --magnetic für missweisend angeben",
+                Message::UseOddThousands => "ostwärts: ungerade Tausender + 500",
+                Message::UseEvenThousands => "westwärts: gerade Tausender + 500",
+                Message::BadVariation => "ungültige Missweisung (z. B. 15E oder 4.5W)",
+                Message::Left => "links",
+                Message::Right => "rechts",
+                Message::LocalInfoFile => "lokale Infos",
+                Message::AirportCount => "Flugplätze",
+                Message::Search => "Suche",
+                Message::Ok => "ok",
+                Message::SearchCheckFailed => {
+                    "die Suche findet einen Flugplatz nicht unter seinem eigenen Namen; Index mit \
+                     `adb update` neu aufbauen"
+                }
+                Message::Completions => "Vervollst.",
+                Message::NoHomeDirectory => {
+                    "kein Home-Verzeichnis für die Dateien von adb; Ort des Index mit --data-dir \
+                     angeben (und der Einstellungen mit --config)"
+                }
+                Message::BenchOpen => "Index öffnen",
+                Message::BenchLookup => "Abfrage (5 Flugplätze)",
+                Message::BenchDist => "dist (1000 Wegpunkte)",
+                Message::Runs => "Durchläufe",
+                Message::Median => "Median",
+                Message::Mean => "Mittel",
+                Message::NoSnapshot => "kein Snapshot am oder vor",
+                Message::OutdatedSnapshot => {
+                    "Snapshot stammt von einer anderen Version von adb und kann nicht gelesen werden"
+                }
+                Message::BadDate => "Datum wie 2023-06-15, 2023-06 oder 2023 erwartet",
+                Message::KeptSnapshot => "Snapshot behalten",
+                Message::NoChanges => "Keine Änderungen an den Daten",
+                Message::AirportsAdded => "Flugplätze hinzugefügt",
+                Message::AirportsRemoved => "Flugplätze entfernt",
+                Message::AirportsChanged => "Flugplätze geändert",
+                Message::RunwaysChanged => "Pistenänderungen",
+                Message::BadUnit => "nm, km oder mi erwartet",
+                Message::MagneticCourses => "Missweisende Kurse",
+                Message::BadBackend => "index, static oder sqlite erwartet",
+                Message::BadKind => {
+                    "large_airport, medium_airport, small_airport, heliport, seaplane_base, \
+                     balloonport oder closed erwartet"
+                }
+                Message::NeedsIndex => "das geht nur mit dem Index (`--backend index`)",
+                Message::NoSqlite => "dieses adb wurde ohne das Feature sqlite gebaut",
+                Message::BadBoundingBox => "zwei Ecken als lat,lon,lat,lon erwartet",
+                Message::Closed => "geschlossen",
+                Message::CustomDataDir => {
+                    "ein nicht von adb angelegtes Datenverzeichnis bleibt; bitte von Hand entfernen"
+                }
+                Message::NoSearch => "dieses adb wurde ohne das Feature search gebaut",
+            },
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{format_number, format_number_in, tr, Locale, Message};
+    use crate::error::Error;
+
+    #[test]
+    fn can_parse_locale_tags() {
+        assert_eq!(Locale::from_tag("de_DE.UTF-8"), Some(Locale::German));
+        assert_eq!(Locale::from_tag("en-US"), Some(Locale::English));
+        assert_eq!(Locale::from_tag("C"), Some(Locale::English));
+        assert_eq!(Locale::from_tag("fr_FR"), None);
+    }
+
+    #[test]
+    fn can_format_numbers() {
+        assert_eq!(format_number_in(11901.0, 0, Locale::English), "11,901");
+        assert_eq!(format_number_in(1234.56, 1, Locale::German), "1.234,6");
+        assert_eq!(format_number_in(-1266.0, 0, Locale::English), "-1,266");
+        assert_eq!(format_number_in(112.24, 1, Locale::English), "112.2");
+        assert_eq!(format_number_in(100000.0, 0, Locale::English), "100,000");
+    }
+
+    #[test]
+    fn threads_can_override_the_locale() {
+        Locale::set_for_thread(Locale::German);
+        assert_eq!(tr(Message::Runways), "Pisten");
+        assert_eq!(format_number(1234.5, 1), "1.234,5");
+
+        Locale::set_for_thread(Locale::English);
+        assert_eq!(tr(Message::Runways), "Runways");
+    }
+
+    #[test]
+    fn cannot_set_the_locale_after_reading_it() {
+        Locale::current();
+        assert!(matches!(
+            Locale::set(Locale::German),
+            Err(Error::LocaleInUse)
+        ));
+    }
+}
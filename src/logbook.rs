@@ -0,0 +1,357 @@
+use std::{collections::BTreeSet, fmt, io, path::Path};
+
+use csv::{ReaderBuilder, StringRecord, WriterBuilder};
+use hashbrown::HashMap;
+use rayon::prelude::*;
+
+use crate::{
+    locale::{tr, Message},
+    model::{Coords, Country},
+    units::{DistanceUnit, Meters},
+    waypoint::{Waypoint, WaypointResolver},
+};
+
+/// Number of airports listed under most visited.
+const TOP_VISITED: usize = 10;
+
+/// Flights measured at once when annotating. Each batch is measured across every core and written
+/// before the next, so a large logbook starts coming out right away.
+const BATCH: usize = 4096;
+
+/// A pilot's logbook exported as CSV, with one flight per row.
+///
+/// We don't know anything about the layout beyond the two columns holding the departure and
+/// arrival airports, so every other column is carried through untouched.
+pub struct Logbook {
+    headers: Option<StringRecord>,
+    records: Vec<StringRecord>,
+}
+
+/// Which columns hold the departure and arrival airports, counting from zero.
+#[derive(Clone, Copy, Debug)]
+pub struct Columns {
+    pub from: usize,
+    pub to: usize,
+}
+
+impl Logbook {
+    pub fn read(path: &Path, has_headers: bool) -> crate::Result<Self> {
+        let mut reader = ReaderBuilder::new()
+            .has_headers(has_headers)
+            .flexible(true)
+            .from_path(path)?;
+
+        let headers = if has_headers {
+            Some(reader.headers()?.clone())
+        } else {
+            None
+        };
+        let records = reader.records().collect::<Result<_, _>>()?;
+        Ok(Logbook { headers, records })
+    }
+
+    /// Resolves the airports of each flight, in order. Rows naming an airport we can't find are
+    /// `None` and reported on stderr.
+    pub fn legs(
+        &self,
+        resolver: &WaypointResolver,
+        columns: Columns,
+    ) -> Vec<Option<(Waypoint, Waypoint)>> {
+        // Logbooks name the same handful of airports over and over.
+        let mut cache: HashMap<String, Option<Waypoint>> = HashMap::new();
+        let mut resolve = |identifier: &str| {
+            let identifier = identifier.trim();
+            cache
+                .entry(identifier.to_uppercase())
+                .or_insert_with(|| match resolver.resolve(identifier) {
+                    Ok(waypoint) => Some(waypoint),
+                    Err(e) => {
+                        eprintln!("{e}");
+                        None
+                    }
+                })
+                .clone()
+        };
+
+        self.records
+            .iter()
+            .map(|record| {
+                let from = resolve(record.get(columns.from)?)?;
+                let to = resolve(record.get(columns.to)?)?;
+                Some((from, to))
+            })
+            .collect()
+    }
+
+    /// Like [`legs`](Self::legs), but only where the given flights went from and to, which for
+    /// airports in the index comes from its coordinate table rather than a lookup of the whole
+    /// airport. `cache` carries what's been resolved from one batch of flights to the next.
+    fn positions(
+        records: &[StringRecord],
+        resolver: &WaypointResolver,
+        columns: Columns,
+        cache: &mut HashMap<String, Option<Coords>>,
+    ) -> Vec<Option<(Coords, Coords)>> {
+        let mut resolve = |identifier: &str| {
+            let identifier = identifier.trim();
+            *cache.entry(identifier.to_uppercase()).or_insert_with(|| {
+                match resolver.coordinates(identifier) {
+                    Ok(coords) => Some(coords),
+                    Err(e) => {
+                        eprintln!("{e}");
+                        None
+                    }
+                }
+            })
+        };
+
+        records
+            .iter()
+            .map(|record| {
+                let from = resolve(record.get(columns.from)?)?;
+                let to = resolve(record.get(columns.to)?)?;
+                Some((from, to))
+            })
+            .collect()
+    }
+
+    /// Writes the logbook back out with a distance column appended, named for its unit like
+    /// `distance_nm`.
+    pub fn write_annotated(
+        &self,
+        resolver: &WaypointResolver,
+        columns: Columns,
+        unit: DistanceUnit,
+        writer: impl io::Write,
+    ) -> crate::Result<()> {
+        // Rows are written back as long as they were read, short ones included.
+        let mut writer = WriterBuilder::new().flexible(true).from_writer(writer);
+
+        if let Some(headers) = &self.headers {
+            let mut headers = headers.clone();
+            headers.push_field(&format!("distance_{}", unit.symbol()));
+            writer.write_record(&headers)?;
+        }
+
+        let mut cache = HashMap::new();
+        for records in self.records.chunks(BATCH) {
+            let legs = Self::positions(records, resolver, columns, &mut cache);
+            let distances: Vec<_> = legs
+                .par_iter()
+                .map(|leg| {
+                    leg.map(|(from, to)| unit.from_meters(Meters(from.distance_to(&to).meters())))
+                        .map(|distance| format!("{distance:.1}"))
+                        .unwrap_or_default()
+                })
+                .collect();
+
+            for (record, distance) in records.iter().zip(distances) {
+                let mut record = record.clone();
+                record.push_field(&distance);
+                writer.write_record(&record)?;
+            }
+            writer.flush()?;
+        }
+
+        writer.flush()?;
+        Ok(())
+    }
+}
+
+/// Totals across a logbook.
+#[derive(Debug, Default)]
+pub struct LogStats {
+    flights: usize,
+    unresolved: usize,
+
+    /// arrivals per airport, most visited first
+    visits: Vec<(String, usize)>,
+    distance: Meters,
+    longest: Option<(String, String, Meters)>,
+    airports: usize,
+    countries: BTreeSet<Country>,
+
+    /// the unit distances are shown in
+    unit: DistanceUnit,
+}
+
+impl LogStats {
+    pub fn from_legs(
+        legs: impl IntoIterator<Item = Option<(Waypoint, Waypoint)>>,
+        unit: DistanceUnit,
+    ) -> Self {
+        let mut stats = LogStats {
+            unit,
+            ..LogStats::default()
+        };
+        let mut visits: HashMap<String, usize> = HashMap::new();
+        let mut airports = BTreeSet::new();
+
+        for leg in legs {
+            stats.flights += 1;
+            let Some((from, to)) = leg else {
+                stats.unresolved += 1;
+                continue;
+            };
+
+            *visits.entry(to.name().to_string()).or_default() += 1;
+            for waypoint in [&from, &to] {
+                airports.insert(waypoint.name().to_string());
+                if let Waypoint::Airport(airport) = waypoint {
+                    stats.countries.insert(airport.iso_country);
+                }
+            }
+
+            let distance = Meters(from.distance_to(&to).meters());
+            stats.distance = stats.distance + distance;
+            if stats
+                .longest
+                .as_ref()
+                .is_none_or(|longest| distance > longest.2)
+            {
+                let (from, to) = (from.name().to_string(), to.name().to_string());
+                stats.longest = Some((from, to, distance));
+            }
+        }
+
+        stats.airports = airports.len();
+        stats.visits = visits.into_iter().collect();
+        stats
+            .visits
+            .sort_by(|a, b| b.1.cmp(&a.1).then_with(|| a.0.cmp(&b.0)));
+        stats
+    }
+}
+
+impl fmt::Display for LogStats {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}: {}", tr(Message::Flights), self.flights)?;
+        if self.unresolved > 0 {
+            write!(f, " ({} {})", self.unresolved, tr(Message::Unresolved))?;
+        }
+        writeln!(f)?;
+        writeln!(
+            f,
+            "{}: {}",
+            tr(Message::TotalDistance),
+            self.distance.display(self.unit)
+        )?;
+        if let Some((from, to, distance)) = &self.longest {
+            writeln!(
+                f,
+                "{}: {from} -> {to} ({})",
+                tr(Message::LongestLeg),
+                distance.display(self.unit)
+            )?;
+        }
+        writeln!(f, "{}: {}", tr(Message::UniqueAirports), self.airports)?;
+        writeln!(
+            f,
+            "{}: {} ({})",
+            tr(Message::UniqueCountries),
+            self.countries.len(),
+            self.countries
+                .iter()
+                .map(Country::as_str)
+                .collect::<Vec<_>>()
+                .join(" ")
+        )?;
+
+        writeln!(f, "\n{}:", tr(Message::MostVisited))?;
+        for (airport, count) in self.visits.iter().take(TOP_VISITED) {
+            writeln!(f, "  {airport:<8} {count:>5}")?;
+        }
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::fs;
+
+    use super::{Columns, LogStats, Logbook};
+    use crate::{
+        embedded::Embedded, golden::airports, locale::Locale, units::DistanceUnit,
+        waypoint::WaypointResolver,
+    };
+
+    /// Writes `csv` to a file of its own and reads it back as a logbook.
+    fn logbook(test: &str, csv: &str, has_headers: bool) -> Logbook {
+        let path = std::env::temp_dir().join(format!("adb-{test}-{}.csv", std::process::id()));
+        fs::write(&path, csv).unwrap();
+        let logbook = Logbook::read(&path, has_headers).unwrap();
+        fs::remove_file(&path).unwrap();
+        logbook
+    }
+
+    #[test]
+    fn annotates_every_row_and_keeps_the_rest() {
+        // Airports in the third and second columns, with a row too short to hold them and one
+        // naming an airport that isn't in the data.
+        let csv = "date,to,from,remarks\n\
+                   2023-06-01,KPAE,KSEA,\"first solo, short\"\n\
+                   2023-06-02, k0s9 ,KPAE,\n\
+                   2023-06-03\n\
+                   2023-06-04,KXXX,KSEA,lost\n";
+        let data = Embedded::from_airports(airports());
+        let resolver = WaypointResolver::new(&data);
+        let columns = Columns { from: 2, to: 1 };
+
+        let mut annotated = Vec::new();
+        logbook("annotate", csv, true)
+            .write_annotated(
+                &resolver,
+                columns,
+                DistanceUnit::NauticalMiles,
+                &mut annotated,
+            )
+            .unwrap();
+        assert_eq!(
+            String::from_utf8(annotated).unwrap(),
+            "date,to,from,remarks,distance_nm\n\
+             2023-06-01,KPAE,KSEA,\"first solo, short\",27.5\n\
+             2023-06-02, k0s9 ,KPAE,,23.1\n\
+             2023-06-03,\n\
+             2023-06-04,KXXX,KSEA,lost,\n"
+        );
+
+        // Without headers the first row is a flight too, and no column name is added.
+        let mut annotated = Vec::new();
+        logbook("annotate-no-headers", "KSEA,KPAE\n", false)
+            .write_annotated(
+                &resolver,
+                Columns { from: 0, to: 1 },
+                DistanceUnit::Kilometers,
+                &mut annotated,
+            )
+            .unwrap();
+        assert_eq!(String::from_utf8(annotated).unwrap(), "KSEA,KPAE,51.0\n");
+    }
+
+    #[test]
+    fn totals_flights() {
+        Locale::set_for_thread(Locale::English);
+
+        let csv = "from,to\nKSEA,KPAE\nKPAE,K0S9\nK0S9,KPAE\nKSEA,KXXX\n";
+        let data = Embedded::from_airports(airports());
+        let resolver = WaypointResolver::new(&data);
+        let legs = logbook("stats", csv, true).legs(&resolver, Columns { from: 0, to: 1 });
+        let stats = LogStats::from_legs(legs, DistanceUnit::NauticalMiles);
+
+        assert_eq!(stats.flights, 4);
+        assert_eq!(stats.unresolved, 1);
+        assert_eq!(stats.airports, 3);
+        assert_eq!(stats.visits[0], ("KPAE".to_string(), 2));
+        assert_eq!(stats.countries.len(), 1);
+
+        let (from, to, _) = stats.longest.as_ref().unwrap();
+        assert_eq!((from.as_str(), to.as_str()), ("KSEA", "KPAE"));
+        let shown = stats.to_string();
+        assert!(shown.contains("Total distance: 73.7 nm"), "{shown}");
+        assert!(
+            shown.contains("Longest leg: KSEA -> KPAE (27.5 nm)"),
+            "{shown}"
+        );
+    }
+}
@@ -0,0 +1,115 @@
+use std::borrow::Borrow;
+
+use crate::{
+    data,
+    model::{Airport, Coords, Country},
+};
+
+/// The lookups every backend answers: the tantivy [`Database`], the [`Embedded`] data, and with
+/// the `sqlite` feature, [`SqliteDatabase`].
+///
+/// Everything else, from phonetic search to typed queries, needs the tantivy index.
+///
+/// [`Database`]: crate::database::Database
+/// [`Embedded`]: crate::embedded::Embedded
+/// [`SqliteDatabase`]: crate::sqlite::SqliteDatabase
+pub trait Lookup {
+    fn by_identifier(&self, identifier: &str) -> crate::Result<Option<Airport>>;
+
+    /// Where the airport [`by_identifier`](Self::by_identifier) finds is, for jobs that only
+    /// measure between airports. The index answers from its coordinate table rather than looking
+    /// up each airport whole.
+    fn coordinates(&self, identifier: &str) -> crate::Result<Option<Coords>> {
+        Ok(self
+            .by_identifier(identifier)?
+            .map(|airport| airport.coordinates))
+    }
+
+    /// Free text search, best matches first.
+    fn search(&self, query: &str, limit: usize) -> crate::Result<Vec<Airport>>;
+
+    /// The airports closest to a point, nearest first.
+    fn nearest(&self, coords: &Coords, limit: usize) -> crate::Result<Vec<Airport>>;
+}
+
+/// The codes an airport can be looked up by, in the order [`resolve`] tries them.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub(crate) enum Code {
+    Identifier,
+    Gps,
+    Local,
+    Iata,
+}
+
+impl Code {
+    pub(crate) const ALL: [Code; 4] = [Code::Identifier, Code::Gps, Code::Local, Code::Iata];
+
+    /// The code an airport has of this kind, if any.
+    pub(crate) fn of(self, airport: &Airport) -> Option<&str> {
+        match self {
+            Code::Identifier => Some(&airport.ident),
+            Code::Gps => airport.gps_code.as_deref(),
+            Code::Local => airport.local_code.as_deref(),
+            Code::Iata => airport.iata_code.as_deref(),
+        }
+    }
+}
+
+/// Picks the airport a code names, so that every backend answers a lookup the same way.
+/// `candidates` gives the airports holding the code as each kind of code in turn.
+///
+/// The code is looked up as an identifier, then a GPS code, then a local code, as many US strips
+/// are known by a local code like `TX42` that isn't their identifier, and last as an IATA code if
+/// it's three letters.
+///
+/// The first open airport found wins, unless a country is preferred, when the first open airport
+/// in that country does and others are kept in case there isn't one. A closed airport is only
+/// kept if nothing open turns up: `AUS` is the identifier of Austin's old airport, but anyone
+/// asking for it wants the one that's open.
+pub(crate) fn resolve<A: Borrow<Airport>, E>(
+    code: &str,
+    prefer_country: Option<Country>,
+    mut candidates: impl FnMut(Code) -> Result<Vec<A>, E>,
+) -> Result<Option<A>, E> {
+    let mut codes = vec![Code::Identifier, Code::Gps, Code::Local];
+    if is_iata_code(code) {
+        codes.push(Code::Iata);
+    }
+
+    let preferred =
+        |airport: &Airport| prefer_country.is_none_or(|country| airport.iso_country == country);
+
+    let mut kept: Option<A> = None;
+    for kind in codes {
+        // Codes other than identifiers can be shared, so each kind can match several airports.
+        let best = candidates(kind)?.into_iter().min_by_key(|airport| {
+            let airport = airport.borrow();
+            (
+                airport.is_closed(),
+                !preferred(airport),
+                data::precedence(airport.kind),
+            )
+        });
+
+        let Some(airport) = best else {
+            continue;
+        };
+        if !airport.borrow().is_closed() && preferred(airport.borrow()) {
+            return Ok(Some(airport));
+        }
+        // An earlier match is kept over a later one, unless the later one is open and the earlier
+        // one isn't.
+        if kept
+            .as_ref()
+            .is_none_or(|kept| kept.borrow().is_closed() && !airport.borrow().is_closed())
+        {
+            kept = Some(airport);
+        }
+    }
+    Ok(kept)
+}
+
+/// True for three letters, the shape of an airline-style IATA code.
+fn is_iata_code(code: &str) -> bool {
+    code.len() == 3 && code.chars().all(|c| c.is_ascii_alphabetic())
+}
@@ -1,19 +1,62 @@
-use std::{iter, process};
+#[cfg(feature = "search")]
+use std::time::Instant;
+use std::{
+    convert::Infallible,
+    fmt::{self, Write as _},
+    fs,
+    io::{self, BufWriter, Write},
+    path::{Path, PathBuf},
+    process,
+    str::FromStr,
+};
 
-mod database;
-mod error;
-mod model;
-mod pairs;
-mod search;
-mod waypoint;
+#[cfg(feature = "search")]
+mod bench;
+mod config;
+mod pager;
 
-use clap::Parser;
-use database::Database;
-use error::Error;
-use hashbrown::HashMap;
-use pairs::Pairs;
+#[cfg(feature = "sqlite")]
+use adb::sqlite::{self, SqliteDatabase};
+use adb::{
+    backup::{self, Locations},
+    data,
+    embedded::Embedded,
+    error::Error,
+    filter::Filter,
+    geo::{self, BoundingBox},
+    local_info::LocalInfo,
+    locale::{format_number, tr, Locale, Message},
+    logbook,
+    logbook::{LogStats, Logbook},
+    lookup::Lookup,
+    model::{Airport, Coords, Country},
+    pairs::Pairs,
+    render::{self, Renderer},
+    route::{self, Route},
+    snapshot, track,
+    track::TrackPoint,
+    units::{DistanceUnit, Meters, NauticalMiles},
+    waypoint::{self, Waypoint, WaypointResolver},
+};
+#[cfg(feature = "search")]
+use adb::{
+    database::{self, Database},
+    diff::DataDiff,
+    model::{AirportKind, AirportSummary},
+    prefixes::PrefixTable,
+    quality::QualityReport,
+    search,
+};
+#[cfg(feature = "search")]
+use clap::CommandFactory;
+use clap::{builder::BoolishValueParser, Parser};
+#[cfg(feature = "search")]
+use clap_complete::{Generator, Shell};
+use serde::Deserialize;
 
-use crate::{model::Coords, waypoint::Waypoint};
+#[cfg(feature = "search")]
+use crate::bench::Bench;
+use crate::config::{Config, SavedSearch};
 
 type Result<T, E = Error> = std::result::Result<T, E>;
 
@@ -23,134 +66,2079 @@ struct Args {
     #[arg(required = true)]
     identifiers: Vec<String>,
 
+    /// print one line per airport
+    #[arg(short, long, group = "display")]
+    short: bool,
+
+    /// print only the runway table
+    #[arg(long, group = "display")]
+    runways: bool,
+
+    /// print only the coordinates, as `lat lon`
+    #[arg(long, group = "display")]
+    coords: bool,
+
+    /// print each airport as a line of JSON, with every field adb knows
+    #[arg(long, group = "display", conflicts_with = "spell")]
+    json: bool,
+
+    /// print the airports as a KML document, for Google Earth
+    #[cfg(feature = "export")]
+    #[arg(long, group = "display", conflicts_with = "spell")]
+    kml: bool,
+
+    /// render airports with an external program, which reads one JSON airport per line on stdin;
+    /// the command is split on whitespace, without shell quoting
+    #[arg(long, env = "ADB_RENDERER")]
+    renderer: Option<String>,
+
+    /// spell each identifier in the ICAO spelling alphabet, e.g. "Kilo Sierra Echo Alpha"
+    #[arg(long)]
+    spell: bool,
+
+    /// treat the identifiers as one batch and summarize how many were found
+    #[arg(long)]
+    any: bool,
+
+    /// maximum number of airports a wildcard pattern like `K0*` may expand to [default: 100]
+    #[arg(long, env = "ADB_LIMIT")]
+    limit: Option<usize>,
+
     #[command(subcommand)]
     command: Option<Command>,
+
+    /// page long output through $PAGER even where the config file says not to
+    #[arg(long, global = true)]
+    pager: bool,
+
+    /// print directly to stdout rather than through $PAGER; `ADB_NO_PAGER=false` pages even where
+    /// the config file says not to
+    #[arg(
+        long,
+        global = true,
+        env = "ADB_NO_PAGER",
+        num_args = 0..=1,
+        require_equals = true,
+        default_missing_value = "true",
+        value_parser = BoolishValueParser::new()
+    )]
+    no_pager: Option<bool>,
+
+    /// directory holding the index; may be a read-only shared directory
+    #[arg(long, global = true, env = "ADB_DATA_DIR")]
+    data_dir: Option<PathBuf>,
+
+    /// where airport data comes from: `index`; `static` for the data built into adb, which needs
+    /// no data directory; or `sqlite` for a single SQLite file, if adb was built with it. Only the
+    /// index supports every command [default: index]
+    #[arg(long, global = true, env = "ADB_BACKEND")]
+    backend: Option<Backend>,
+
+    /// read the latest snapshot kept by `adb update --keep` on or before a date, e.g. `2023-06`
+    #[arg(long, global = true, value_name = "DATE", value_parser = parse_as_of)]
+    as_of: Option<String>,
+
+    /// unit to show distances in: `nm`, `km` or `mi` [default: nm]
+    #[arg(long, global = true, env = "ADB_UNITS")]
+    units: Option<DistanceUnit>,
+
+    /// language for human-readable output, e.g. `en` or `de` [default: from $LANG]
+    #[arg(long, global = true, env = "ADB_LOCALE")]
+    locale: Option<String>,
+
+    /// config file to read settings from
+    #[arg(long, global = true, env = "ADB_CONFIG")]
+    config: Option<PathBuf>,
+
+    /// CSV of your own notes per airport, keyed by an `ident` column [default: local-info.csv in
+    /// the config directory]
+    #[arg(long, global = true, env = "ADB_LOCAL_INFO")]
+    local_info: Option<PathBuf>,
+
+    /// only show airports matching an expression over their fields, e.g. `elevation_ft > 5000 &&
+    /// runways.any(length > 7000)`; applies to lookups and search
+    #[arg(long = "where", global = true, value_name = "EXPR")]
+    filter: Option<Filter>,
+
+    /// when a code names airports in more than one country, take the one in this country, e.g.
+    /// `US`
+    #[arg(long, global = true, value_name = "CODE", env = "ADB_PREFER_COUNTRY")]
+    prefer_country: Option<Country>,
+
+    /// list closed airports and show closed runways, which are left out by default;
+    /// `ADB_INCLUDE_CLOSED=false` leaves them out even where the config file says not to
+    #[arg(
+        long,
+        global = true,
+        env = "ADB_INCLUDE_CLOSED",
+        num_args = 0..=1,
+        require_equals = true,
+        default_missing_value = "true",
+        value_parser = BoolishValueParser::new()
+    )]
+    include_closed: Option<bool>,
 }
 
 #[derive(Debug, Parser)]
 enum Command {
     /// measure distance between airports
     Dist {
-        origin: String,
+        #[arg(required_unless_present_any = ["json_route", "route"])]
+        origin: Option<String>,
         waypoints: Vec<String>,
+
+        /// read the route from a JSON array of waypoints, e.g. `[{"ident": "KSEA"}, {"lat":
+        /// 47.1, "lon": -121.5, "name": "TOD"}]`
+        #[arg(long, conflicts_with = "origin")]
+        json_route: Option<PathBuf>,
+
+        /// fly a route saved with `adb route save`
+        #[arg(long, value_name = "NAME", conflicts_with_all = ["origin", "json_route"])]
+        route: Option<String>,
+
+        /// drop a waypoint that resolves to the same position as the one before it
+        #[arg(long)]
+        collapse: bool,
+
+        /// number the legs and list each waypoint's resolved position
+        #[arg(short, long)]
+        verbose: bool,
+
+        /// print the route's bounding box and geographic center
+        #[arg(long)]
+        bounds: bool,
+
+        /// print the route as a GeoJSON FeatureCollection instead, for geojson.io or Leaflet
+        #[cfg(feature = "export")]
+        #[arg(long, conflicts_with_all = ["verbose", "bounds", "check_altitude"])]
+        geojson: bool,
+
+        /// print the route as a GPX file instead, for GPS units and EFB apps
+        #[cfg(feature = "export")]
+        #[arg(long, conflicts_with_all = ["verbose", "bounds", "check_altitude", "geojson"])]
+        gpx: bool,
+
+        /// print the route as a KML document instead, for Google Earth
+        #[cfg(feature = "export")]
+        #[arg(long, conflicts_with_all = ["verbose", "bounds", "check_altitude", "geojson", "gpx"])]
+        kml: bool,
+
+        /// insert a waypoint at a distance along the route, e.g. `250nm`, or at the point on the
+        /// route closest to a fix
+        #[arg(long, value_name = "DISTANCE|FIX")]
+        split_at: Option<SplitAt>,
+
+        /// insert fuel stops so that no leg is longer than `--max-leg`
+        #[arg(long, requires = "max_leg")]
+        suggest_stops: bool,
+
+        /// longest leg to allow with `--suggest-stops`, e.g. `350nm`
+        #[arg(long, value_name = "DISTANCE", value_parser = parse_nautical_miles)]
+        max_leg: Option<f64>,
+
+        /// shortest usable runway for a fuel stop, in feet
+        #[arg(long, value_name = "FEET", default_value_t = 0)]
+        min_runway: i32,
+
+        /// only stop where local info lists this fuel, e.g. `100LL`
+        #[arg(long, value_name = "GRADE", requires = "suggest_stops")]
+        fuel: Option<String>,
+
+        /// flag legs where this VFR cruising altitude breaks the hemispheric rule
+        #[arg(long, value_name = "FEET")]
+        check_altitude: Option<u32>,
+
+        /// list each leg's magnetic course, with variation from the World Magnetic Model
+        #[cfg(feature = "magnetic")]
+        #[arg(long)]
+        magnetic: bool,
+
+        /// magnetic variation for `--check-altitude`, e.g. `15E` or `4W` [default: each leg's
+        /// variation from the World Magnetic Model with `--magnetic`; otherwise courses are
+        /// checked as true]
+        #[arg(long, value_parser = parse_variation, requires = "check_altitude")]
+        variation: Option<f64>,
     },
 
     /// search airports
     #[command(alias = "find", alias = "s", alias = "f")]
-    Search { query: String },
+    Search {
+        #[arg(required_unless_present_any = ["saved", "bbox"])]
+        query: Option<String>,
+
+        /// only list airports inside a box given by two opposite corners, e.g.
+        /// `47,-123,48.5,-121.5`; without a query, lists every airport in the box
+        #[arg(
+            long,
+            value_name = "LAT,LON,LAT,LON",
+            allow_hyphen_values = true,
+            conflicts_with = "save"
+        )]
+        bbox: Option<BoundingBox>,
+
+        /// match names by how they sound, for names heard but not seen, like "Puyallup"
+        #[arg(long)]
+        phonetic: bool,
+
+        /// forgive typos: words of five letters or more also match words one letter off, so
+        /// "Heathorw" finds Heathrow
+        #[arg(long, conflicts_with = "phonetic")]
+        fuzzy: bool,
+
+        /// treat the query as a regular expression over airport names and towns, e.g.
+        /// `'^Mount .*'`; case is ignored
+        #[arg(long, conflicts_with_all = ["phonetic", "fuzzy"])]
+        regex: bool,
+
+        /// only list airports of entry, marked by an `aoe` column in local info
+        #[arg(long)]
+        aoe: bool,
+
+        /// only list airports with at least one lighted runway
+        #[arg(long)]
+        lighted: bool,
+
+        /// show each airport in full, runways and all, rather than on one line
+        #[arg(long)]
+        full: bool,
+
+        /// rank by how well airports match alone, without putting large and scheduled-service
+        /// airports first
+        #[cfg(feature = "search")]
+        #[arg(long)]
+        no_boost: bool,
+
+        /// only list airports in this country, by its two-letter ISO code, e.g. `US`
+        #[arg(long, value_name = "CODE")]
+        country: Option<Country>,
+
+        /// save the query, its flags and any `--where` filter in the config file under this name
+        #[arg(long, value_name = "NAME")]
+        save: Option<String>,
+
+        /// run a search saved with `--save`; a `--where` filter given here replaces the saved one
+        #[arg(long, value_name = "NAME", conflicts_with_all = ["query", "save"])]
+        saved: Option<String>,
+    },
+
+    /// list the airports nearest to an airport, waypoint or `lat lon` position, with the distance
+    /// and bearing to each
+    #[command(allow_negative_numbers = true, visible_alias = "where")]
+    Nearest {
+        /// an airport or waypoint, or a position as `LAT LON`
+        #[arg(required = true, num_args = 1..=2)]
+        point: Vec<String>,
+
+        /// number of airports to list
+        #[arg(short = 'n', long, default_value_t = 10)]
+        count: usize,
+
+        /// only list airports with at least one lighted runway
+        #[arg(long)]
+        lighted: bool,
+
+        /// only list airports whose local info has this fuel, e.g. `100LL` or `Jet-A`
+        #[arg(long, value_name = "GRADE")]
+        fuel: Option<String>,
+
+        /// only list airports of entry, marked by an `aoe` column in local info
+        #[arg(long)]
+        aoe: bool,
+    },
+
+    /// list every airport within a distance of an airport, waypoint or `lat lon` position
+    #[cfg(feature = "search")]
+    #[command(allow_negative_numbers = true)]
+    Within {
+        point: String,
+
+        /// distance to search within, e.g. `50nm`
+        #[arg(long, value_name = "DISTANCE", value_parser = parse_nautical_miles)]
+        radius: f64,
+
+        /// only list airports of this kind, e.g. `small_airport`; may be given more than once
+        #[arg(long)]
+        kind: Vec<AirportKind>,
+    },
+
+    /// list ICAO identifier prefixes by country
+    #[cfg(feature = "search")]
+    Prefixes { country: Option<String> },
+
+    /// compute a track parallel to the direct one, offset to one side
+    #[command(group = clap::ArgGroup::new("side").required(true))]
+    Offset {
+        origin: String,
+        destination: String,
+
+        /// offset to the right of the direct track, e.g. `5nm`
+        #[arg(long, value_name = "DISTANCE", value_parser = parse_nautical_miles, group = "side")]
+        right: Option<f64>,
+
+        /// offset to the left of the direct track, e.g. `5nm`
+        #[arg(long, value_name = "DISTANCE", value_parser = parse_nautical_miles, group = "side")]
+        left: Option<f64>,
+    },
+
+    /// save and manage named routes
+    Route {
+        #[command(subcommand)]
+        command: RouteCommand,
+    },
+
+    /// read data recorded elsewhere
+    Import {
+        #[command(subcommand)]
+        command: ImportCommand,
+    },
+
+    /// work with a logbook exported as CSV
+    Log {
+        #[command(subcommand)]
+        command: LogCommand,
+    },
+
+    /// inspect and maintain the local database
+    #[cfg(feature = "search")]
+    Db {
+        #[command(subcommand)]
+        command: DbCommand,
+    },
+
+    /// build the index, show where adb keeps its files and check that search works
+    #[cfg(feature = "search")]
+    Init {
+        /// also install completions for this shell
+        #[arg(long, value_name = "SHELL")]
+        completions: Option<Shell>,
+    },
+
+    /// time index open, lookup, search and a long dist, to include when reporting slowness
+    #[cfg(feature = "search")]
+    Bench {
+        /// times to repeat each operation
+        #[arg(long, default_value_t = 10, value_parser = clap::value_parser!(u16).range(1..))]
+        runs: u16,
+    },
 
     /// update database
-    Update,
+    #[cfg(feature = "search")]
+    Update {
+        /// keep a copy of the current index as a snapshot dated today, for use with `--as-of`
+        #[arg(long)]
+        keep: bool,
+    },
+}
+
+#[derive(Debug, Parser)]
+enum RouteCommand {
+    /// save a route under a name, for use with `adb dist --route NAME`
+    Save {
+        name: String,
+        #[arg(required = true, num_args = 2..)]
+        waypoints: Vec<String>,
+    },
+
+    /// compare two routes, each either a saved route or comma-separated waypoints like
+    /// `KSEA,KYKM,KPDX`
+    Diff { a: String, b: String },
+}
+
+#[derive(Debug, Parser)]
+enum ImportCommand {
+    /// work out the airports visited on a recorded GPX or KML track and compare the distance
+    /// flown with the direct route
+    Track { path: PathBuf },
+}
+
+#[derive(Debug, Parser)]
+enum LogCommand {
+    /// append the distance of each flight to a logbook
+    Annotate {
+        path: PathBuf,
+
+        #[command(flatten)]
+        columns: LogColumns,
+
+        /// write the annotated logbook here instead of to stdout
+        #[arg(short, long)]
+        output: Option<PathBuf>,
+    },
+
+    /// summarize a logbook: distance flown, longest leg, and the airports and countries visited
+    Stats {
+        path: PathBuf,
+
+        #[command(flatten)]
+        columns: LogColumns,
+    },
+}
+
+#[derive(Debug, clap::Args)]
+struct LogColumns {
+    /// column holding the departure airport, counting from 1
+    #[arg(long, value_parser = clap::value_parser!(u16).range(1..))]
+    from_col: u16,
+
+    /// column holding the arrival airport, counting from 1
+    #[arg(long, value_parser = clap::value_parser!(u16).range(1..))]
+    to_col: u16,
+
+    /// the first row is a flight rather than column names
+    #[arg(long)]
+    no_headers: bool,
+}
+
+impl LogColumns {
+    fn read(&self, path: &Path) -> Result<(Logbook, logbook::Columns)> {
+        let columns = logbook::Columns {
+            from: usize::from(self.from_col) - 1,
+            to: usize::from(self.to_col) - 1,
+        };
+        Ok((Logbook::read(path, !self.no_headers)?, columns))
+    }
+}
+
+#[cfg(feature = "search")]
+#[derive(Debug, Parser)]
+enum DbCommand {
+    /// report missing data and the distribution of runway lengths
+    Quality,
+
+    /// show how much disk space adb is using
+    Size {
+        /// merge index segments to reclaim space first
+        #[arg(long)]
+        compact: bool,
+    },
+
+    /// remove the index and cached data
+    Clean {
+        /// also remove configuration and user data
+        #[arg(long)]
+        all: bool,
+    },
+
+    /// save the config file, local info and index snapshots to an archive, e.g. `adb.tar.zst`,
+    /// for moving them to another machine
+    Backup { path: PathBuf },
+
+    /// put back the files saved by `adb db backup`, replacing any already there
+    Restore { path: PathBuf },
+}
+
+/// Where airports are looked up.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Deserialize)]
+#[serde(try_from = "String")]
+enum Backend {
+    /// the tantivy index in the data directory, built on first use
+    #[cfg(feature = "search")]
+    #[default]
+    Index,
+
+    /// the data compiled into adb, parsed into memory on each run; the only backend, short of
+    /// SQLite, in a build without the `search` feature
+    #[cfg_attr(not(feature = "search"), default)]
+    Static,
+
+    /// a SQLite file in the data directory, built on first use
+    #[cfg(feature = "sqlite")]
+    Sqlite,
+}
+
+impl FromStr for Backend {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.to_ascii_lowercase().as_str() {
+            #[cfg(feature = "search")]
+            "index" => Ok(Backend::Index),
+            #[cfg(not(feature = "search"))]
+            "index" => Err(tr(Message::NoSearch).into()),
+            "static" => Ok(Backend::Static),
+            #[cfg(feature = "sqlite")]
+            "sqlite" => Ok(Backend::Sqlite),
+            #[cfg(not(feature = "sqlite"))]
+            "sqlite" => Err(tr(Message::NoSqlite).into()),
+            _ => Err(tr(Message::BadBackend).into()),
+        }
+    }
+}
+
+impl TryFrom<String> for Backend {
+    type Error = String;
+
+    fn try_from(s: String) -> Result<Self, Self::Error> {
+        s.parse()
+    }
+}
+
+/// Where `dist --split-at` divides a route.
+#[derive(Clone, Debug)]
+enum SplitAt {
+    /// nautical miles from the origin
+    Distance(f64),
+    Fix(String),
+}
+
+impl FromStr for SplitAt {
+    type Err = Infallible;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let distance = s
+            .strip_suffix("nm")
+            .and_then(|nm| parse_nautical_miles(nm).ok());
+        Ok(match distance {
+            Some(nm) => SplitAt::Distance(nm),
+            None => SplitAt::Fix(s.into()),
+        })
+    }
+}
+
+/// Parses a magnetic variation like `15E` or `4.5W`, east positive.
+fn parse_variation(s: &str) -> Result<f64, String> {
+    let bad = || tr(Message::BadVariation).to_string();
+    let s = s.trim();
+    let (degrees, sign) = match s.chars().last().map(|c| c.to_ascii_uppercase()) {
+        Some('E') => (&s[..s.len() - 1], 1.0),
+        Some('W') => (&s[..s.len() - 1], -1.0),
+        _ => (s, 1.0),
+    };
+    let degrees: f64 = degrees.trim().parse().map_err(|_| bad())?;
+    if degrees.abs() <= 180.0 {
+        Ok(sign * degrees)
+    } else {
+        Err(bad())
+    }
+}
+
+fn parse_as_of(s: &str) -> Result<String, String> {
+    if snapshot::is_date_prefix(s) {
+        Ok(s.into())
+    } else {
+        Err(tr(Message::BadDate).into())
+    }
+}
+
+/// Parses a distance like `350nm` or `350`.
+fn parse_nautical_miles(s: &str) -> Result<f64, String> {
+    s.strip_suffix("nm")
+        .unwrap_or(s)
+        .trim()
+        .parse()
+        .ok()
+        .filter(|nm: &f64| nm.is_finite() && *nm > 0.0)
+        .ok_or_else(|| format!("{}: {s}", tr(Message::BadDistance)))
+}
+
+impl fmt::Display for SplitAt {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            SplitAt::Distance(nm) => write!(f, "{nm}nm"),
+            SplitAt::Fix(fix) => f.write_str(fix),
+        }
+    }
 }
 
 fn main() {
-    if let Err(e) = run(&Args::parse()) {
-        eprintln!("{e}");
-        process::exit(1);
+    match run(&Args::parse()) {
+        // Whoever was reading stopped, as when the pager is quit early or output is piped into
+        // `head`. That's not an error from our point of view.
+        Err(Error::IO(e)) if e.kind() == io::ErrorKind::BrokenPipe => {}
+        Err(e) => {
+            eprintln!("{e}");
+            process::exit(1);
+        }
+        Ok(()) => {}
     }
 }
 
 fn run(args: &Args) -> Result<()> {
+    // Without a home directory there's no default config file or local info, which is fine until
+    // something needs to be saved; the index, though, has to go somewhere.
+    let default_dirs = data::project_dirs().ok();
+    let config_path = match &args.config {
+        Some(path) => Some(path.clone()),
+        None => default_dirs
+            .as_ref()
+            .map(|dirs| dirs.config_dir().join("config.toml")),
+    };
+    let config = match &config_path {
+        Some(path) => Config::load(path)?,
+        None => Config::default(),
+    };
+    let saved_config_path = || config_path.as_deref().ok_or(Error::NoHomeDirectory);
+
+    if let Some(tag) = args.locale.as_ref().or(config.locale.as_ref()) {
+        Locale::set(Locale::from_tag(tag).ok_or_else(|| Error::UnknownLocale(tag.clone()))?)?;
+    }
+
+    // Only asked for by backends and commands that keep files, so that `--backend static` works
+    // without a home directory.
+    #[cfg(any(feature = "search", feature = "sqlite"))]
+    let data_dir = || -> Result<PathBuf> {
+        match args.data_dir.as_ref().or(config.data_dir.as_ref()) {
+            Some(path) => Ok(path.clone()),
+            None => Ok(data::project_dirs()?.data_dir().into()),
+        }
+    };
+    let prefer_country = args.prefer_country.or(config.prefer_country);
+    #[cfg(feature = "search")]
+    let open_database = || -> Result<Database> {
+        let mut db = match &args.as_of {
+            Some(as_of) => Database::open_snapshot(&snapshot::find(&data_dir()?, as_of)?)?,
+            None => Database::initialize(&data_dir()?)?,
+        };
+        db.set_prefer_country(prefer_country);
+        Ok(db)
+    };
+    let local_info_path = match args.local_info.as_ref().or(config.local_info.as_ref()) {
+        Some(path) => Some(path.clone()),
+        None => default_dirs
+            .as_ref()
+            .map(|dirs| dirs.config_dir().join("local-info.csv")),
+    };
+    let local_info = || match &local_info_path {
+        Some(path) => LocalInfo::load(path),
+        None => Ok(LocalInfo::default()),
+    };
+    // `--pager` is a flag, so it beats `ADB_NO_PAGER`, which in turn beats the config file.
+    let pager = match (args.pager, args.no_pager) {
+        (true, _) => true,
+        (false, Some(no_pager)) => !no_pager,
+        (false, None) => config.pager.unwrap_or(true),
+    };
+    let unit = args.units.or(config.units).unwrap_or_default();
+    let mut out = pager::Output::new(pager);
+    #[cfg(feature = "search")]
+    let limit = args.limit.or(config.limit).unwrap_or(100);
+
+    let backend = args.backend.or(config.backend).unwrap_or_default();
+    let include_closed = args
+        .include_closed
+        .or(config.include_closed)
+        .unwrap_or(false);
+    let open_lookup = || -> Result<Box<dyn Lookup>> {
+        Ok(match backend {
+            #[cfg(feature = "search")]
+            Backend::Index => Box::new(open_database()?),
+            Backend::Static => {
+                let mut data = Embedded::load();
+                data.set_prefer_country(prefer_country);
+                Box::new(data)
+            }
+            #[cfg(feature = "sqlite")]
+            Backend::Sqlite => {
+                let mut db = SqliteDatabase::open(&sqlite::path(&data_dir()?))?;
+                db.set_prefer_country(prefer_country);
+                Box::new(db)
+            }
+        })
+    };
+
     if let Some(command) = &args.command {
+        // Search works with any backend, if only for plain text.
+        #[cfg(feature = "search")]
+        let needs_index = matches!(
+            command,
+            Command::Within { .. }
+                | Command::Prefixes { .. }
+                | Command::Db { .. }
+                | Command::Init { .. }
+                | Command::Bench { .. }
+                | Command::Update { .. }
+        );
+        // Snapshots are of the index, so only a plain update has a meaning for SQLite.
+        #[cfg(all(feature = "search", feature = "sqlite"))]
+        let needs_index = needs_index
+            && !(backend == Backend::Sqlite && matches!(command, Command::Update { keep: false }));
+        #[cfg(feature = "search")]
+        if backend != Backend::Index && needs_index {
+            return Err(Error::NeedsIndex);
+        }
+
         match command {
-            Command::Dist { origin, waypoints } => {
-                print_distance(origin, waypoints)?;
+            Command::Dist {
+                origin,
+                waypoints,
+                json_route,
+                route,
+                collapse,
+                verbose,
+                bounds,
+                #[cfg(feature = "export")]
+                geojson,
+                #[cfg(feature = "export")]
+                gpx,
+                #[cfg(feature = "export")]
+                kml,
+                split_at,
+                suggest_stops,
+                max_leg,
+                min_runway,
+                fuel,
+                check_altitude,
+                #[cfg(feature = "magnetic")]
+                magnetic,
+                variation,
+            } => {
+                let source = open_lookup()?;
+                let resolver = resolver(&*source, &config, config_path.as_deref())?;
+                let route = match (json_route, route) {
+                    (Some(path), _) => waypoint::read_json_route(path, &resolver)?,
+                    (None, Some(name)) => config
+                        .routes
+                        .get(name)
+                        .ok_or_else(|| Error::UnknownRoute(name.clone()))?
+                        .iter()
+                        .map(|identifier| resolver.resolve(identifier))
+                        .collect::<Result<_>>()?,
+                    (None, None) => origin
+                        .iter()
+                        .chain(waypoints)
+                        .map(|identifier| resolver.resolve(identifier))
+                        .collect::<Result<_>>()?,
+                };
+                let mut route = check_zero_length_legs(route, *collapse);
+                let stops = match max_leg.filter(|_| *suggest_stops) {
+                    Some(max_leg) => {
+                        let fuel_stops = match fuel {
+                            Some(grade) => {
+                                let info = local_info()?;
+                                let keep = |ident: &str| info.has_fuel(ident, grade);
+                                Some(local_airports(&*source, &info, keep)?)
+                            }
+                            None => None,
+                        };
+                        let planned = route::length(&route);
+                        let stops;
+                        (route, stops) = route::insert_fuel_stops(
+                            &*source,
+                            route,
+                            max_leg,
+                            *min_runway,
+                            fuel_stops.as_deref(),
+                        )?;
+                        Some((stops, route::length(&route) - planned))
+                    }
+                    None => None,
+                };
+                let split = match split_at {
+                    Some(split_at) => Some(split_route(&resolver, &mut route, split_at)?),
+                    None => None,
+                };
+                let route = Route::new(route);
+                #[cfg(feature = "export")]
+                let exported = export_route(&route, *geojson, *gpx, *kml);
+                #[cfg(not(feature = "export"))]
+                let exported: Option<String> = None;
+
+                if let Some(exported) = exported {
+                    out.write_all(exported.as_bytes())?;
+                } else {
+                    render::print_distance(&route, *verbose, unit, &mut out)?;
+                    // A variation given outright applies to every leg.
+                    let variations = variation.map(|variation| vec![variation; route.legs.len()]);
+                    #[cfg(feature = "magnetic")]
+                    let variations = if *magnetic {
+                        let today = snapshot::today();
+                        let modeled: Vec<_> = route
+                            .legs
+                            .iter()
+                            .map(|leg| geo::variation(&route.ends(leg).0.coordinates(), today))
+                            .collect();
+                        render::print_magnetic_courses(&route, &modeled, &mut out)?;
+
+                        // Checked as magnetic only if the model covers every leg.
+                        variations.or_else(|| modeled.into_iter().collect())
+                    } else {
+                        variations
+                    };
+                    if let Some(altitude) = check_altitude {
+                        render::print_altitude_check(
+                            &route,
+                            *altitude,
+                            variations.as_deref(),
+                            &mut out,
+                        )?;
+                    }
+                    if let Some((stops, added)) = stops {
+                        render::print_fuel_stops(&stops, added, unit, &mut out)?;
+                    }
+                    if let Some((coords, along)) = split {
+                        print_split(&*source, &coords, along, unit, &mut out)?;
+                    }
+                    if *bounds {
+                        render::print_bounds(&route.waypoints, &mut out)?;
+                    }
+                }
+            }
+            Command::Search {
+                query,
+                bbox,
+                phonetic,
+                fuzzy,
+                regex,
+                aoe,
+                lighted,
+                full,
+                #[cfg(feature = "search")]
+                no_boost,
+                country,
+                save,
+                saved,
+            } => {
+                #[cfg(feature = "search")]
+                let no_boost = *no_boost;
+                #[cfg(not(feature = "search"))]
+                let no_boost = false;
+                let mut search = SavedSearch {
+                    query: query.clone().unwrap_or_default(),
+                    filter: None,
+                    phonetic: *phonetic,
+                    fuzzy: *fuzzy,
+                    regex: *regex,
+                    country: *country,
+                    aoe: *aoe,
+                    lighted: *lighted,
+                    no_boost,
+                };
+                let mut filter = args.filter.clone();
+
+                // Flags given here add to the saved ones, and a filter or country replaces theirs.
+                if let Some(name) = saved {
+                    let saved = config
+                        .searches
+                        .get(name)
+                        .ok_or_else(|| Error::UnknownSearch(name.clone()))?;
+                    if filter.is_none() {
+                        filter = saved
+                            .filter
+                            .as_deref()
+                            .map(str::parse)
+                            .transpose()
+                            .map_err(|e| {
+                                Error::Config(
+                                    config_path.clone().unwrap_or_default(),
+                                    format!("{e}"),
+                                )
+                            })?;
+                    }
+                    search.query = saved.query.clone();
+                    search.phonetic |= saved.phonetic;
+                    search.fuzzy |= saved.fuzzy;
+                    search.regex |= saved.regex;
+                    search.country = search.country.or(saved.country);
+                    search.aoe |= saved.aoe;
+                    search.lighted |= saved.lighted;
+                    search.no_boost |= saved.no_boost;
+                }
+
+                if let Some(name) = save {
+                    search.filter = filter.as_ref().map(Filter::to_string);
+                    Config::save_search(saved_config_path()?, name, &search)?;
+                }
+                let SavedSearch {
+                    query,
+                    phonetic,
+                    fuzzy,
+                    regex,
+                    country,
+                    aoe,
+                    lighted,
+                    #[cfg(feature = "search")]
+                    no_boost,
+                    ..
+                } = search;
+
+                let info = aoe.then(local_info).transpose()?;
+                let keep = |airport: &Airport| {
+                    (include_closed || !airport.is_closed())
+                        && filter.as_ref().is_none_or(|filter| filter.matches(airport))
+                        && info
+                            .as_ref()
+                            .is_none_or(|info| info.is_set(&airport.ident, "aoe"))
+                        && bbox.is_none_or(|bbox| bbox.contains(&airport.coordinates))
+                        && country.is_none_or(|country| airport.iso_country == country)
+                        && (!lighted || airport.has_lighted_runway())
+                };
+
+                // Closed airports, airports of entry and those in the box can be told from their
+                // summaries; anything else needs whole airports.
+                #[cfg(feature = "search")]
+                let keep_summary = |airport: &AirportSummary| {
+                    (include_closed || airport.kind != AirportKind::Closed)
+                        && info
+                            .as_ref()
+                            .is_none_or(|info| info.is_set(&airport.ident, "aoe"))
+                        && bbox.is_none_or(|bbox| bbox.contains(&airport.coordinates))
+                };
+                let mode = match (phonetic, fuzzy, regex) {
+                    (true, _, _) => SearchMode::Phonetic,
+                    (_, true, _) => SearchMode::Fuzzy,
+                    (_, _, true) => SearchMode::Regex,
+                    _ => SearchMode::Text,
+                };
+                #[cfg(feature = "search")]
+                let summarized = !full
+                    && mode == SearchMode::Text
+                    && !lighted
+                    && filter.is_none()
+                    && country.is_none();
+
+                let airports = match (backend, bbox) {
+                    #[cfg(feature = "search")]
+                    (Backend::Index, Some(bbox)) if query.is_empty() => {
+                        let mut airports = open_database()?.in_bounds(bbox)?;
+                        airports.retain(keep);
+                        airports
+                    }
+                    #[cfg(feature = "search")]
+                    (Backend::Index, _) if summarized => {
+                        let mut db = open_database()?;
+                        db.set_boost(!no_boost);
+                        print_search_summaries(&db, &query, &keep_summary, &mut out)?;
+                        Vec::new()
+                    }
+                    #[cfg(feature = "search")]
+                    (Backend::Index, _) => {
+                        let mut db = open_database()?;
+                        db.set_boost(!no_boost);
+                        search_index(&db, &query, country.as_ref(), &keep, mode)?
+                    }
+                    (_, Some(_)) if query.is_empty() => return Err(Error::NeedsIndex),
+                    _ if mode != SearchMode::Text => return Err(Error::NeedsIndex),
+                    _ => search_simple(&*open_lookup()?, &query, &keep)?,
+                };
+                print_search_results(airports, *full, include_closed, &mut out)?;
+            }
+            Command::Nearest {
+                point,
+                count,
+                lighted,
+                fuel,
+                aoe,
+            } => {
+                let source = open_lookup()?;
+                let origin = resolver(&*source, &config, config_path.as_deref())?
+                    .resolve(&point.join(" "))?;
+                let coords = origin.coordinates();
+
+                // The airport itself, if that's what was given, is no use in the list.
+                let keep = |airport: &Airport| {
+                    !is_origin(&origin, airport)
+                        && (include_closed || !airport.is_closed())
+                        && (!lighted || airport.has_lighted_runway())
+                };
+
+                // Local info is short enough to measure to every airport in it, and a local info
+                // filter may leave none of the nearest candidates.
+                let info = (fuel.is_some() || *aoe).then(local_info).transpose()?;
+                let airports = match &info {
+                    Some(info) => {
+                        let listed = |ident: &str| {
+                            fuel.as_ref()
+                                .is_none_or(|grade| info.has_fuel(ident, grade))
+                                && (!aoe || info.is_set(ident, "aoe"))
+                        };
+                        let mut airports = local_airports(&*source, info, listed)?;
+                        airports.retain(keep);
+                        airports.sort_by(|a, b| {
+                            let a = coords.distance_to(&a.coordinates).meters();
+                            let b = coords.distance_to(&b.coordinates).meters();
+                            a.total_cmp(&b)
+                        });
+                        airports.truncate(*count);
+                        airports
+                    }
+                    None => nearest_kept(&*source, &coords, *count, keep)?,
+                };
+                render::print_nearest(&coords, airports, unit, &mut out)?;
+            }
+            #[cfg(feature = "search")]
+            Command::Within {
+                point,
+                radius,
+                kind,
+            } => {
+                let db = open_database()?;
+                let origin = resolver(&db, &config, config_path.as_deref())?.resolve(point)?;
+                let coords = origin.coordinates();
+                let mut airports = db.within(&coords, NauticalMiles(*radius).to_meters())?;
+                airports.retain(|airport| {
+                    !is_origin(&origin, airport)
+                        && (include_closed || !airport.is_closed())
+                        && (kind.is_empty() || kind.contains(&airport.kind))
+                });
+                render::print_nearest(&coords, airports, unit, &mut out)?;
             }
-            Command::Search { query } => print_search(query)?,
-            Command::Update => {
-                search::initialize(true)?;
+            #[cfg(feature = "search")]
+            Command::Prefixes { country } => {
+                let db = open_database()?;
+                print_prefixes(&db, country.as_deref(), &mut out)?;
+            }
+            Command::Offset {
+                origin,
+                destination,
+                right,
+                left,
+            } => {
+                let source = open_lookup()?;
+                let resolver = resolver(&*source, &config, config_path.as_deref())?;
+                let origin = resolver.resolve(origin)?;
+                let destination = resolver.resolve(destination)?;
+
+                // Right is positive; clap makes sure exactly one side is given.
+                let offset = right.or(left.map(|nm| -nm)).unwrap_or(0.0);
+                render::print_offset(&origin, &destination, offset, unit, &mut out)?;
+            }
+            Command::Route { command } => match command {
+                RouteCommand::Save { name, waypoints } => {
+                    // Resolve everything now so a typo is caught when saving rather than when
+                    // flying the route.
+                    let source = open_lookup()?;
+                    let resolver = resolver(&*source, &config, config_path.as_deref())?;
+                    for identifier in waypoints {
+                        resolver.resolve(identifier)?;
+                    }
+                    Config::save_route(saved_config_path()?, name, waypoints)?;
+                    writeln!(out, "{} {name}", tr(Message::Saved))?;
+                }
+                RouteCommand::Diff { a, b } => {
+                    let source = open_lookup()?;
+                    let resolver = resolver(&*source, &config, config_path.as_deref())?;
+                    let route_a = resolve_route(&resolver, &config, a)?;
+                    let route_b = resolve_route(&resolver, &config, b)?;
+                    render::print_route_diff((a, &route_a), (b, &route_b), unit, &mut out)?;
+                }
+            },
+            Command::Import { command } => match command {
+                ImportCommand::Track { path } => {
+                    let source = open_lookup()?;
+                    print_track(&*source, &track::read_track(path)?, unit, &mut out)?;
+                }
+            },
+            Command::Log { command } => match command {
+                LogCommand::Annotate {
+                    path,
+                    columns,
+                    output,
+                } => {
+                    let source = open_lookup()?;
+                    let resolver = resolver(&*source, &config, config_path.as_deref())?;
+                    let (logbook, columns) = columns.read(path)?;
+                    match output {
+                        Some(output) => {
+                            let file = BufWriter::new(fs::File::create(output)?);
+                            logbook.write_annotated(&resolver, columns, unit, file)?;
+                        }
+                        None => logbook.write_annotated(&resolver, columns, unit, &mut out)?,
+                    }
+                }
+                LogCommand::Stats { path, columns } => {
+                    let source = open_lookup()?;
+                    let resolver = resolver(&*source, &config, config_path.as_deref())?;
+                    let (logbook, columns) = columns.read(path)?;
+                    let stats = LogStats::from_legs(logbook.legs(&resolver, columns), unit);
+                    write!(out, "{stats}")?;
+                }
+            },
+            #[cfg(feature = "search")]
+            Command::Db { command } => match command {
+                DbCommand::Quality => {
+                    let db = open_database()?;
+                    write!(out, "{}", QualityReport::from_airports(db.scan()))?;
+                }
+                DbCommand::Size { compact } => print_size(&data_dir()?, *compact, &mut out)?,
+                DbCommand::Clean { all } => clean(&data_dir()?, *all, &mut out)?,
+                DbCommand::Backup { path } => {
+                    let data_dir = data_dir()?;
+                    let locations = Locations {
+                        config: config_path.as_deref(),
+                        local_info: local_info_path.as_deref(),
+                        data_dir: &data_dir,
+                    };
+                    for saved in backup::backup(&locations, path)? {
+                        writeln!(out, "{} {}", tr(Message::BackedUp), saved.display())?;
+                    }
+                }
+                DbCommand::Restore { path } => {
+                    let data_dir = data_dir()?;
+                    let locations = Locations {
+                        config: config_path.as_deref(),
+                        local_info: local_info_path.as_deref(),
+                        data_dir: &data_dir,
+                    };
+                    for restored in backup::restore(&locations, path)? {
+                        writeln!(out, "{} {}", tr(Message::Restored), restored.display())?;
+                    }
+                }
+            },
+            #[cfg(feature = "search")]
+            Command::Init { completions } => {
+                init(
+                    &data_dir()?,
+                    config_path.as_deref(),
+                    local_info_path.as_deref(),
+                    &mut out,
+                )?;
+                if let Some(shell) = completions {
+                    let path = install_completions(*shell)?;
+                    let label = tr(Message::Completions);
+                    writeln!(out, "{label:<12} {}", path.display())?;
+                }
+            }
+            #[cfg(feature = "search")]
+            Command::Bench { runs } => {
+                let bench = Bench::run(&data_dir()?, usize::from(*runs))?;
+                write!(out, "{bench}")?;
+            }
+            #[cfg(all(feature = "search", feature = "sqlite"))]
+            Command::Update { .. } if backend == Backend::Sqlite => {
+                SqliteDatabase::rebuild(&sqlite::path(&data_dir()?))?;
+            }
+            #[cfg(feature = "search")]
+            Command::Update { keep } => {
+                let data_dir = data_dir()?;
+                if *keep {
+                    if let Some(path) = snapshot::keep(&data_dir)? {
+                        writeln!(out, "{} {}", tr(Message::KeptSnapshot), path.display())?;
+                    }
+                }
+
+                // Read the old records straight from the old index, whatever its schema; opened
+                // the usual way, an index from an older version of adb would be rebuilt before we
+                // got to see it, and opened as a snapshot it would be refused.
+                let previous = search::stored_records(&data_dir)?;
+                search::initialize(&data_dir, true)?;
+                if let Some(previous) = previous {
+                    let db = Database::initialize(&data_dir)?;
+                    write!(out, "{}", DataDiff::new(previous, db.scan()))?;
+                }
             }
         }
-        return Ok(());
-    }
+    } else {
+        let mut renderer: Box<dyn Renderer> =
+            match args.renderer.as_ref().or(config.renderer.as_ref()) {
+                _ if args.short => Box::new(render::Short),
+                _ if args.runways => Box::new(render::Runways),
+                _ if args.coords => Box::new(render::Coords),
+                _ if args.json => Box::new(render::Json),
+                #[cfg(feature = "export")]
+                _ if args.kml => Box::new(render::Kml::default()),
+                Some(command) => Box::new(render::External::new(command)),
+                None => Box::new(render::WithLocalInfo(Box::new(render::Full), local_info()?)),
+            };
+        if args.spell {
+            renderer = Box::new(render::Spelled(renderer));
+        }
 
-    let db = Database::initialize()?;
-    for identifier in &args.identifiers {
-        match db.by_identifier(identifier)? {
-            Some(airport) => {
-                println!("{airport}");
+        match backend {
+            #[cfg(feature = "search")]
+            Backend::Index => {
+                let db = open_database()?;
+                print_lookup(&db, args, include_closed, &mut *renderer, limit, &mut out)?;
             }
-            None => {
-                eprintln!("{identifier} not found");
+            _ => print_simple_lookup(
+                &*open_lookup()?,
+                args,
+                include_closed,
+                &mut *renderer,
+                &mut out,
+            )?,
+        }
+    }
+
+    Ok(())
+}
+
+#[cfg(feature = "search")]
+/// Builds the index if need be, reports where adb keeps its files, and checks that an airport can
+/// be found by searching for its own name.
+fn init(
+    data_dir: &Path,
+    config_path: Option<&Path>,
+    local_info_path: Option<&Path>,
+    out: &mut dyn Write,
+) -> Result<()> {
+    let start = Instant::now();
+    let db = Database::initialize(data_dir)?;
+    let elapsed = start.elapsed().as_secs_f64();
+
+    let missing = format!("({})", tr(Message::NotFound));
+    let note = |path: &Path| {
+        if path.exists() {
+            String::new()
+        } else {
+            missing.clone()
+        }
+    };
+    let airports = format!(
+        "({} {}, {}s)",
+        format_number(db.airport_count() as f64, 0),
+        tr(Message::AirportCount),
+        format_number(elapsed, 1)
+    );
+    let rows = [
+        (Message::Index, Some(data_dir), airports),
+        (
+            Message::Config,
+            config_path,
+            config_path.map(note).unwrap_or_default(),
+        ),
+        (
+            Message::LocalInfoFile,
+            local_info_path,
+            local_info_path.map(note).unwrap_or_default(),
+        ),
+    ];
+    for (label, path, note) in rows {
+        let Some(path) = path else {
+            continue;
+        };
+        let line = format!("{:<12} {}  {note}", tr(label), path.display());
+        writeln!(out, "{}", line.trim_end())?;
+    }
+
+    let sample: Airport = db
+        .scan()
+        .next()
+        .ok_or_else(|| Error::SearchCheck(String::new()))?;
+    let found = db
+        .search(&sample.name, 25)?
+        .iter()
+        .any(|airport| airport.ident == sample.ident)
+        && db.by_identifier(&sample.ident)?.is_some();
+    if !found {
+        return Err(Error::SearchCheck(sample.ident));
+    }
+    writeln!(
+        out,
+        "{:<12} {} ({} {})",
+        tr(Message::Search),
+        tr(Message::Ok),
+        sample.ident,
+        sample.name
+    )?;
+
+    Ok(())
+}
+
+#[cfg(feature = "search")]
+/// Writes completions for `shell` where the shell looks for them by itself. Shells without a
+/// per-user completion directory get a file in the config directory to source from their profile.
+fn install_completions(shell: Shell) -> Result<PathBuf> {
+    let base = directories::BaseDirs::new().ok_or(Error::NoHomeDirectory)?;
+    let path = match shell {
+        Shell::Bash => base.data_dir().join("bash-completion/completions/adb"),
+        Shell::Fish => base.config_dir().join("fish/completions/adb.fish"),
+        Shell::Zsh => base.home_dir().join(".zfunc/_adb"),
+        _ => data::project_dirs()?
+            .config_dir()
+            .join("completions")
+            .join(shell.file_name("adb")),
+    };
+
+    if let Some(parent) = path.parent() {
+        fs::create_dir_all(parent)?;
+    }
+    let mut file = fs::File::create(&path)?;
+    clap_complete::generate(shell, &mut Args::command(), "adb", &mut file);
+    Ok(path)
+}
+
+#[cfg(feature = "search")]
+fn print_size(data_dir: &Path, compact: bool, out: &mut dyn Write) -> Result<()> {
+    // Only look at what's there: sizing up the index shouldn't download and build one.
+    let db = Database::open_existing(data_dir)?;
+    if let (true, Some(db)) = (compact, &db) {
+        db.compact()?;
+    }
+
+    let dirs = data::project_dirs().ok();
+    let index = match &db {
+        Some(db) => format!("({} {})", db.segment_count(), tr(Message::Segments)),
+        None => format!("({})", tr(Message::NotBuilt)),
+    };
+    let rows = [
+        (Message::Index, Some(data_dir), index),
+        (
+            Message::Cache,
+            dirs.as_ref().map(|dirs| dirs.cache_dir()),
+            String::new(),
+        ),
+        (
+            Message::Config,
+            dirs.as_ref().map(|dirs| dirs.config_dir()),
+            String::new(),
+        ),
+    ];
+
+    for (label, path, note) in rows {
+        let Some(path) = path else {
+            continue;
+        };
+        let line = format!(
+            "{:<8} {:>10}  {}  {note}",
+            tr(label),
+            format_size(dir_size(path)?),
+            path.display()
+        );
+        writeln!(out, "{}", line.trim_end())?;
+    }
+
+    Ok(())
+}
+
+#[cfg(feature = "search")]
+fn dir_size(path: &Path) -> io::Result<u64> {
+    if !path.exists() {
+        return Ok(0);
+    }
+
+    let mut size = 0;
+    for entry in fs::read_dir(path)? {
+        let entry = entry?;
+        let metadata = entry.metadata()?;
+        size += if metadata.is_dir() {
+            dir_size(&entry.path())?
+        } else {
+            metadata.len()
+        };
+    }
+    Ok(size)
+}
+
+#[cfg(feature = "search")]
+fn format_size(bytes: u64) -> String {
+    const UNITS: [&str; 4] = ["B", "KiB", "MiB", "GiB"];
+
+    let mut size = bytes as f64;
+    let mut unit = 0;
+    while size >= 1024.0 && unit < UNITS.len() - 1 {
+        size /= 1024.0;
+        unit += 1;
+    }
+
+    let precision = if unit == 0 { 0 } else { 1 };
+    format!("{} {}", format_number(size, precision), UNITS[unit])
+}
+
+#[cfg(feature = "search")]
+/// Removes the directories adb made for itself. A data directory given with `--data-dir` may be
+/// shared, or hold more than adb put there, so that one is left for its owner to remove.
+fn clean(data_dir: &Path, all: bool, out: &mut dyn Write) -> Result<()> {
+    let dirs = data::project_dirs()?;
+    if data_dir != dirs.data_dir() {
+        return Err(Error::CustomDataDir(data_dir.into()));
+    }
+
+    // On some platforms, macOS among them, data and settings share a directory. Then only the
+    // index and the directories kept inside it go, unless settings are meant to go too.
+    let config_dir = dirs.config_dir();
+    let mut paths = Vec::new();
+    for dir in [data_dir, dirs.cache_dir()] {
+        if all || !config_dir.starts_with(dir) {
+            paths.push(dir.to_path_buf());
+        } else if dir == data_dir && dir.exists() {
+            for entry in fs::read_dir(dir)? {
+                let name = entry?.file_name();
+                if name.to_str().is_some_and(database::is_index_file) {
+                    paths.push(dir.join(name));
+                }
             }
+            paths.push(snapshot::dir(dir));
+            #[cfg(feature = "sqlite")]
+            paths.push(sqlite::path(dir).parent().unwrap().to_path_buf());
+        }
+    }
+    if all {
+        paths.push(config_dir.to_path_buf());
+    }
+
+    for path in paths {
+        if path.is_dir() {
+            fs::remove_dir_all(&path)?;
+        } else if path.exists() {
+            fs::remove_file(&path)?;
+        } else {
+            continue;
         }
+        writeln!(out, "{} {}", tr(Message::Removed), path.display())?;
     }
 
     Ok(())
 }
 
-fn print_distance<T: AsRef<str>>(origin: &T, waypoints: &[T]) -> Result<()> {
-    const METERS_PER_NAUTICAL_MILE: f64 = 1852.001;
+#[cfg(feature = "search")]
+fn print_lookup(
+    db: &Database,
+    args: &Args,
+    include_closed: bool,
+    renderer: &mut dyn Renderer,
+    limit: usize,
+    out: &mut dyn Write,
+) -> Result<()> {
+    let mut prefixes = None;
+    let (identifiers, batch) = lookup_identifiers(args);
+    let mut missing = Vec::new();
+    let mut filtered = Vec::new();
 
-    let db = Database::initialize()?;
-    let cache: HashMap<_, Waypoint> = iter::once(origin)
-        .chain(waypoints)
-        .map(|text| text.as_ref())
-        .filter_map(|identifier| {
-            if let Some(waypoint) = db.by_identifier(identifier).ok().flatten() {
-                return Some((identifier, waypoint.into()));
+    // Whole identifiers are looked up together up front, and taken in order as they come up.
+    let is_pattern = |identifier: &str| identifier.contains(['*', '?']);
+    let whole: Vec<_> = identifiers
+        .iter()
+        .copied()
+        .filter(|i| !is_pattern(i))
+        .collect();
+    let mut found = db.lookup_many(&whole)?.into_iter();
+
+    for &identifier in &identifiers {
+        if is_pattern(identifier) {
+            let airports = db.by_pattern(identifier, limit + 1, |airport| {
+                (include_closed || !airport.is_closed())
+                    && args
+                        .filter
+                        .as_ref()
+                        .is_none_or(|filter| filter.matches(airport))
+            })?;
+            if airports.is_empty() {
+                eprintln!("{identifier} {}", tr(Message::NotFound));
+                missing.push(identifier);
+            } else if airports.len() > limit {
+                eprintln!("{identifier}: {} {limit}", tr(Message::TruncatedTo));
             }
 
-            identifier
-                .parse()
-                .map(|coords: Coords| (identifier, Waypoint::from(coords)))
-                .ok()
-        })
+            for mut airport in airports.into_iter().take(limit) {
+                if !include_closed {
+                    airport.remove_closed_runways();
+                }
+                renderer.render(&airport, out)?;
+            }
+            continue;
+        }
+
+        let Some(mut airport) = found.next().flatten() else {
+            let prefixes = prefixes.get_or_insert_with(|| PrefixTable::from_airports(db.scan()));
+            let suggestions: Vec<_> = prefixes
+                .candidates(identifier)
+                .into_iter()
+                .filter(|candidate| matches!(db.by_identifier(candidate), Ok(Some(_))))
+                .collect();
+
+            if suggestions.is_empty() {
+                eprintln!("{identifier} {}", tr(Message::NotFound));
+            } else {
+                eprintln!(
+                    "{identifier} {}; {} {}?",
+                    tr(Message::NotFound),
+                    tr(Message::DidYouMean),
+                    suggestions.join(", ")
+                );
+            }
+
+            missing.push(identifier);
+            continue;
+        };
+
+        if args
+            .filter
+            .as_ref()
+            .is_some_and(|filter| !filter.matches(&airport))
+        {
+            filtered.push(identifier);
+            continue;
+        }
+        if !include_closed {
+            airport.remove_closed_runways();
+        }
+        renderer.render(&airport, out)?;
+    }
+
+    renderer.finish(out)?;
+
+    if batch {
+        let structured = renderer.is_structured();
+        print_batch_summary(&identifiers, &missing, &filtered, structured, out)?;
+    }
+
+    Ok(())
+}
+
+/// Looks up airports in a backend other than the index. Only whole identifiers can be found this
+/// way; there's no index to match patterns against or suggest from.
+fn print_simple_lookup(
+    source: &dyn Lookup,
+    args: &Args,
+    include_closed: bool,
+    renderer: &mut dyn Renderer,
+    out: &mut dyn Write,
+) -> Result<()> {
+    let (identifiers, batch) = lookup_identifiers(args);
+    let mut missing = Vec::new();
+    let mut filtered = Vec::new();
+
+    for &identifier in &identifiers {
+        let Some(mut airport) = source.by_identifier(identifier)? else {
+            eprintln!("{identifier} {}", tr(Message::NotFound));
+            missing.push(identifier);
+            continue;
+        };
+
+        if args
+            .filter
+            .as_ref()
+            .is_some_and(|filter| !filter.matches(&airport))
+        {
+            filtered.push(identifier);
+            continue;
+        }
+        if !include_closed {
+            airport.remove_closed_runways();
+        }
+        renderer.render(&airport, out)?;
+    }
+
+    renderer.finish(out)?;
+
+    if batch {
+        let structured = renderer.is_structured();
+        print_batch_summary(&identifiers, &missing, &filtered, structured, out)?;
+    }
+
+    Ok(())
+}
+
+/// The `count` airports nearest a point among those `keep` accepts, asking the source for more
+/// candidates each time too few of them pass.
+fn nearest_kept(
+    source: &dyn Lookup,
+    coords: &Coords,
+    count: usize,
+    keep: impl Fn(&Airport) -> bool,
+) -> Result<Vec<Airport>> {
+    let mut limit = count + 1;
+    loop {
+        let candidates = source.nearest(coords, limit)?;
+        let exhausted = candidates.len() < limit;
+        let kept: Vec<_> = candidates
+            .into_iter()
+            .filter(|airport| keep(airport))
+            .take(count)
+            .collect();
+        if kept.len() == count || exhausted {
+            return Ok(kept);
+        }
+        limit *= 4;
+    }
+}
+
+/// The identifiers to look up, and whether to treat them as a batch.
+fn lookup_identifiers(args: &Args) -> (Vec<&str>, bool) {
+    // "KSEA|KBFI|KPAE" is shorthand for a batch lookup of each identifier.
+    let identifiers = args
+        .identifiers
+        .iter()
+        .flat_map(|identifier| identifier.split('|'))
+        .map(str::trim)
+        .filter(|identifier| !identifier.is_empty())
         .collect();
+    let batch = args.any || args.identifiers.iter().any(|i| i.contains('|'));
+    (identifiers, batch)
+}
 
-    fn get_by_ident<'a>(ident: &str, cache: &'a HashMap<&str, Waypoint>) -> Result<&'a Waypoint> {
-        cache
-            .get(ident)
-            .ok_or_else(|| Error::from_identifier(ident))
+/// Says how many of a batch were found, and which were missing or left out by `--where`. So the
+/// counts add up to what was printed. After output meant for other programs, like JSON or KML,
+/// the summary goes to stderr so as not to spoil it.
+fn print_batch_summary(
+    identifiers: &[&str],
+    missing: &[&str],
+    filtered: &[&str],
+    structured: bool,
+    out: &mut dyn Write,
+) -> io::Result<()> {
+    let found = identifiers.len() - missing.len() - filtered.len();
+    let mut summary = format!("{found}/{} {}", identifiers.len(), tr(Message::Found));
+    for (label, identifiers) in [
+        (Message::FilteredOut, filtered),
+        (Message::Missing, missing),
+    ] {
+        if !identifiers.is_empty() {
+            write!(summary, "; {}: {}", tr(label), identifiers.join(", ")).unwrap();
+        }
+    }
+
+    if structured {
+        eprintln!("{summary}");
+        Ok(())
+    } else {
+        writeln!(out, "\n{summary}")
+    }
+}
+
+/// Warns about consecutive waypoints at the same position, optionally dropping the repeats.
+fn check_zero_length_legs(route: Vec<Waypoint>, collapse: bool) -> Vec<Waypoint> {
+    let mut checked: Vec<Waypoint> = Vec::with_capacity(route.len());
+    for waypoint in route {
+        if let Some(previous) = checked.last() {
+            if previous.is_same_point(&waypoint) {
+                eprintln!(
+                    "{} -> {}: {}",
+                    previous.name(),
+                    waypoint.name(),
+                    tr(Message::ZeroLengthLeg)
+                );
+
+                if collapse {
+                    continue;
+                }
+            }
+        }
+        checked.push(waypoint);
+    }
+    checked
+}
+
+fn is_origin(origin: &Waypoint, airport: &Airport) -> bool {
+    matches!(origin, Waypoint::Airport(origin) if origin.ident == airport.ident)
+}
+
+/// The airports in local info whose identifiers pass `keep`.
+fn local_airports(
+    db: &dyn Lookup,
+    info: &LocalInfo,
+    keep: impl Fn(&str) -> bool,
+) -> Result<Vec<Airport>> {
+    let mut airports = Vec::new();
+    for ident in info.idents().filter(|ident| keep(ident)) {
+        if let Some(airport) = db.by_identifier(ident)? {
+            airports.push(airport);
+        }
     }
+    Ok(airports)
+}
 
-    let airport_pairs = iter::once(origin).chain(waypoints).pairs().map(|(a, b)| {
-        get_by_ident(a.as_ref(), &cache)
-            .and_then(|a| get_by_ident(b.as_ref(), &cache).map(|b| (a, b)))
-    });
+/// Inserts a `SPLIT` waypoint into the route, returning its position and its distance along the
+/// route in meters.
+fn split_route(
+    resolver: &WaypointResolver,
+    route: &mut Vec<Waypoint>,
+    split_at: &SplitAt,
+) -> Result<(Coords, f64)> {
+    let outside = || Error::SplitOutsideRoute(split_at.to_string());
 
-    let mut dist = 0.0;
-    let mut preformat_records = Vec::new();
-    let mut dist_column_width = 0;
+    // Find the leg to split and how far along it the split falls.
+    let (leg, fraction) = match split_at {
+        SplitAt::Distance(nm) => {
+            let mut remaining = NauticalMiles(*nm).to_meters().0;
+            let mut found = None;
+            for (idx, (left, right)) in route.iter().pairs().enumerate() {
+                let length = left.distance_to(right).meters();
+                if remaining <= length && length > 0.0 {
+                    // Interpolation is spherical but leg lengths are ellipsoidal; a few rounds of
+                    // correction bring the split to the requested distance.
+                    let (a, b) = (left.coordinates(), right.coordinates());
+                    let mut fraction = remaining / length;
+                    for _ in 0..4 {
+                        let actual = a.distance_to(&geo::intermediate(&a, &b, fraction)).meters();
+                        if actual > 0.0 {
+                            fraction = (fraction * remaining / actual).min(1.0);
+                        }
+                    }
+                    found = Some((idx, fraction));
+                    break;
+                }
+                remaining -= length;
+            }
+            found.filter(|_| *nm > 0.0).ok_or_else(outside)?
+        }
+        SplitAt::Fix(fix) => {
+            let fix = resolver.resolve(fix)?.coordinates();
+            let mut best: Option<(usize, f64, f64)> = None;
+            for (idx, (left, right)) in route.iter().pairs().enumerate() {
+                let (a, b) = (left.coordinates(), right.coordinates());
+                let t = geo::closest_fraction(&a, &b, &fix);
+                let d = geo::intermediate(&a, &b, t).distance_to(&fix).meters();
+                if best.is_none_or(|(_, _, closest)| d < closest) {
+                    best = Some((idx, t, d));
+                }
+            }
+            best.map(|(idx, t, _)| (idx, t)).ok_or_else(outside)?
+        }
+    };
 
-    for pair in airport_pairs {
-        let (left, right) = pair?;
-        let leg = left.distance_to(right).meters();
+    let (a, b) = (route[leg].coordinates(), route[leg + 1].coordinates());
+    let coords = geo::intermediate(&a, &b, fraction);
+    let along = route[..=leg]
+        .iter()
+        .pairs()
+        .map(|(left, right)| left.distance_to(right).meters())
+        .sum::<f64>()
+        + a.distance_to(&coords).meters();
 
-        let formatted_distance = format!("{:.01}", leg / METERS_PER_NAUTICAL_MILE);
-        dist_column_width = formatted_distance.len().max(dist_column_width);
-        preformat_records.push((left.name(), right.name(), formatted_distance));
-        dist += leg;
+    // A split that lands on an existing waypoint would only add a zero-length leg.
+    let on_waypoint = |other: &Coords| other.distance_to(&coords).meters() < 100.0;
+    if !on_waypoint(&a) && !on_waypoint(&b) {
+        route.insert(leg + 1, Waypoint::Named("SPLIT".into(), coords));
     }
+    Ok((coords, along))
+}
 
-    for (a, b, dist) in preformat_records {
-        println!("{a:>4} -> {b:>4}  {dist:>dist_column_width$}");
+/// The route in whichever export format was asked for, if any.
+#[cfg(feature = "export")]
+fn export_route(route: &Route, geojson: bool, gpx: bool, kml: bool) -> Option<String> {
+    use adb::export;
+
+    if geojson {
+        let geojson = export::geojson(route);
+        Some(serde_json::to_string_pretty(&geojson).unwrap() + "\n")
+    } else if gpx {
+        Some(export::gpx(route))
+    } else if kml {
+        Some(export::kml_route(route))
+    } else {
+        None
     }
+}
 
-    println!(
-        "\nTotal distance: {:.01} nm",
-        dist / METERS_PER_NAUTICAL_MILE
-    );
+fn print_split(
+    db: &dyn Lookup,
+    coords: &Coords,
+    along: f64,
+    unit: DistanceUnit,
+    out: &mut dyn Write,
+) -> Result<()> {
+    writeln!(
+        out,
+        "{}: {coords} ({} {})",
+        tr(Message::SplitPoint),
+        Meters(along).display(unit),
+        tr(Message::AlongRoute)
+    )?;
+
+    if let Some(airport) = db.nearest(coords, 1)?.into_iter().next() {
+        let distance = Meters(coords.distance_to(&airport.coordinates).meters());
+        writeln!(
+            out,
+            "{}: {} {} ({})",
+            tr(Message::NearestAirport),
+            airport.ident,
+            airport.name,
+            distance.display(unit)
+        )?;
+    }
 
     Ok(())
 }
 
-fn print_search(query: &str) -> tantivy::Result<()> {
-    use std::io::{self, Write};
+/// Snaps each stop on a recorded track to the nearest airport and lists the legs between them.
+fn print_track(
+    db: &dyn Lookup,
+    segments: &[Vec<TrackPoint>],
+    unit: DistanceUnit,
+    out: &mut dyn Write,
+) -> Result<()> {
+    let mut legs = Vec::new();
+    for points in segments {
+        let stops = track::stops(points);
+        let mut previous: Option<(Airport, usize)> = None;
 
-    let db = Database::initialize()?;
-    let candidates = db.search(query)?;
+        for stop in stops {
+            // Heliports often sit right next to the field we actually landed at, so prefer the
+            // nearest airport proper.
+            let nearby = db.nearest(&points[stop].coords, 10)?;
+            let airport = match nearby.iter().position(|a| a.kind.is_airport()) {
+                Some(idx) => nearby[idx].clone(),
+                None => match nearby.into_iter().next() {
+                    Some(airport) => airport,
+                    None => continue,
+                },
+            };
 
-    let mut handle = io::stdout().lock();
+            match previous.take() {
+                // Two stops at the same field are a long taxi, not a flight.
+                Some((from, start)) if from.ident == airport.ident => {
+                    previous = Some((from, start));
+                }
+                Some((from, start)) => {
+                    let flown = track::flown_distance(&points[start..=stop]);
+                    let direct = from.coordinates.distance_to(&airport.coordinates).meters();
+                    legs.push((from.ident.clone(), airport.ident.clone(), flown, direct));
+                    previous = Some((airport, stop));
+                }
+                None => previous = Some((airport, stop)),
+            }
+        }
+    }
 
-    for candidate in candidates {
+    let in_unit = |meters: f64| format_number(unit.from_meters(Meters(meters)), 1);
+    for (from, to, flown, direct) in &legs {
+        let sign = if flown < direct { '-' } else { '+' };
         writeln!(
-            handle,
-            "{} {} {}",
-            candidate.ident, candidate.iso_region, candidate.name
+            out,
+            "{from:>4} -> {to:>4}  {} {:>8}  {} {:>8}  ({sign}{})",
+            tr(Message::Flown),
+            in_unit(*flown),
+            tr(Message::Direct),
+            in_unit(*direct),
+            in_unit((flown - direct).abs())
+        )?;
+    }
+
+    let flown: f64 = legs.iter().map(|leg| leg.2).sum();
+    let direct: f64 = legs.iter().map(|leg| leg.3).sum();
+    writeln!(
+        out,
+        "\n{}: {} {}, {} {}",
+        tr(Message::TotalDistance),
+        tr(Message::Flown),
+        Meters(flown).display(unit),
+        tr(Message::Direct),
+        Meters(direct).display(unit)
+    )?;
+
+    Ok(())
+}
+
+/// The resolver for waypoints given on the command line, which also knows the user's own
+/// waypoints from the config file.
+fn resolver<'a>(
+    db: &'a dyn Lookup,
+    config: &Config,
+    config_path: Option<&Path>,
+) -> Result<WaypointResolver<'a>> {
+    let waypoints = config
+        .waypoints
+        .iter()
+        .map(|(name, coords)| match coords.parse() {
+            Ok(coords) => Ok((name.clone(), coords)),
+            Err(e) => Err(Error::Config(
+                config_path.map(Path::to_path_buf).unwrap_or_default(),
+                format!("waypoints.{name}: {e}"),
+            )),
+        })
+        .collect::<Result<Vec<_>>>()?;
+    Ok(WaypointResolver::new(db).with_user_waypoints(waypoints))
+}
+
+/// Resolves a saved route by name, or else a comma-separated list of waypoints.
+fn resolve_route(
+    resolver: &WaypointResolver,
+    config: &Config,
+    spec: &str,
+) -> Result<Vec<Waypoint>> {
+    match config.routes.get(spec) {
+        Some(waypoints) => waypoints
+            .iter()
+            .map(|identifier| resolver.resolve(identifier))
+            .collect(),
+        None => spec
+            .split(',')
+            .map(str::trim)
+            .filter(|identifier| !identifier.is_empty())
+            .map(|identifier| resolver.resolve(identifier))
+            .collect(),
+    }
+}
+
+#[cfg(feature = "search")]
+fn print_prefixes(db: &Database, country: Option<&str>, out: &mut dyn Write) -> io::Result<()> {
+    let table = PrefixTable::from_airports(db.scan());
+
+    match country {
+        Some(country) => {
+            let prefixes = table.for_country(country);
+            let width = prefixes.iter().map(|(p, _)| p.len()).max().unwrap_or(0);
+            for (prefix, count) in prefixes {
+                writeln!(out, "{prefix:<width$}  {}", format_number(*count as f64, 0))?;
+            }
+        }
+        None => {
+            for (country, prefixes) in table.countries() {
+                let prefixes: Vec<_> = prefixes.iter().map(|(p, _)| p.as_str()).collect();
+                writeln!(out, "{country}  {}", prefixes.join(" "))?;
+            }
+        }
+    }
+    Ok(())
+}
+
+/// Matches listed by `adb search`.
+const SEARCH_RESULTS: usize = 25;
+
+/// How a search matches words.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+enum SearchMode {
+    Text,
+    Phonetic,
+    Fuzzy,
+    Regex,
+}
+
+#[cfg(feature = "search")]
+/// Searches the index for the best matches `keep` accepts.
+fn search_index(
+    db: &Database,
+    query: &str,
+    country: Option<&Country>,
+    keep: &dyn Fn(&Airport) -> bool,
+    mode: SearchMode,
+) -> tantivy::Result<Vec<Airport>> {
+    // A filter may reject most of the best-scoring matches, so look further down the list for
+    // enough that pass it.
+    let candidates = match (country, mode) {
+        (_, SearchMode::Fuzzy) => db
+            .search_fuzzy(query)?
+            .filter(|airport| airport.as_ref().map_or(true, keep))
+            .take(SEARCH_RESULTS)
+            .collect::<tantivy::Result<_>>()?,
+        (_, SearchMode::Regex) => db
+            .search_regex(query)?
+            .filter(|airport| airport.as_ref().map_or(true, keep))
+            .take(SEARCH_RESULTS)
+            .collect::<tantivy::Result<_>>()?,
+        (_, SearchMode::Phonetic) => db
+            .search_phonetic(query, SEARCH_RESULTS * 40)?
+            .into_iter()
+            .filter(|airport| keep(airport))
+            .take(SEARCH_RESULTS)
+            .collect(),
+        (Some(country), SearchMode::Text) => db
+            .search_country(query, country)?
+            .filter(|airport| airport.as_ref().map_or(true, keep))
+            .take(SEARCH_RESULTS)
+            .collect::<tantivy::Result<_>>()?,
+        (None, SearchMode::Text) => db
+            .search_iter(query)?
+            .filter(|airport| airport.as_ref().map_or(true, keep))
+            .take(SEARCH_RESULTS)
+            .collect::<tantivy::Result<_>>()?,
+    };
+    Ok(candidates)
+}
+
+#[cfg(feature = "search")]
+/// Lists the best matches `keep` accepts on one line each, from their summaries alone.
+fn print_search_summaries(
+    db: &Database,
+    query: &str,
+    keep: &dyn Fn(&AirportSummary) -> bool,
+    out: &mut dyn Write,
+) -> Result<()> {
+    // The first failed write is kept and the rest skipped, since the visitor can't return it.
+    let mut written = Ok(());
+    db.search_summaries(query, SEARCH_RESULTS, keep, |summary| {
+        if written.is_ok() {
+            written = writeln!(
+                out,
+                "{} {} {}",
+                summary.ident, summary.iso_region, summary.name
+            );
+        }
+    })?;
+    Ok(written?)
+}
+
+/// Searches a backend other than the index, which has no phonetic search or paged results.
+fn search_simple(
+    source: &dyn Lookup,
+    query: &str,
+    keep: &dyn Fn(&Airport) -> bool,
+) -> Result<Vec<Airport>> {
+    Ok(source
+        .search(query, SEARCH_RESULTS * 40)?
+        .into_iter()
+        .filter(|airport| keep(airport))
+        .take(SEARCH_RESULTS)
+        .collect())
+}
+
+fn print_search_results(
+    candidates: Vec<Airport>,
+    full: bool,
+    include_closed: bool,
+    out: &mut dyn Write,
+) -> io::Result<()> {
+    for mut candidate in candidates {
+        if !full {
+            writeln!(
+                out,
+                "{} {} {}",
+                candidate.ident, candidate.iso_region, candidate.name
+            )?;
+            continue;
+        }
+
+        if !include_closed {
+            candidate.remove_closed_runways();
+        }
+        writeln!(out, "{candidate}")?;
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use adb::{embedded::Embedded, model::Airport, render};
+    use clap::Parser;
+
+    use super::{print_simple_lookup, Args, Backend, Config};
+
+    #[test]
+    fn keeps_batch_summaries_out_of_json() {
+        let args = Args::try_parse_from(["adb", "KSEA|KXXX", "--json"]).unwrap();
+        let mut out = Vec::new();
+        print_simple_lookup(&Embedded::load(), &args, false, &mut render::Json, &mut out).unwrap();
+
+        // Every line is an airport, and the summary went to stderr.
+        let airports: Vec<Airport> = String::from_utf8(out)
+            .unwrap()
+            .lines()
+            .map(|line| serde_json::from_str(line).unwrap())
+            .collect();
+        assert_eq!(airports.len(), 1);
+        assert_eq!(airports[0].ident, "KSEA");
+
+        // So batches of any size can be asked for as JSON.
+        assert!(Args::try_parse_from(["adb", "--any", "KSEA", "KBFI", "--json"]).is_ok());
+    }
+
+    #[test]
+    fn counts_airports_left_out_by_filters() {
+        let args = Args::try_parse_from(["adb", "KSEA|KBFI|KXXX", "--where", "elevation_ft > 400"])
+            .unwrap();
+        let mut out = Vec::new();
+        print_simple_lookup(
+            &Embedded::load(),
+            &args,
+            false,
+            &mut render::Short,
+            &mut out,
         )
         .unwrap();
+
+        // KBFI is neither printed nor counted as found.
+        let out = String::from_utf8(out).unwrap();
+        assert!(out.contains("KSEA") && !out.contains("Boeing"));
+        let summary = out.lines().last().unwrap();
+        assert!(summary.starts_with("1/3 "), "{summary}");
+        assert!(
+            summary.contains(": KBFI;") && summary.ends_with(": KXXX"),
+            "{summary}"
+        );
     }
 
-    Ok(())
+    #[test]
+    fn reads_backend_and_closed_airports_from_config() {
+        let config: Config = toml::from_str("backend = \"static\"\ninclude-closed = true").unwrap();
+        assert_eq!(config.backend, Some(Backend::Static));
+        assert_eq!(config.include_closed, Some(true));
+        assert!(toml::from_str::<Config>("backend = \"tape\"").is_err());
+
+        // The config file can turn closed airports on, so the flag has to be able to turn them off.
+        let args = Args::try_parse_from(["adb", "KSEA", "--include-closed=false"]).unwrap();
+        assert_eq!(args.include_closed, Some(false));
+        let args = Args::try_parse_from(["adb", "KSEA", "--include-closed"]).unwrap();
+        assert_eq!(args.include_closed, Some(true));
+    }
 }
@@ -1,27 +1,265 @@
 use std::{borrow::Cow, fmt, num::ParseFloatError, str::FromStr};
 
-use geoutils::Location;
-use serde::{Deserialize, Serialize};
+use geoutils::{Distance, Location};
+use serde::{Deserialize, Deserializer, Serialize};
+
+use crate::{
+    locale::{format_number, tr, Message},
+    units::Feet,
+};
 
 #[derive(Clone, Debug, Deserialize, Serialize)]
 pub struct Airport {
     pub ident: String,
-    pub kind: String,
+    pub kind: AirportKind,
     pub name: String,
-    pub elevation_ft: Option<i32>,
-    pub continent: String,
-    pub iso_country: String,
+    pub elevation_ft: Option<Feet>,
+    pub continent: Continent,
+    pub iso_country: Country,
     pub iso_region: String,
-    pub municipality: String,
-    pub gps_code: String,
-    pub iata_code: String,
-    pub local_code: String,
+    #[serde(deserialize_with = "empty_as_none")]
+    pub municipality: Option<String>,
+    #[serde(deserialize_with = "empty_as_none")]
+    pub gps_code: Option<String>,
+    #[serde(deserialize_with = "empty_as_none")]
+    pub iata_code: Option<String>,
+    #[serde(deserialize_with = "empty_as_none")]
+    pub local_code: Option<String>,
+
+    /// whether airlines fly scheduled services here
+    #[serde(default)]
+    pub scheduled_service: bool,
     pub coordinates: Coords,
     pub runways: Vec<Runway>,
 }
 
+/// The parts of an [`Airport`] that one-line listings show, borrowed from the JSON they're read
+/// from rather than copied out of it.
+///
+/// Text is only copied where the JSON escapes it, and runways are skipped over unread.
+#[derive(Debug, Deserialize)]
+pub struct AirportSummary<'a> {
+    #[serde(borrow)]
+    pub ident: Cow<'a, str>,
+    pub kind: AirportKind,
+    #[serde(borrow)]
+    pub name: Cow<'a, str>,
+    #[serde(borrow)]
+    pub iso_region: Cow<'a, str>,
+    #[serde(borrow)]
+    pub municipality: Option<Cow<'a, str>>,
+    pub elevation_ft: Option<Feet>,
+    pub coordinates: Coords,
+}
+
+/// Reads an empty string as `None`, since an empty cell is how the CSV says a value is missing.
+fn empty_as_none<'de, D: Deserializer<'de>>(deserializer: D) -> Result<Option<String>, D::Error> {
+    let value = Option::<String>::deserialize(deserializer)?;
+    Ok(value.filter(|value| !value.is_empty()))
+}
+
+/// Reads the CSV's `yes` and `no`.
+fn yes_as_true<'de, D: Deserializer<'de>>(deserializer: D) -> Result<bool, D::Error> {
+    Ok(String::deserialize(deserializer)? == "yes")
+}
+
+/// What sort of place an airport is, as OurAirports classifies it.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash, Deserialize, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum AirportKind {
+    LargeAirport,
+    MediumAirport,
+    SmallAirport,
+    Heliport,
+    SeaplaneBase,
+    Balloonport,
+    Closed,
+}
+
+impl AirportKind {
+    /// The kind as OurAirports writes it, e.g. `small_airport`.
+    pub fn as_str(self) -> &'static str {
+        match self {
+            AirportKind::LargeAirport => "large_airport",
+            AirportKind::MediumAirport => "medium_airport",
+            AirportKind::SmallAirport => "small_airport",
+            AirportKind::Heliport => "heliport",
+            AirportKind::SeaplaneBase => "seaplane_base",
+            AirportKind::Balloonport => "balloonport",
+            AirportKind::Closed => "closed",
+        }
+    }
+
+    /// True for open airports with runways, rather than heliports, seaplane bases and the like.
+    pub fn is_airport(self) -> bool {
+        matches!(
+            self,
+            AirportKind::LargeAirport | AirportKind::MediumAirport | AirportKind::SmallAirport
+        )
+    }
+}
+
+impl FromStr for AirportKind {
+    type Err = String;
+
+    /// Reads a kind as OurAirports writes it, e.g. `small_airport`, allowing `small-airport`.
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.to_ascii_lowercase().replace('-', "_").as_str() {
+            "large_airport" => Ok(AirportKind::LargeAirport),
+            "medium_airport" => Ok(AirportKind::MediumAirport),
+            "small_airport" => Ok(AirportKind::SmallAirport),
+            "heliport" => Ok(AirportKind::Heliport),
+            "seaplane_base" => Ok(AirportKind::SeaplaneBase),
+            "balloonport" => Ok(AirportKind::Balloonport),
+            "closed" => Ok(AirportKind::Closed),
+            _ => Err(format!("{}: {s}", tr(Message::BadKind))),
+        }
+    }
+}
+
+/// The continent an airport is on, by its two letter code.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord, Hash, Deserialize, Serialize)]
+pub enum Continent {
+    #[serde(rename = "AF")]
+    Africa,
+    #[serde(rename = "AN")]
+    Antarctica,
+    #[serde(rename = "AS")]
+    Asia,
+    #[serde(rename = "EU")]
+    Europe,
+    #[serde(rename = "NA")]
+    NorthAmerica,
+    #[serde(rename = "OC")]
+    Oceania,
+    #[serde(rename = "SA")]
+    SouthAmerica,
+}
+
+impl Continent {
+    pub fn code(self) -> &'static str {
+        match self {
+            Continent::Africa => "AF",
+            Continent::Antarctica => "AN",
+            Continent::Asia => "AS",
+            Continent::Europe => "EU",
+            Continent::NorthAmerica => "NA",
+            Continent::Oceania => "OC",
+            Continent::SouthAmerica => "SA",
+        }
+    }
+}
+
+impl fmt::Display for Continent {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.code())
+    }
+}
+
+/// An ISO 3166-1 alpha-2 country code, e.g. `US`.
+///
+/// Only assigned codes are accepted, along with `XK`, which the data uses for Kosovo; that one is
+/// user-assigned, reserved for exactly this kind of use.
+#[derive(Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash, Deserialize, Serialize)]
+#[serde(try_from = "String", into = "String")]
+pub struct Country([u8; 2]);
+
+impl Country {
+    pub fn as_str(&self) -> &str {
+        // Only ever built from an assigned code, which is two ASCII letters.
+        std::str::from_utf8(&self.0).unwrap()
+    }
+
+    /// The flag emoji, made of the regional indicator symbols for the two letters.
+    pub fn flag(&self) -> String {
+        self.0
+            .iter()
+            .map(|&letter| char::from_u32(0x1F1E6 + u32::from(letter - b'A')).unwrap())
+            .collect()
+    }
+}
+
+/// The officially assigned ISO 3166-1 alpha-2 codes, in order.
+const ASSIGNED_COUNTRIES: [[u8; 2]; 249] = [
+    *b"AD", *b"AE", *b"AF", *b"AG", *b"AI", *b"AL", *b"AM", *b"AO", *b"AQ", *b"AR", *b"AS", *b"AT",
+    *b"AU", *b"AW", *b"AX", *b"AZ", *b"BA", *b"BB", *b"BD", *b"BE", *b"BF", *b"BG", *b"BH", *b"BI",
+    *b"BJ", *b"BL", *b"BM", *b"BN", *b"BO", *b"BQ", *b"BR", *b"BS", *b"BT", *b"BV", *b"BW", *b"BY",
+    *b"BZ", *b"CA", *b"CC", *b"CD", *b"CF", *b"CG", *b"CH", *b"CI", *b"CK", *b"CL", *b"CM", *b"CN",
+    *b"CO", *b"CR", *b"CU", *b"CV", *b"CW", *b"CX", *b"CY", *b"CZ", *b"DE", *b"DJ", *b"DK", *b"DM",
+    *b"DO", *b"DZ", *b"EC", *b"EE", *b"EG", *b"EH", *b"ER", *b"ES", *b"ET", *b"FI", *b"FJ", *b"FK",
+    *b"FM", *b"FO", *b"FR", *b"GA", *b"GB", *b"GD", *b"GE", *b"GF", *b"GG", *b"GH", *b"GI", *b"GL",
+    *b"GM", *b"GN", *b"GP", *b"GQ", *b"GR", *b"GS", *b"GT", *b"GU", *b"GW", *b"GY", *b"HK", *b"HM",
+    *b"HN", *b"HR", *b"HT", *b"HU", *b"ID", *b"IE", *b"IL", *b"IM", *b"IN", *b"IO", *b"IQ", *b"IR",
+    *b"IS", *b"IT", *b"JE", *b"JM", *b"JO", *b"JP", *b"KE", *b"KG", *b"KH", *b"KI", *b"KM", *b"KN",
+    *b"KP", *b"KR", *b"KW", *b"KY", *b"KZ", *b"LA", *b"LB", *b"LC", *b"LI", *b"LK", *b"LR", *b"LS",
+    *b"LT", *b"LU", *b"LV", *b"LY", *b"MA", *b"MC", *b"MD", *b"ME", *b"MF", *b"MG", *b"MH", *b"MK",
+    *b"ML", *b"MM", *b"MN", *b"MO", *b"MP", *b"MQ", *b"MR", *b"MS", *b"MT", *b"MU", *b"MV", *b"MW",
+    *b"MX", *b"MY", *b"MZ", *b"NA", *b"NC", *b"NE", *b"NF", *b"NG", *b"NI", *b"NL", *b"NO", *b"NP",
+    *b"NR", *b"NU", *b"NZ", *b"OM", *b"PA", *b"PE", *b"PF", *b"PG", *b"PH", *b"PK", *b"PL", *b"PM",
+    *b"PN", *b"PR", *b"PS", *b"PT", *b"PW", *b"PY", *b"QA", *b"RE", *b"RO", *b"RS", *b"RU", *b"RW",
+    *b"SA", *b"SB", *b"SC", *b"SD", *b"SE", *b"SG", *b"SH", *b"SI", *b"SJ", *b"SK", *b"SL", *b"SM",
+    *b"SN", *b"SO", *b"SR", *b"SS", *b"ST", *b"SV", *b"SX", *b"SY", *b"SZ", *b"TC", *b"TD", *b"TF",
+    *b"TG", *b"TH", *b"TJ", *b"TK", *b"TL", *b"TM", *b"TN", *b"TO", *b"TR", *b"TT", *b"TV", *b"TW",
+    *b"TZ", *b"UA", *b"UG", *b"UM", *b"US", *b"UY", *b"UZ", *b"VA", *b"VC", *b"VE", *b"VG", *b"VI",
+    *b"VN", *b"VU", *b"WF", *b"WS", *b"YE", *b"YT", *b"ZA", *b"ZM", *b"ZW",
+];
+
+impl FromStr for Country {
+    type Err = ParseCountryError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let code = match s.as_bytes() {
+            &[a, b] => [a.to_ascii_uppercase(), b.to_ascii_uppercase()],
+            _ => return Err(ParseCountryError(s.into())),
+        };
+
+        if &code == b"XK" || ASSIGNED_COUNTRIES.binary_search(&code).is_ok() {
+            Ok(Country(code))
+        } else {
+            Err(ParseCountryError(s.into()))
+        }
+    }
+}
+
+impl TryFrom<String> for Country {
+    type Error = ParseCountryError;
+
+    fn try_from(value: String) -> Result<Self, Self::Error> {
+        value.parse()
+    }
+}
+
+impl From<Country> for String {
+    fn from(country: Country) -> Self {
+        country.as_str().into()
+    }
+}
+
+impl fmt::Debug for Country {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "Country({})", self.as_str())
+    }
+}
+
+impl fmt::Display for Country {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
+#[derive(Debug)]
+pub struct ParseCountryError(String);
+
+impl fmt::Display for ParseCountryError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}: {:?}", tr(Message::BadCountryCode), self.0)
+    }
+}
+
+impl std::error::Error for ParseCountryError {}
+
 impl Airport {
-    pub fn from_template(template: AirportTemplate) -> Option<Self> {
+    pub fn from_template(template: AirportTemplate) -> Self {
         let AirportTemplate {
             ident,
             kind,
@@ -34,11 +272,13 @@ impl Airport {
             gps_code,
             iata_code,
             local_code,
+            scheduled_service,
             latitude_deg,
             longitude_deg,
+            keywords: _,
         } = template;
 
-        Some(Airport {
+        Airport {
             ident,
             kind,
             name,
@@ -50,76 +290,163 @@ impl Airport {
             gps_code,
             iata_code,
             local_code,
+            scheduled_service,
             coordinates: Coords {
                 latitude: latitude_deg,
                 longitude: longitude_deg,
             },
             runways: Default::default(),
-        })
+        }
+    }
+
+    /// A single-line summary of the airport, for lookups that print many airports at once.
+    pub fn short(&self) -> ShortAirport<'_> {
+        ShortAirport { airport: self }
+    }
+
+    /// The runway section of the airport display, without the surrounding airport details.
+    pub fn runway_table(&self) -> RunwayTable<'_> {
+        RunwayTable {
+            runways: &self.runways,
+        }
+    }
+
+    /// True if at least one open runway is lighted, so the airport can be used at night.
+    pub fn has_lighted_runway(&self) -> bool {
+        self.runways
+            .iter()
+            .any(|runway| runway.is_lighted && !runway.is_closed)
+    }
+
+    pub fn is_closed(&self) -> bool {
+        self.kind == AirportKind::Closed
+    }
+
+    /// Drops the runways that are closed, leaving only those that can be used.
+    pub fn remove_closed_runways(&mut self) {
+        self.runways.retain(|runway| !runway.is_closed);
     }
 }
 
 impl fmt::Display for Airport {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
-        match self.elevation_ft {
-            Some(elevation) => write!(
-                f,
-                "{} {} ({} feet)\n  {}\n  {}\n  {}",
-                self.ident,
-                self.name,
-                elevation,
-                self.municipality,
-                self.iso_region,
-                self.coordinates
-            )?,
-
-            None => write!(
-                f,
-                "{} {}\n  {}\n  {}\n  {}",
-                self.ident, self.name, self.municipality, self.iso_region, self.coordinates
-            )?,
-        };
+        write!(f, "{} {}", self.ident, self.name)?;
+        if let Some(elevation) = self.elevation_ft {
+            let elevation = format_number(elevation.0.into(), 0);
+            write!(f, " ({elevation} {})", tr(Message::Feet))?;
+        }
+        if let Some(municipality) = &self.municipality {
+            write!(f, "\n  {municipality}")?;
+        }
+        write!(f, "\n  {}\n  {}", self.iso_region, self.coordinates)?;
 
         if !self.runways.is_empty() {
-            f.write_str("\n\nRunways:\n")?;
-            for rwy in &self.runways {
-                let name = &rwy.name;
-                let length = rwy
-                    .length
-                    .map(|length| Cow::from(length.to_string() + "ft"))
-                    .unwrap_or_else(|| Cow::from("unknown"));
-
-                if rwy.is_lighted {
-                    writeln!(f, "  {name} {length:>8}  +L")?;
-                } else {
-                    writeln!(f, "  {name} {length:>8}")?;
-                }
+            write!(f, "\n\n{}:\n{}", tr(Message::Runways), self.runway_table())?;
+        }
+
+        Ok(())
+    }
+}
+
+pub struct RunwayTable<'a> {
+    runways: &'a [Runway],
+}
+
+impl fmt::Display for RunwayTable<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        for rwy in self.runways {
+            let name = &rwy.name;
+            let length = rwy
+                .length
+                .map(|length| Cow::from(format_number(length.0.into(), 0) + "ft"))
+                .unwrap_or_else(|| Cow::from(tr(Message::Unknown)));
+
+            write!(f, "  {name} {length:>8}")?;
+            if rwy.is_lighted {
+                write!(f, "  +L")?;
+            }
+            if rwy.is_closed {
+                write!(f, "  ({})", tr(Message::Closed))?;
             }
+            writeln!(f)?;
         }
 
         Ok(())
     }
 }
 
+pub struct ShortAirport<'a> {
+    airport: &'a Airport,
+}
+
+impl fmt::Display for ShortAirport<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let airport = self.airport;
+        write!(f, "{} {}", airport.ident, airport.name)?;
+
+        if let Some(municipality) = &airport.municipality {
+            write!(f, ", {municipality}")?;
+        }
+
+        write!(f, ", {}", airport.iso_region)?;
+
+        if let Some(elevation) = airport.elevation_ft {
+            write!(f, ", {}ft", format_number(elevation.0.into(), 0))?;
+        }
+
+        let (n, e) = airport.coordinates.hemispheres();
+        let lat = airport.coordinates.latitude.abs();
+        let lon = airport.coordinates.longitude.abs();
+        write!(f, ", {lat:.04}{n} {lon:.04}{e}")
+    }
+}
+
 #[derive(Clone, Debug, Deserialize)]
 pub struct AirportTemplate {
     ident: String,
     #[serde(rename = "type")]
-    kind: String,
+    kind: AirportKind,
     name: String,
-    elevation_ft: Option<i32>,
-    continent: String,
-    iso_country: String,
+    elevation_ft: Option<Feet>,
+    continent: Continent,
+    iso_country: Country,
     iso_region: String,
-    municipality: String,
-    gps_code: String,
-    iata_code: String,
-    local_code: String,
+    #[serde(deserialize_with = "empty_as_none")]
+    municipality: Option<String>,
+    #[serde(deserialize_with = "empty_as_none")]
+    gps_code: Option<String>,
+    #[serde(deserialize_with = "empty_as_none")]
+    iata_code: Option<String>,
+    #[serde(deserialize_with = "empty_as_none")]
+    local_code: Option<String>,
+    #[serde(default, deserialize_with = "yes_as_true")]
+    scheduled_service: bool,
     latitude_deg: f64,
     longitude_deg: f64,
+
+    /// alternate names, often including the local spelling in its own script
+    keywords: String,
+}
+
+impl AirportTemplate {
+    pub fn ident(&self) -> &str {
+        &self.ident
+    }
+
+    pub fn kind(&self) -> AirportKind {
+        self.kind
+    }
+
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+
+    pub fn keywords(&self) -> &str {
+        &self.keywords
+    }
 }
 
-#[derive(Clone, Copy, Debug, Deserialize, Serialize)]
+#[derive(Clone, Copy, Debug, PartialEq, Deserialize, Serialize)]
 pub struct Coords {
     pub latitude: f64,
     pub longitude: f64,
@@ -133,12 +460,39 @@ impl Coords {
         } = self;
         Location::new(latitude, longitude)
     }
+
+    pub fn distance_to(&self, other: &Coords) -> Distance {
+        let left = self.location();
+        let right = other.location();
+
+        // I have never, ever, ever seen Vicenty's formula fail to yield a result, but IF IT DOES
+        // we'll fall back to haversine distance.
+        left.distance_to(&right)
+            .unwrap_or_else(|_| left.haversine_distance_to(&right))
+    }
+
+    /// Initial great circle course to another point, in degrees true.
+    pub fn bearing_to(&self, other: &Coords) -> f64 {
+        let (lat1, lon1) = (self.latitude.to_radians(), self.longitude.to_radians());
+        let (lat2, lon2) = (other.latitude.to_radians(), other.longitude.to_radians());
+        let delta = lon2 - lon1;
+
+        let y = delta.sin() * lat2.cos();
+        let x = lat1.cos() * lat2.sin() - lat1.sin() * lat2.cos() * delta.cos();
+        y.atan2(x).to_degrees().rem_euclid(360.0)
+    }
+
+    /// The hemisphere letters for latitude and longitude, e.g. `("N", "W")`.
+    pub fn hemispheres(&self) -> (&'static str, &'static str) {
+        let n = if self.latitude >= 0.0 { "N" } else { "S" };
+        let e = if self.longitude >= 0.0 { "E" } else { "W" };
+        (n, e)
+    }
 }
 
 impl fmt::Display for Coords {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
-        let n = if self.latitude >= 0.0 { "N" } else { "S" };
-        let e = if self.longitude >= 0.0 { "E" } else { "W" };
+        let (n, e) = self.hemispheres();
 
         let lat = self.latitude.abs();
         let lon = self.longitude.abs();
@@ -189,9 +543,9 @@ impl fmt::Display for ParseCoordsError {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
         match self {
             ParseCoordsError::MissingComponent | ParseCoordsError::TooManyComponents => {
-                f.write_str("bad coordinate format")
+                f.write_str(tr(Message::BadCoordinateFormat))
             }
-            ParseCoordsError::Float(e) => write!(f, "bad coordinate value: {e}"),
+            ParseCoordsError::Float(e) => write!(f, "{}: {e}", tr(Message::BadCoordinateValue)),
         }
     }
 }
@@ -199,24 +553,71 @@ impl fmt::Display for ParseCoordsError {
 #[derive(Clone, Debug, Deserialize)]
 pub struct RunwayTemplate {
     airport_ident: String,
-    length_ft: Option<i32>,
+    length_ft: Option<Feet>,
+    width_ft: Option<Feet>,
+    #[serde(deserialize_with = "empty_as_none")]
+    surface: Option<String>,
     lighted: i8,
     closed: i8,
 
     /// runway identifier, e.g. 34L, where le and he are inverse
     le_ident: String,
+    le_elevation_ft: Option<Feet>,
+    #[serde(rename = "le_heading_degT")]
+    le_heading: Option<f64>,
+    le_displaced_threshold_ft: Option<Feet>,
 
     /// runway identifier, e.g. 34L, where le and he are inverse
     he_ident: String,
+    he_elevation_ft: Option<Feet>,
+    #[serde(rename = "he_heading_degT")]
+    he_heading: Option<f64>,
+    he_displaced_threshold_ft: Option<Feet>,
 }
 
 #[derive(Clone, Debug, Deserialize, Serialize)]
 pub struct Runway {
     pub airport: String,
     pub name: String,
-    pub length: Option<i32>,
+    pub length: Option<Feet>,
+    #[serde(default)]
+    pub width: Option<Feet>,
+
+    /// surface code as published, e.g. `ASPH` or `GRVL`; the data isn't consistent about these
+    #[serde(default)]
+    pub surface: Option<String>,
     pub is_closed: bool,
     pub is_lighted: bool,
+
+    /// The low end first, then the high end. Helipads and some strips have only the one.
+    ///
+    /// Empty in an index built before ends were recorded, until `adb update` rebuilds it.
+    #[serde(default)]
+    pub ends: Vec<RunwayEnd>,
+}
+
+/// One end of a runway, as seen by an aircraft landing on or departing from it.
+#[derive(Clone, Debug, Deserialize, Serialize)]
+pub struct RunwayEnd {
+    /// e.g. 34L
+    pub ident: String,
+
+    /// degrees true
+    pub heading: Option<f64>,
+
+    /// at the threshold
+    pub elevation: Option<Feet>,
+    pub displaced_threshold: Option<Feet>,
+}
+
+impl RunwayEnd {
+    /// Headwind and crosswind components of a wind blowing from `direction` (degrees true) at
+    /// `speed`, in the same units as the speed. A tailwind is a negative headwind, and a
+    /// crosswind from the right is positive. `None` if the heading isn't published.
+    pub fn wind_components(&self, direction: f64, speed: f64) -> Option<(f64, f64)> {
+        let angle = (direction - self.heading?).to_radians();
+        Some((speed * angle.cos(), speed * angle.sin()))
+    }
 }
 
 impl From<RunwayTemplate> for Runway {
@@ -224,26 +625,191 @@ impl From<RunwayTemplate> for Runway {
         let RunwayTemplate {
             airport_ident,
             length_ft,
+            width_ft,
+            surface,
             lighted,
             closed,
             le_ident,
+            le_elevation_ft,
+            le_heading,
+            le_displaced_threshold_ft,
             he_ident,
+            he_elevation_ft,
+            he_heading,
+            he_displaced_threshold_ft,
         } = template;
 
+        let name = format!("{le_ident}/{he_ident}");
+        let mut ends = vec![RunwayEnd {
+            ident: le_ident,
+            heading: le_heading,
+            elevation: le_elevation_ft,
+            displaced_threshold: le_displaced_threshold_ft,
+        }];
+        if !he_ident.is_empty() {
+            ends.push(RunwayEnd {
+                ident: he_ident,
+                heading: he_heading,
+                elevation: he_elevation_ft,
+                displaced_threshold: he_displaced_threshold_ft,
+            });
+        }
+
         Self {
             airport: airport_ident,
-            name: format!("{le_ident}/{he_ident}"),
+            name,
             length: length_ft,
+            width: width_ft,
+            surface,
             is_closed: closed == 1,
             is_lighted: lighted == 1,
+            ends,
         }
     }
 }
 
 #[cfg(test)]
 mod tests {
+    use proptest::prelude::*;
+
+    use std::borrow::Cow;
+
+    use super::{
+        AirportSummary, Continent, Coords, Country, ParseCoordsError, Runway, RunwayTemplate,
+    };
+    use crate::golden::airport_records;
+
     #[test]
     fn can_parse_coordinates() {
-        todo!()
+        let coords: Coords = "47.4479 -122.3103".parse().unwrap();
+        assert_eq!(coords.latitude, 47.4479);
+        assert_eq!(coords.longitude, -122.3103);
+    }
+
+    #[test]
+    fn can_compute_bearing() {
+        let origin = Coords {
+            latitude: 0.0,
+            longitude: 0.0,
+        };
+        let north = Coords {
+            latitude: 10.0,
+            longitude: 0.0,
+        };
+        let west = Coords {
+            latitude: 0.0,
+            longitude: -10.0,
+        };
+
+        assert!(origin.bearing_to(&north).abs() < 1e-9);
+        assert!((origin.bearing_to(&west) - 270.0).abs() < 1e-9);
+    }
+
+    #[test]
+    fn rejects_bad_coordinates() {
+        assert!(matches!(
+            "47.4479".parse::<Coords>(),
+            Err(ParseCoordsError::MissingComponent)
+        ));
+        assert!(matches!(
+            "47.4479 -122.3103 12".parse::<Coords>(),
+            Err(ParseCoordsError::TooManyComponents)
+        ));
+        assert!(matches!(
+            "north west".parse::<Coords>(),
+            Err(ParseCoordsError::Float(_))
+        ));
+    }
+
+    #[test]
+    fn summaries_borrow_from_json() {
+        let records = airport_records();
+        let json = records.lines().next().unwrap();
+        let summary: AirportSummary = serde_json::from_str(json).unwrap();
+        assert!(matches!(summary.ident, Cow::Borrowed(_)));
+        assert!(matches!(summary.iso_region, Cow::Borrowed(_)));
+    }
+
+    #[test]
+    fn validates_country_codes() {
+        let country: Country = "us".parse().unwrap();
+        assert_eq!(country.as_str(), "US");
+        assert_eq!(country.flag(), "🇺🇸");
+        assert!("USA".parse::<Country>().is_err());
+        assert!("U1".parse::<Country>().is_err());
+        assert!("ZZ".parse::<Country>().is_err());
+        assert!("QQ".parse::<Country>().is_err());
+        assert_eq!("xk".parse::<Country>().unwrap().as_str(), "XK");
+        assert!(serde_json::from_str::<Country>("\"\"").is_err());
+
+        let continent: Continent = serde_json::from_str("\"NA\"").unwrap();
+        assert_eq!(continent, Continent::NorthAmerica);
+        assert!(serde_json::from_str::<Continent>("\"XX\"").is_err());
+    }
+
+    #[test]
+    fn reads_both_runway_ends() {
+        let csv = r#""id","airport_ref","airport_ident","length_ft","width_ft","surface","lighted","closed","le_ident","le_latitude_deg","le_longitude_deg","le_elevation_ft","le_heading_degT","le_displaced_threshold_ft","he_ident","he_latitude_deg","he_longitude_deg","he_elevation_ft","he_heading_degT","he_displaced_threshold_ft"
+1,2,"KSEA",11901,150,"CON",1,0,"16L",47.46,-122.30,363,180,,"34R",47.43,-122.30,347,360,1000
+2,3,"00A",80,80,"ASPH-G",1,0,"H1",,,,,,,,,,,
+"#;
+        let runways: Vec<Runway> = csv::Reader::from_reader(csv.as_bytes())
+            .deserialize::<RunwayTemplate>()
+            .map(|template| template.unwrap().into())
+            .collect();
+
+        let [runway, helipad] = &runways[..] else {
+            panic!("expected two runways");
+        };
+        assert_eq!(runway.name, "16L/34R");
+        assert_eq!(runway.surface.as_deref(), Some("CON"));
+        assert_eq!(runway.ends[1].ident, "34R");
+        assert_eq!(
+            runway.ends[1].displaced_threshold.map(|feet| feet.0),
+            Some(1000)
+        );
+        assert_eq!(helipad.ends.len(), 1);
+
+        // Wind from 210 at 20 on 16L: a headwind with the crosswind from the right.
+        let (head, cross) = runway.ends[0].wind_components(210.0, 20.0).unwrap();
+        assert!((head - 17.32).abs() < 0.01 && (cross - 10.0).abs() < 1e-9);
+        assert!(helipad.ends[0].wind_components(210.0, 20.0).is_none());
+    }
+
+    proptest! {
+        #[test]
+        fn parses_what_it_is_given(latitude in -90.0..=90.0, longitude in -180.0..=180.0) {
+            let coords: Coords = format!("{latitude} {longitude}").parse().unwrap();
+            prop_assert_eq!(coords.latitude, latitude);
+            prop_assert_eq!(coords.longitude, longitude);
+        }
+
+        #[test]
+        fn distance_is_symmetric(
+            a in (-80.0..80.0, -180.0..180.0),
+            offset in (-60.0..60.0f64, -90.0..90.0f64),
+        ) {
+            // Vincenty can fail to converge for nearly antipodal points; keep clear of them.
+            let a = Coords { latitude: a.0, longitude: a.1 };
+            let b = Coords {
+                latitude: (a.latitude + offset.0).clamp(-89.0, 89.0),
+                longitude: (a.longitude + offset.1 + 540.0).rem_euclid(360.0) - 180.0,
+            };
+
+            let there = a.distance_to(&b).meters();
+            let back = b.distance_to(&a).meters();
+            prop_assert!((there - back).abs() < 1e-3, "{there} != {back}");
+        }
+
+        #[test]
+        fn bearing_back_is_reciprocal_on_the_equator(a in -90.0..90.0f64, b in -90.0..90.0f64) {
+            prop_assume!((a - b).abs() > 1e-6);
+            let a = Coords { latitude: 0.0, longitude: a };
+            let b = Coords { latitude: 0.0, longitude: b };
+
+            let there = a.bearing_to(&b);
+            let back = b.bearing_to(&a);
+            prop_assert!(((there - back).abs() - 180.0).abs() < 1e-9);
+        }
     }
 }
@@ -0,0 +1,74 @@
+use std::{
+    env,
+    io::{self, LineWriter, Write},
+    process::{Child, Command, Stdio},
+};
+
+/// Where output goes: stdout, or the user's pager when stdout is a terminal. Output is written as
+/// it's produced, so long commands start showing results right away.
+///
+/// The pager is taken from `$PAGER`, defaulting to `less`. When `$LESS` is unset we pass `FRX` so
+/// that short output is printed directly rather than opening a full screen pager.
+pub struct Output {
+    writer: Box<dyn Write>,
+    pager: Option<Child>,
+}
+
+impl Output {
+    pub fn new(page: bool) -> Self {
+        if page && atty::is(atty::Stream::Stdout) {
+            if let Some(mut child) = spawn_pager() {
+                if let Some(stdin) = child.stdin.take() {
+                    return Output {
+                        writer: Box::new(LineWriter::new(stdin)),
+                        pager: Some(child),
+                    };
+                }
+            }
+        }
+
+        Output {
+            writer: Box::new(io::stdout().lock()),
+            pager: None,
+        }
+    }
+}
+
+impl Write for Output {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        self.writer.write(buf)
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        self.writer.flush()
+    }
+}
+
+impl Drop for Output {
+    /// Closes the pager's input, letting it know the output is complete, and waits for the user
+    /// to quit it.
+    fn drop(&mut self) {
+        let _ = self.writer.flush();
+        self.writer = Box::new(io::sink());
+        if let Some(mut child) = self.pager.take() {
+            let _ = child.wait();
+        }
+    }
+}
+
+fn spawn_pager() -> Option<Child> {
+    let pager = env::var("PAGER")
+        .ok()
+        .filter(|pager| !pager.trim().is_empty())
+        .unwrap_or_else(|| String::from("less"));
+
+    let mut parts = pager.split_whitespace();
+    let mut command = Command::new(parts.next()?);
+    command.args(parts).stdin(Stdio::piped());
+
+    if env::var_os("LESS").is_none() {
+        command.env("LESS", "FRX");
+    }
+
+    command.spawn().ok()
+}
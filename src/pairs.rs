@@ -1,3 +1,7 @@
+/// Consecutive pairs of items, so `[a, b, c]` yields `(a, b)` and `(b, c)`.
+///
+/// Every item but the first and last is in two pairs and so gets cloned once. Over references
+/// that's free; over owned values like `String` it costs one clone per item.
 pub trait Pairs: Iterator + Sized {
     fn pairs(self) -> PairsIter<Self>;
 }
@@ -9,16 +13,22 @@ pub struct PairsIter<I: Iterator> {
 
 impl<I: Iterator> Iterator for PairsIter<I>
 where
-    I::Item: Copy,
+    I::Item: Clone,
 {
     type Item = (I::Item, I::Item);
 
     fn next(&mut self) -> Option<Self::Item> {
-        let left = self.left.take()?;
         let right = self.source.next()?;
-        self.left = Some(right);
+        let left = self.left.replace(right.clone())?;
         Some((left, right))
     }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        match self.left {
+            Some(_) => self.source.size_hint(),
+            None => (0, Some(0)),
+        }
+    }
 }
 
 impl<I: Iterator> Pairs for I {
@@ -29,3 +39,27 @@ impl<I: Iterator> Pairs for I {
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use proptest::prelude::*;
+
+    use super::Pairs;
+
+    proptest! {
+        #[test]
+        fn pairs_are_consecutive_items(items in prop::collection::vec(any::<u32>(), 0..20)) {
+            let pairs: Vec<_> = items.iter().pairs().map(|(a, b)| (*a, *b)).collect();
+            let windows: Vec<_> = items.windows(2).map(|w| (w[0], w[1])).collect();
+            prop_assert_eq!(pairs, windows);
+        }
+
+        #[test]
+        fn pairs_clone_owned_items(items in prop::collection::vec(".{0,8}", 0..20)) {
+            let expected: Vec<_> = items.windows(2).map(|w| (w[0].clone(), w[1].clone())).collect();
+            let iter = items.into_iter().pairs();
+            prop_assert_eq!(iter.size_hint().0, expected.len());
+            prop_assert_eq!(iter.collect::<Vec<_>>(), expected);
+        }
+    }
+}
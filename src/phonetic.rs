@@ -0,0 +1,158 @@
+//! Sound-alike codes for place names, so an airport can be found from a name heard over the radio,
+//! and the spelling alphabet for reading an identifier back.
+
+const ALPHABET: [&str; 26] = [
+    "Alpha", "Bravo", "Charlie", "Delta", "Echo", "Foxtrot", "Golf", "Hotel", "India", "Juliet",
+    "Kilo", "Lima", "Mike", "November", "Oscar", "Papa", "Quebec", "Romeo", "Sierra", "Tango",
+    "Uniform", "Victor", "Whiskey", "X-ray", "Yankee", "Zulu",
+];
+
+/// Digits as pronounced on the radio.
+const DIGITS: [&str; 10] = [
+    "Zero", "One", "Two", "Tree", "Four", "Fife", "Six", "Seven", "Eight", "Niner",
+];
+
+/// Spells an identifier in the ICAO spelling alphabet, e.g. `KSEA` as "Kilo Sierra Echo Alpha".
+pub fn spell(identifier: &str) -> String {
+    identifier
+        .chars()
+        .filter_map(|c| match c.to_ascii_uppercase() {
+            c @ 'A'..='Z' => Some(ALPHABET[(c as u8 - b'A') as usize]),
+            c @ '0'..='9' => Some(DIGITS[(c as u8 - b'0') as usize]),
+            '-' => Some("Dash"),
+            _ => None,
+        })
+        .collect::<Vec<_>>()
+        .join(" ")
+}
+
+/// American Soundex codes for each word in `text`, separated by spaces.
+///
+/// Hyphens and other punctuation split words, so "Wilkes-Barre" becomes `W422 B600`.
+pub fn encode(text: &str) -> String {
+    text.split(|c: char| !c.is_alphabetic())
+        .filter_map(soundex)
+        .collect::<Vec<_>>()
+        .join(" ")
+}
+
+/// The four-character Soundex code of a word, or `None` if it has no ASCII letters.
+pub fn soundex(word: &str) -> Option<String> {
+    let mut letters = word
+        .chars()
+        .filter(char::is_ascii_alphabetic)
+        .map(|c| c.to_ascii_uppercase());
+
+    let first = letters.next()?;
+    let mut code = String::with_capacity(4);
+    code.push(first);
+
+    let mut previous = digit(first);
+    for c in letters {
+        let current = digit(c);
+        if current != '0' && current != previous {
+            code.push(current);
+            if code.len() == 4 {
+                break;
+            }
+        }
+
+        // H and W don't separate letters with the same code; vowels do.
+        if c != 'H' && c != 'W' {
+            previous = current;
+        }
+    }
+
+    while code.len() < 4 {
+        code.push('0');
+    }
+    Some(code)
+}
+
+/// How closely the words of `query` resemble words in `text`, from 0 to 1.
+///
+/// Soundex codes are coarse, so many names share one; this ranks the ones that are also spelled
+/// most like what was heard first.
+pub fn similarity(query: &str, text: &str) -> f64 {
+    let words = |s: &str| -> Vec<String> {
+        s.split(|c: char| !c.is_alphabetic())
+            .filter(|w| !w.is_empty())
+            .map(str::to_lowercase)
+            .collect()
+    };
+    let (query, text) = (words(query), words(text));
+    if query.is_empty() {
+        return 0.0;
+    }
+
+    let total: f64 = query
+        .iter()
+        .map(|q| {
+            text.iter()
+                .map(|t| {
+                    let longest = q.chars().count().max(t.chars().count()) as f64;
+                    1.0 - levenshtein(q, t) as f64 / longest
+                })
+                .fold(0.0, f64::max)
+        })
+        .sum();
+    total / query.len() as f64
+}
+
+fn levenshtein(a: &str, b: &str) -> usize {
+    let b: Vec<char> = b.chars().collect();
+    let mut row: Vec<usize> = (0..=b.len()).collect();
+
+    for (i, ca) in a.chars().enumerate() {
+        let mut diagonal = row[0];
+        row[0] = i + 1;
+        for (j, &cb) in b.iter().enumerate() {
+            let substitution = diagonal + usize::from(ca != cb);
+            diagonal = row[j + 1];
+            row[j + 1] = substitution.min(row[j] + 1).min(row[j + 1] + 1);
+        }
+    }
+
+    row[b.len()]
+}
+
+fn digit(c: char) -> char {
+    match c {
+        'B' | 'F' | 'P' | 'V' => '1',
+        'C' | 'G' | 'J' | 'K' | 'Q' | 'S' | 'X' | 'Z' => '2',
+        'D' | 'T' => '3',
+        'L' => '4',
+        'M' | 'N' => '5',
+        'R' => '6',
+        _ => '0',
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{encode, similarity, soundex, spell};
+
+    #[test]
+    fn can_encode_names() {
+        assert_eq!(soundex("Robert").as_deref(), Some("R163"));
+        assert_eq!(soundex("Rupert").as_deref(), Some("R163"));
+        assert_eq!(soundex("Ashcraft").as_deref(), Some("A261"));
+        assert_eq!(soundex("Tymczak").as_deref(), Some("T522"));
+        assert_eq!(soundex("Puyallup"), soundex("Pualup"));
+        assert_eq!(encode("Wilkes-Barre"), "W422 B600");
+    }
+
+    #[test]
+    fn can_spell_identifiers() {
+        assert_eq!(spell("KSEA"), "Kilo Sierra Echo Alpha");
+        assert_eq!(spell("s50"), "Sierra Fife Zero");
+    }
+
+    #[test]
+    fn ranks_closer_spellings_higher() {
+        let heard = similarity("pyallup", "Pierce County Thun Field, Puyallup");
+        let other = similarity("pyallup", "Philip Airport, Philip");
+        assert!(heard > other);
+        assert_eq!(similarity("thun", "Thun Field"), 1.0);
+    }
+}
@@ -0,0 +1,150 @@
+use hashbrown::HashMap;
+use serde::Deserialize;
+
+/// A letter is treated as a country's own prefix (like K for the US) when that country holds at
+/// least this share of all ICAO-style identifiers starting with it.
+const SINGLE_LETTER_SHARE: f64 = 0.9;
+
+/// Prefixes covering less than this share of a country's identifiers are assumed to be noise in
+/// the data rather than a convention.
+const MINIMUM_SHARE: f64 = 0.01;
+
+/// The projection of an airport needed to derive prefixes.
+#[derive(Debug, Deserialize)]
+pub struct PrefixSource {
+    ident: String,
+    iso_country: String,
+}
+
+/// ICAO identifier prefix conventions per country, derived from the identifiers in the data.
+pub struct PrefixTable {
+    by_country: HashMap<String, Vec<(String, usize)>>,
+}
+
+impl PrefixTable {
+    pub fn from_airports(airports: impl IntoIterator<Item = PrefixSource>) -> Self {
+        let mut by_letter: HashMap<char, HashMap<String, usize>> = HashMap::new();
+        let mut by_pair: HashMap<String, HashMap<String, usize>> = HashMap::new();
+
+        for PrefixSource { ident, iso_country } in airports {
+            if !is_icao_style(&ident) {
+                continue;
+            }
+
+            let letter = ident.chars().next().unwrap();
+            *by_letter
+                .entry(letter)
+                .or_default()
+                .entry(iso_country.clone())
+                .or_default() += 1;
+            *by_pair
+                .entry(iso_country)
+                .or_default()
+                .entry(ident[..2].to_string())
+                .or_default() += 1;
+        }
+
+        let mut by_country: HashMap<String, Vec<(String, usize)>> = HashMap::new();
+        for (country, pairs) in by_pair {
+            let total: usize = pairs.values().sum();
+            let mut prefixes: HashMap<String, usize> = HashMap::new();
+
+            for (pair, count) in pairs {
+                let letter = pair.chars().next().unwrap();
+                let owners = &by_letter[&letter];
+                let share = owners[&country] as f64 / owners.values().sum::<usize>() as f64;
+
+                let prefix = if share >= SINGLE_LETTER_SHARE {
+                    letter.to_string()
+                } else {
+                    pair
+                };
+                *prefixes.entry(prefix).or_default() += count;
+            }
+
+            let mut prefixes: Vec<_> = prefixes
+                .into_iter()
+                .filter(|&(_, count)| count as f64 / total as f64 >= MINIMUM_SHARE)
+                .collect();
+            prefixes.sort_by(|a, b| b.1.cmp(&a.1).then_with(|| a.0.cmp(&b.0)));
+            by_country.insert(country, prefixes);
+        }
+
+        PrefixTable { by_country }
+    }
+
+    /// Prefixes used by a country, most common first, with the number of identifiers using each.
+    pub fn for_country(&self, country: &str) -> &[(String, usize)] {
+        self.by_country
+            .get(&country.to_ascii_uppercase())
+            .map(|prefixes| prefixes.as_slice())
+            .unwrap_or_default()
+    }
+
+    pub fn countries(&self) -> impl Iterator<Item = (&str, &[(String, usize)])> {
+        let mut countries: Vec<_> = self
+            .by_country
+            .iter()
+            .map(|(country, prefixes)| (country.as_str(), prefixes.as_slice()))
+            .collect();
+        countries.sort_by_key(|&(country, _)| country);
+        countries.into_iter()
+    }
+
+    /// Full identifiers a bare local code might be short for, e.g. KSEA for SEA.
+    ///
+    /// Only single-letter prefixes are considered, since those are the countries that build ICAO
+    /// identifiers by prefixing the local code.
+    pub fn candidates(&self, code: &str) -> Vec<String> {
+        if code.len() != 3 || !code.bytes().all(|u| u.is_ascii_alphanumeric()) {
+            return Vec::new();
+        }
+
+        let mut candidates: Vec<_> = self
+            .by_country
+            .values()
+            .flatten()
+            .filter(|(prefix, _)| prefix.len() == 1)
+            .map(|(prefix, _)| format!("{prefix}{}", code.to_ascii_uppercase()))
+            .collect();
+        candidates.sort_unstable();
+        candidates.dedup();
+        candidates
+    }
+}
+
+fn is_icao_style(ident: &str) -> bool {
+    ident.len() == 4 && ident.bytes().all(|u| u.is_ascii_uppercase())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{PrefixSource, PrefixTable};
+
+    fn source(ident: &str, iso_country: &str) -> PrefixSource {
+        PrefixSource {
+            ident: ident.into(),
+            iso_country: iso_country.into(),
+        }
+    }
+
+    #[test]
+    fn derives_single_and_double_letter_prefixes() {
+        let table = PrefixTable::from_airports([
+            source("KSEA", "US"),
+            source("KBFI", "US"),
+            source("PANC", "US"),
+            source("EGLL", "GB"),
+            source("EGKK", "GB"),
+            source("EDDF", "DE"),
+            source("00WA", "US"),
+        ]);
+
+        assert_eq!(
+            table.for_country("us"),
+            [("K".to_string(), 2), ("P".to_string(), 1)]
+        );
+        assert_eq!(table.for_country("GB"), [("EG".to_string(), 2)]);
+        assert_eq!(table.candidates("sea"), ["KSEA", "PSEA"]);
+    }
+}
@@ -0,0 +1,125 @@
+use std::fmt;
+
+use crate::{
+    locale::{format_number, tr, Message},
+    model::Airport,
+};
+
+/// Runway lengths are bucketed in steps of this many feet.
+const BUCKET_FT: i32 = 1000;
+
+/// Lengths at or beyond this many buckets share the last one.
+const BUCKETS: usize = 13;
+
+const BAR_WIDTH: usize = 40;
+
+/// Counts of missing or suspicious data across a set of airports.
+#[derive(Debug, Default)]
+pub struct QualityReport {
+    airports: usize,
+    missing_elevation: usize,
+    missing_coordinates: usize,
+    missing_iata: usize,
+    missing_runways: usize,
+    runway_lengths: [usize; BUCKETS],
+    unknown_runway_lengths: usize,
+}
+
+impl QualityReport {
+    pub fn from_airports(airports: impl IntoIterator<Item = Airport>) -> Self {
+        let mut report = QualityReport::default();
+
+        for airport in airports {
+            report.airports += 1;
+            report.missing_elevation += airport.elevation_ft.is_none() as usize;
+            report.missing_iata += airport.iata_code.is_none() as usize;
+            report.missing_runways += airport.runways.is_empty() as usize;
+
+            // The CSV requires coordinates, so the only way to be missing them is to be sitting
+            // at null island.
+            let coords = airport.coordinates;
+            report.missing_coordinates +=
+                (coords.latitude == 0.0 && coords.longitude == 0.0) as usize;
+
+            for runway in &airport.runways {
+                match runway.length {
+                    Some(length) => {
+                        let bucket = (length.0.max(0) / BUCKET_FT) as usize;
+                        report.runway_lengths[bucket.min(BUCKETS - 1)] += 1;
+                    }
+                    None => report.unknown_runway_lengths += 1,
+                }
+            }
+        }
+
+        report
+    }
+
+    fn write_count(&self, f: &mut fmt::Formatter<'_>, label: Message, count: usize) -> fmt::Result {
+        let share = if self.airports == 0 {
+            0.0
+        } else {
+            count as f64 / self.airports as f64 * 100.0
+        };
+
+        writeln!(
+            f,
+            "  {:<24} {:>8} ({}%)",
+            tr(label),
+            format_number(count as f64, 0),
+            format_number(share, 1)
+        )
+    }
+}
+
+impl fmt::Display for QualityReport {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        writeln!(
+            f,
+            "{}: {}",
+            tr(Message::Airports),
+            format_number(self.airports as f64, 0)
+        )?;
+        self.write_count(f, Message::MissingElevation, self.missing_elevation)?;
+        self.write_count(f, Message::MissingCoordinates, self.missing_coordinates)?;
+        self.write_count(f, Message::MissingIata, self.missing_iata)?;
+        self.write_count(f, Message::MissingRunways, self.missing_runways)?;
+
+        writeln!(f, "\n{} (ft):", tr(Message::RunwayLengths))?;
+        let largest = self
+            .runway_lengths
+            .iter()
+            .copied()
+            .chain([self.unknown_runway_lengths])
+            .max()
+            .unwrap_or(0)
+            .max(1);
+
+        let rows = self.runway_lengths.iter().enumerate().map(|(idx, &count)| {
+            let low = idx as i32 * BUCKET_FT;
+            let label = if idx == BUCKETS - 1 {
+                format!("{}+", format_number(low.into(), 0))
+            } else {
+                format!(
+                    "{}–{}",
+                    format_number(low.into(), 0),
+                    format_number((low + BUCKET_FT - 1).into(), 0)
+                )
+            };
+            (label, count)
+        });
+
+        for (label, count) in
+            rows.chain([(tr(Message::Unknown).into(), self.unknown_runway_lengths)])
+        {
+            let bar = "#".repeat(count * BAR_WIDTH / largest);
+            writeln!(
+                f,
+                "  {label:>13} {:>8} {bar}",
+                format_number(count as f64, 0)
+            )?;
+        }
+
+        Ok(())
+    }
+}
@@ -0,0 +1,127 @@
+//! Airport searches built from typed criteria rather than query syntax.
+
+use crate::{
+    model::{Airport, AirportKind, Coords},
+    units::NauticalMiles,
+};
+
+/// A search for airports, run with [`Database::query`](crate::database::Database::query):
+///
+/// ```no_run
+/// # use adb::{database::Database, model::{AirportKind, Coords}, query::AirportQuery};
+/// # let db = Database::initialize("index".as_ref()).unwrap();
+/// let seattle = Coords { latitude: 47.45, longitude: -122.31 };
+/// let query = AirportQuery::new()
+///     .country("US")
+///     .kind(AirportKind::SmallAirport)
+///     .min_runway_ft(3000)
+///     .near(seattle, 50.0);
+/// for airport in db.query(&query).unwrap().take(10) {
+///     println!("{}", airport.unwrap().ident);
+/// }
+/// ```
+///
+/// Every criterion is answered by the index, `near` by its spatial index, so only matching airports
+/// are read.
+#[derive(Clone, Debug, Default)]
+pub struct AirportQuery {
+    pub(crate) text: Option<String>,
+    pub(crate) country: Option<String>,
+    pub(crate) region: Option<String>,
+    pub(crate) kind: Option<AirportKind>,
+    pub(crate) min_runway_ft: Option<i32>,
+
+    /// center and radius in meters
+    pub(crate) near: Option<(Coords, f64)>,
+}
+
+impl AirportQuery {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Free text in the same syntax as `adb search`, matched against identifiers, names, towns
+    /// and keywords.
+    pub fn text(mut self, text: impl Into<String>) -> Self {
+        self.text = Some(text.into());
+        self
+    }
+
+    /// ISO 3166 country code, e.g. `US`.
+    pub fn country(mut self, country: impl Into<String>) -> Self {
+        self.country = Some(country.into().to_uppercase());
+        self
+    }
+
+    /// ISO 3166-2 region code, e.g. `US-WA`.
+    pub fn region(mut self, region: impl Into<String>) -> Self {
+        self.region = Some(region.into().to_uppercase());
+        self
+    }
+
+    pub fn kind(mut self, kind: AirportKind) -> Self {
+        self.kind = Some(kind);
+        self
+    }
+
+    /// Only airports with an open runway at least this long.
+    pub fn min_runway_ft(mut self, feet: i32) -> Self {
+        self.min_runway_ft = Some(feet);
+        self
+    }
+
+    /// Only airports within `nautical_miles` of a point.
+    pub fn near(mut self, coords: Coords, nautical_miles: f64) -> Self {
+        self.near = Some((coords, NauticalMiles(nautical_miles).to_meters().0));
+        self
+    }
+
+    /// True if the airport meets the criteria other than text, country and region, for checking
+    /// airports that didn't come from the index.
+    pub fn matches(&self, airport: &Airport) -> bool {
+        self.kind.is_none_or(|kind| airport.kind == kind)
+            && self.min_runway_ft.is_none_or(|feet| {
+                airport
+                    .runways
+                    .iter()
+                    .any(|runway| !runway.is_closed && runway.length.is_some_and(|l| l.0 >= feet))
+            })
+            && self.near.is_none_or(|(coords, meters)| {
+                coords.distance_to(&airport.coordinates).meters() <= meters
+            })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::AirportQuery;
+    use crate::{
+        golden::airports,
+        model::{AirportKind, Coords},
+    };
+
+    #[test]
+    fn matches_criteria_the_index_cannot() {
+        let airports = airports();
+        let seattle = Coords {
+            latitude: 47.45,
+            longitude: -122.31,
+        };
+
+        let query = AirportQuery::new().min_runway_ft(5000).near(seattle, 50.0);
+        let idents: Vec<_> = airports
+            .iter()
+            .filter(|airport| query.matches(airport))
+            .map(|airport| airport.ident.as_str())
+            .collect();
+        assert_eq!(idents, ["KSEA", "KPAE"]);
+
+        let query = AirportQuery::new().kind(AirportKind::SmallAirport);
+        let idents: Vec<_> = airports
+            .iter()
+            .filter(|airport| query.matches(airport))
+            .map(|airport| airport.ident.as_str())
+            .collect();
+        assert_eq!(idents, ["K0S9"]);
+    }
+}
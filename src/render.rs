@@ -0,0 +1,707 @@
+use std::{
+    io::{self, Write},
+    process::{Command, Stdio},
+};
+
+use hashbrown::HashSet;
+
+use crate::{
+    error::Error,
+    geo::{self, BoundingBox},
+    local_info::LocalInfo,
+    locale::{format_number, tr, Message},
+    model::{self, Airport},
+    pairs::Pairs,
+    phonetic,
+    route::Route,
+    units::{DistanceUnit, Meters, NauticalMiles},
+    waypoint::Waypoint,
+};
+
+/// Renders looked-up airports for output.
+pub trait Renderer {
+    fn render(&mut self, airport: &Airport, out: &mut dyn Write) -> io::Result<()>;
+
+    /// Called once after every airport has been rendered.
+    fn finish(&mut self, _out: &mut dyn Write) -> crate::Result<()> {
+        Ok(())
+    }
+
+    /// True for output meant for other programs, such as JSON or KML, which nothing else may be
+    /// written alongside.
+    fn is_structured(&self) -> bool {
+        false
+    }
+}
+
+/// The standard multi-line airport display.
+pub struct Full;
+
+impl Renderer for Full {
+    fn render(&mut self, airport: &Airport, out: &mut dyn Write) -> io::Result<()> {
+        writeln!(out, "{airport}")
+    }
+}
+
+/// One line per airport.
+pub struct Short;
+
+impl Renderer for Short {
+    fn render(&mut self, airport: &Airport, out: &mut dyn Write) -> io::Result<()> {
+        writeln!(out, "{}", airport.short())
+    }
+}
+
+/// Only the runway table.
+pub struct Runways;
+
+impl Renderer for Runways {
+    fn render(&mut self, airport: &Airport, out: &mut dyn Write) -> io::Result<()> {
+        write!(out, "{}", airport.runway_table())
+    }
+}
+
+/// Only the coordinates, as `lat lon`.
+pub struct Coords;
+
+impl Renderer for Coords {
+    fn render(&mut self, airport: &Airport, out: &mut dyn Write) -> io::Result<()> {
+        let coords = airport.coordinates;
+        writeln!(out, "{} {}", coords.latitude, coords.longitude)
+    }
+}
+
+/// One JSON object per airport and per line, in the same shape as the stored records, for `jq`
+/// and the like.
+pub struct Json;
+
+impl Renderer for Json {
+    fn render(&mut self, airport: &Airport, out: &mut dyn Write) -> io::Result<()> {
+        writeln!(out, "{}", serde_json::to_string(airport).unwrap())
+    }
+
+    fn is_structured(&self) -> bool {
+        true
+    }
+}
+
+/// A KML document with a placemark per airport, for Google Earth. Written all at once at the end,
+/// since the airports go inside one document.
+#[cfg(feature = "export")]
+#[derive(Default)]
+pub struct Kml {
+    airports: Vec<Airport>,
+}
+
+#[cfg(feature = "export")]
+impl Renderer for Kml {
+    fn render(&mut self, airport: &Airport, _out: &mut dyn Write) -> io::Result<()> {
+        self.airports.push(airport.clone());
+        Ok(())
+    }
+
+    fn finish(&mut self, out: &mut dyn Write) -> crate::Result<()> {
+        out.write_all(crate::export::kml_airports(&self.airports).as_bytes())?;
+        Ok(())
+    }
+
+    fn is_structured(&self) -> bool {
+        true
+    }
+}
+
+/// Follows another renderer's output with the identifier in the spelling alphabet, for reading it
+/// out over the radio.
+pub struct Spelled(pub Box<dyn Renderer>);
+
+impl Renderer for Spelled {
+    fn render(&mut self, airport: &Airport, out: &mut dyn Write) -> io::Result<()> {
+        self.0.render(airport, out)?;
+        writeln!(out, "  {}", phonetic::spell(&airport.ident))
+    }
+
+    fn finish(&mut self, out: &mut dyn Write) -> crate::Result<()> {
+        self.0.finish(out)
+    }
+
+    fn is_structured(&self) -> bool {
+        self.0.is_structured()
+    }
+}
+
+/// Follows another renderer's output with the user's own notes on the airport, if they have any.
+pub struct WithLocalInfo(pub Box<dyn Renderer>, pub LocalInfo);
+
+impl Renderer for WithLocalInfo {
+    fn render(&mut self, airport: &Airport, out: &mut dyn Write) -> io::Result<()> {
+        // Rendered aside first, to see whether it already ends with a blank line.
+        let mut rendered = Vec::new();
+        self.0.render(airport, &mut rendered)?;
+        out.write_all(&rendered)?;
+
+        let details: Vec<_> = self.1.details(&airport.ident).collect();
+        if details.is_empty() {
+            return Ok(());
+        }
+
+        let width = details
+            .iter()
+            .map(|(k, _)| k.chars().count())
+            .max()
+            .unwrap_or(0);
+        if !rendered.ends_with(b"\n\n") {
+            writeln!(out)?;
+        }
+        writeln!(out, "{}:", tr(Message::LocalInfo))?;
+        for (column, value) in details {
+            writeln!(out, "  {column:<width$}  {value}")?;
+        }
+        Ok(())
+    }
+
+    fn finish(&mut self, out: &mut dyn Write) -> crate::Result<()> {
+        self.0.finish(out)
+    }
+
+    fn is_structured(&self) -> bool {
+        self.0.is_structured()
+    }
+}
+
+/// Hands airports to an external program and prints whatever it writes.
+///
+/// The program is run once per invocation. It receives one JSON object per airport on stdin, in
+/// the same shape as the stored records, and its stdout becomes our output. This lets people use
+/// their own briefing formats without patching adb.
+///
+/// The command is split on whitespace and run directly, not through a shell, so its arguments
+/// can't be quoted; a program that needs them should be wrapped in a script.
+pub struct External {
+    command: String,
+    input: String,
+}
+
+impl External {
+    pub fn new(command: impl Into<String>) -> Self {
+        External {
+            command: command.into(),
+            input: String::new(),
+        }
+    }
+}
+
+impl Renderer for External {
+    fn render(&mut self, airport: &Airport, _out: &mut dyn Write) -> io::Result<()> {
+        self.input += &serde_json::to_string(airport).unwrap();
+        self.input.push('\n');
+        Ok(())
+    }
+
+    fn finish(&mut self, out: &mut dyn Write) -> crate::Result<()> {
+        let mut parts = self.command.split_whitespace();
+        let program = parts
+            .next()
+            .ok_or_else(|| Error::Renderer(self.command.clone()))?;
+
+        let mut child = Command::new(program)
+            .args(parts)
+            .stdin(Stdio::piped())
+            .stdout(Stdio::piped())
+            .spawn()?;
+
+        // Write from another thread so a renderer that produces output as it reads can't
+        // deadlock against us.
+        let mut stdin = child.stdin.take().unwrap();
+        let input = std::mem::take(&mut self.input);
+        let writer = std::thread::spawn(move || stdin.write_all(input.as_bytes()));
+
+        let output = child.wait_with_output()?;
+        let written = writer.join().unwrap();
+
+        // What the program managed to print is kept even if it failed.
+        out.write_all(&output.stdout)?;
+        if !output.status.success() {
+            return Err(Error::Renderer(self.command.clone()));
+        }
+
+        // A program may well stop reading once it has what it needs, which is up to it.
+        match written {
+            Err(e) if e.kind() != io::ErrorKind::BrokenPipe => Err(e.into()),
+            _ => Ok(()),
+        }
+    }
+
+    /// We can't know what the program writes, so it's left alone.
+    fn is_structured(&self) -> bool {
+        true
+    }
+}
+
+pub fn print_distance(
+    route: &Route,
+    verbose: bool,
+    unit: DistanceUnit,
+    out: &mut dyn Write,
+) -> io::Result<()> {
+    let mut preformat_records = Vec::new();
+    let mut dist_column_width = 0;
+
+    for leg in &route.legs {
+        let (left, right) = route.ends(leg);
+        let formatted_distance = format_number(unit.from_meters(leg.distance), 1);
+        dist_column_width = formatted_distance.len().max(dist_column_width);
+        preformat_records.push((left.name(), right.name(), formatted_distance));
+    }
+
+    let leg_column_width = preformat_records.len().to_string().len();
+    for (idx, (a, b, dist)) in preformat_records.into_iter().enumerate() {
+        if verbose {
+            write!(out, "{:>leg_column_width$}  ", idx + 1)?;
+        }
+        writeln!(out, "{a:>4} -> {b:>4}  {dist:>dist_column_width$}")?;
+    }
+
+    if verbose {
+        print_waypoints(&route.waypoints, out)?;
+    }
+
+    writeln!(
+        out,
+        "\n{}: {}",
+        tr(Message::TotalDistance),
+        route.distance().display(unit)
+    )
+}
+
+/// Lists each leg's initial course true and magnetic, given the variation at the start of each leg
+/// (east positive) as far as it's known.
+pub fn print_magnetic_courses(
+    route: &Route,
+    variations: &[Option<f64>],
+    out: &mut dyn Write,
+) -> io::Result<()> {
+    writeln!(out, "\n{}:", tr(Message::MagneticCourses))?;
+
+    for (leg, variation) in route.legs.iter().zip(variations) {
+        let (left, right) = route.ends(leg);
+        write!(
+            out,
+            "  {:>4} -> {:>4}  {:03.0}°T  ",
+            left.name(),
+            right.name(),
+            leg.course
+        )?;
+        match variation {
+            Some(variation) => {
+                let course = (leg.course - variation).rem_euclid(360.0);
+                let side = if *variation < 0.0 { "W" } else { "E" };
+                writeln!(out, "{course:03.0}°M  ({:.1}°{side})", variation.abs())
+            }
+            None => writeln!(out, "{}", tr(Message::Unknown)),
+        }?;
+    }
+    Ok(())
+}
+
+/// Checks each leg's course against the VFR hemispheric rule: odd thousands plus 500 feet for
+/// magnetic courses from 000 to 179, even thousands plus 500 from 180 to 359.
+///
+/// `variations` holds each leg's variation, east positive; without them, courses are checked as
+/// true.
+pub fn print_altitude_check(
+    route: &Route,
+    altitude: u32,
+    variations: Option<&[f64]>,
+    out: &mut dyn Write,
+) -> io::Result<()> {
+    writeln!(
+        out,
+        "\n{} ({} ft, {}):",
+        tr(Message::AltitudeCheck),
+        format_number(altitude.into(), 0),
+        match variations {
+            Some(_) => tr(Message::Magnetic),
+            None => tr(Message::True),
+        }
+    )?;
+
+    // An altitude like 8000 isn't a VFR cruising altitude in either direction.
+    let thousands = (altitude >= 500 && altitude % 1000 == 500).then_some(altitude / 1000);
+
+    for (i, leg) in route.legs.iter().enumerate() {
+        let (left, right) = route.ends(leg);
+        let variation = variations.map_or(0.0, |variations| variations[i]);
+        let course = (leg.course - variation).rem_euclid(360.0);
+        let eastbound = course < 180.0;
+        let ok = thousands.is_some_and(|thousands| (thousands % 2 == 1) == eastbound);
+
+        write!(
+            out,
+            "  {:>4} -> {:>4}  {course:03.0}°  ",
+            left.name(),
+            right.name()
+        )?;
+        match (ok, eastbound) {
+            (true, _) => writeln!(out, "{}", tr(Message::Ok)),
+            (false, true) => writeln!(out, "{}", tr(Message::UseOddThousands)),
+            (false, false) => writeln!(out, "{}", tr(Message::UseEvenThousands)),
+        }?;
+    }
+    Ok(())
+}
+
+/// Prints the ends of a track `offset` nautical miles to the right (or left, if negative) of the
+/// direct track between two points, with distances shown in `unit`.
+pub fn print_offset(
+    origin: &Waypoint,
+    destination: &Waypoint,
+    offset: f64,
+    unit: DistanceUnit,
+    out: &mut dyn Write,
+) -> io::Result<()> {
+    let (a, b) = (origin.coordinates(), destination.coordinates());
+    let meters = NauticalMiles(offset).to_meters().0;
+
+    // The course changes along a great circle, so each end is pushed out square to the course
+    // at that end.
+    let initial = a.bearing_to(&b);
+    let last = (b.bearing_to(&a) + 180.0).rem_euclid(360.0);
+    let start = geo::destination(&a, initial + 90.0, meters);
+    let end = geo::destination(&b, last + 90.0, meters);
+
+    let direct = Meters(a.distance_to(&b).meters()).display(unit);
+    let parallel = Meters(start.distance_to(&end).meters()).display(unit);
+    let side = if offset < 0.0 {
+        tr(Message::Left)
+    } else {
+        tr(Message::Right)
+    };
+
+    writeln!(
+        out,
+        "{} -> {}, {} {side}",
+        origin.name(),
+        destination.name(),
+        Meters(meters.abs()).display(unit)
+    )?;
+    writeln!(out, "  {}  {start}", origin.name())?;
+    writeln!(out, "  {}  {end}", destination.name())?;
+    writeln!(
+        out,
+        "\n{}: {parallel} ({} {direct})",
+        tr(Message::TotalDistance),
+        tr(Message::Direct),
+    )
+}
+
+pub fn print_fuel_stops(
+    stops: &[Airport],
+    added: Meters,
+    unit: DistanceUnit,
+    out: &mut dyn Write,
+) -> io::Result<()> {
+    writeln!(out, "\n{}:", tr(Message::FuelStops))?;
+    for airport in stops {
+        writeln!(out, "  {}", airport.short())?;
+    }
+    writeln!(
+        out,
+        "{}: {}",
+        tr(Message::AddedDistance),
+        added.display(unit)
+    )
+}
+
+pub fn print_route_diff(
+    a: (&str, &[Waypoint]),
+    b: (&str, &[Waypoint]),
+    unit: DistanceUnit,
+    out: &mut dyn Write,
+) -> io::Result<()> {
+    type Leg = (String, String, Meters);
+
+    fn legs(route: &[Waypoint]) -> Vec<Leg> {
+        route
+            .iter()
+            .pairs()
+            .map(|(left, right)| {
+                let dist = Meters(left.distance_to(right).meters());
+                (left.name().to_string(), right.name().to_string(), dist)
+            })
+            .collect()
+    }
+
+    fn same_leg(x: &Leg, y: &Leg) -> bool {
+        x.0 == y.0 && x.1 == y.1
+    }
+
+    let (name_a, route_a) = a;
+    let (name_b, route_b) = b;
+    let legs_a = legs(route_a);
+    let legs_b = legs(route_b);
+    let total_a: Meters = legs_a.iter().map(|leg| leg.2).sum();
+    let total_b: Meters = legs_b.iter().map(|leg| leg.2).sum();
+
+    // Legs flown by A come first, marked `-` if B doesn't fly them, followed by B's own legs.
+    let rows = legs_a
+        .iter()
+        .map(|leg| {
+            let mark = if legs_b.iter().any(|other| same_leg(leg, other)) {
+                ' '
+            } else {
+                '-'
+            };
+            (mark, leg)
+        })
+        .chain(
+            legs_b
+                .iter()
+                .filter(|leg| !legs_a.iter().any(|other| same_leg(leg, other)))
+                .map(|leg| ('+', leg)),
+        );
+
+    for (mark, (from, to, dist)) in rows {
+        writeln!(
+            out,
+            "{mark} {from:>4} -> {to:>4}  {:>8}",
+            format_number(unit.from_meters(*dist), 1)
+        )?;
+    }
+
+    // Shared waypoints in the order A reaches them, each once.
+    let names_b: HashSet<_> = route_b.iter().map(|w| w.name().to_string()).collect();
+    let mut seen = HashSet::new();
+    let shared: Vec<_> = route_a
+        .iter()
+        .map(|w| w.name().to_string())
+        .filter(|name| names_b.contains(name) && seen.insert(name.clone()))
+        .collect();
+    writeln!(
+        out,
+        "\n{}: {}",
+        tr(Message::SharedWaypoints),
+        shared.join(" ")
+    )?;
+
+    let name_width = name_a.chars().count().max(name_b.chars().count());
+    writeln!(out, "\n{}:", tr(Message::TotalDistance))?;
+    for (name, total) in [(name_a, total_a), (name_b, total_b)] {
+        writeln!(out, "  {name:<name_width$}  {:>11}", total.display(unit))?;
+    }
+
+    let (sign, difference) = if total_b >= total_a {
+        ("+", total_b - total_a)
+    } else {
+        ("-", total_a - total_b)
+    };
+    writeln!(
+        out,
+        "{}: {sign}{}",
+        tr(Message::Difference),
+        difference.display(unit)
+    )
+}
+
+fn print_waypoints(route: &[Waypoint], out: &mut dyn Write) -> io::Result<()> {
+    let names: Vec<_> = route.iter().map(|w| w.name().to_string()).collect();
+    let name_width = names
+        .iter()
+        .map(|name| name.chars().count())
+        .max()
+        .unwrap_or(0);
+
+    writeln!(out, "\n{}:", tr(Message::Waypoints))?;
+    for (waypoint, name) in route.iter().zip(names) {
+        let coords = waypoint.coordinates();
+        match waypoint {
+            Waypoint::Airport(airport) => writeln!(
+                out,
+                "  {name:<name_width$}  {coords}  {} (ident {})",
+                airport.name, airport.ident
+            ),
+            Waypoint::Coords(_) | Waypoint::Named(..) => writeln!(
+                out,
+                "  {name:<name_width$}  {coords}  ({})",
+                tr(Message::Coordinates)
+            ),
+        }?;
+    }
+    Ok(())
+}
+
+pub fn print_bounds(route: &[Waypoint], out: &mut dyn Write) -> io::Result<()> {
+    let points: Vec<_> = route.iter().map(Waypoint::coordinates).collect();
+    let (Some(bounds), Some(center)) = (BoundingBox::from_points(&points), geo::center(&points))
+    else {
+        return Ok(());
+    };
+
+    writeln!(
+        out,
+        "{}: {} / {}\n{}: {center}",
+        tr(Message::Bounds),
+        bounds.south_west(),
+        bounds.north_east(),
+        tr(Message::Center),
+    )
+}
+
+pub fn print_nearest(
+    coords: &model::Coords,
+    airports: Vec<Airport>,
+    unit: DistanceUnit,
+    out: &mut dyn Write,
+) -> io::Result<()> {
+    let records: Vec<_> = airports
+        .into_iter()
+        .map(|airport| {
+            let distance = Meters(coords.distance_to(&airport.coordinates).meters());
+            let distance = distance.display(unit);
+            let bearing = coords.bearing_to(&airport.coordinates);
+            (airport, distance, bearing)
+        })
+        .collect();
+
+    let ident_width = records.iter().map(|r| r.0.ident.len()).max().unwrap_or(0);
+    let dist_width = records.iter().map(|r| r.1.len()).max().unwrap_or(0);
+
+    for (airport, distance, bearing) in records {
+        writeln!(
+            out,
+            "{:<ident_width$}  {distance:>dist_width$}  {bearing:03.0}°  {}",
+            airport.ident, airport.name
+        )?;
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use std::io::Write;
+
+    use super::{
+        print_altitude_check, print_bounds, print_distance, print_fuel_stops,
+        print_magnetic_courses, print_nearest, print_offset, print_route_diff, Coords, External,
+        Full, Json, Renderer, Runways, Short, Spelled, WithLocalInfo,
+    };
+    use crate::{
+        golden::{airports, assert_golden, testdata},
+        local_info::LocalInfo,
+        locale::Locale,
+        model,
+        route::Route,
+        units::{DistanceUnit, Meters},
+        waypoint::Waypoint,
+    };
+
+    fn render(name: &str, mut renderer: impl Renderer) {
+        // Output is only stable in one locale; this is the default for an unset or C locale.
+        Locale::set_for_thread(Locale::English);
+
+        let mut out = Vec::new();
+        for airport in airports() {
+            renderer.render(&airport, &mut out).unwrap();
+        }
+        renderer.finish(&mut out).unwrap();
+        assert_golden(name, &String::from_utf8(out).unwrap());
+    }
+
+    #[test]
+    fn airport_formats_match_golden_files() {
+        let local_info = LocalInfo::load(&testdata("local-info.csv")).unwrap();
+
+        render("full.txt", Full);
+        render("short.txt", Short);
+        render("runways.txt", Runways);
+        render("coords.txt", Coords);
+        render("json.txt", Json);
+        render("spelled.txt", Spelled(Box::new(Short)));
+        render("local-info.txt", WithLocalInfo(Box::new(Full), local_info));
+    }
+
+    #[test]
+    fn route_formats_match_golden_files() {
+        Locale::set_for_thread(Locale::English);
+
+        let airports = airports();
+        let mut route: Vec<Waypoint> = airports[..3].iter().cloned().map(Waypoint::from).collect();
+        let fix = model::Coords {
+            latitude: 47.2,
+            longitude: -122.9,
+        };
+        route.insert(1, Waypoint::from(fix));
+
+        let mut out = Vec::new();
+        let planned = Route::new(route.clone());
+        print_distance(&planned, true, DistanceUnit::NauticalMiles, &mut out).unwrap();
+        print_altitude_check(&planned, 5500, Some(&[15.0, 15.2, -0.5]), &mut out).unwrap();
+        print_magnetic_courses(&planned, &[Some(15.2), Some(-0.5), None], &mut out).unwrap();
+        print_fuel_stops(
+            &airports[1..2],
+            Meters(12_345.0),
+            DistanceUnit::NauticalMiles,
+            &mut out,
+        )
+        .unwrap();
+        writeln!(out).unwrap();
+        print_bounds(&route, &mut out).unwrap();
+        assert_golden("dist.txt", &String::from_utf8(out).unwrap());
+
+        let mut out = Vec::new();
+        print_offset(
+            &route[0],
+            &route[2],
+            -5.0,
+            DistanceUnit::NauticalMiles,
+            &mut out,
+        )
+        .unwrap();
+        assert_golden("offset.txt", &String::from_utf8(out).unwrap());
+
+        let mut out = Vec::new();
+        print_nearest(
+            &fix,
+            airports.clone(),
+            DistanceUnit::NauticalMiles,
+            &mut out,
+        )
+        .unwrap();
+        assert_golden("nearest.txt", &String::from_utf8(out).unwrap());
+
+        let mut out = Vec::new();
+        let other = [route[0].clone(), route[3].clone(), route[2].clone()];
+        print_route_diff(
+            ("a", &route),
+            ("b", &other),
+            DistanceUnit::NauticalMiles,
+            &mut out,
+        )
+        .unwrap();
+        assert_golden("route-diff.txt", &String::from_utf8(out).unwrap());
+    }
+
+    #[test]
+    #[cfg(unix)]
+    fn external_renderers_may_stop_reading_early() {
+        let airports = airports();
+
+        // Enough input to fill the pipe, so we're still writing when the program exits.
+        let mut renderer = External::new("head -c 1");
+        let mut out = Vec::new();
+        for _ in 0..1000 {
+            for airport in &airports {
+                renderer.render(airport, &mut out).unwrap();
+            }
+        }
+        renderer.finish(&mut out).unwrap();
+        assert_eq!(out, b"{");
+
+        let mut renderer = External::new("true");
+        renderer.render(&airports[0], &mut out).unwrap();
+        renderer.finish(&mut out).unwrap();
+        assert_eq!(out, b"{");
+
+        let mut renderer = External::new("false");
+        assert!(renderer.finish(&mut out).is_err());
+    }
+}
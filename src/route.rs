@@ -0,0 +1,201 @@
+//! A route worked out once, leg by leg, for the renderers and exporters to share.
+
+use crate::{
+    error::Error,
+    geo,
+    lookup::Lookup,
+    model::Airport,
+    pairs::Pairs,
+    units::{Meters, NauticalMiles},
+    waypoint::Waypoint,
+};
+
+#[derive(Clone, Debug)]
+pub struct Route {
+    pub waypoints: Vec<Waypoint>,
+    pub legs: Vec<Leg>,
+}
+
+/// The stretch between two consecutive waypoints of a route.
+#[derive(Clone, Copy, Debug)]
+pub struct Leg {
+    /// index of the waypoint the leg starts from; it ends at the next one
+    pub from: usize,
+
+    /// measured on the ellipsoid
+    pub distance: Meters,
+
+    /// initial great circle course, in degrees true
+    pub course: f64,
+}
+
+impl Route {
+    pub fn new(waypoints: Vec<Waypoint>) -> Self {
+        let legs = waypoints
+            .iter()
+            .pairs()
+            .enumerate()
+            .map(|(from, (left, right))| Leg {
+                from,
+                distance: Meters(left.distance_to(right).meters()),
+                course: left.coordinates().bearing_to(&right.coordinates()),
+            })
+            .collect();
+
+        Route { waypoints, legs }
+    }
+
+    pub fn distance(&self) -> Meters {
+        self.legs.iter().map(|leg| leg.distance).sum()
+    }
+
+    /// The waypoints at either end of a leg.
+    pub fn ends(&self, leg: &Leg) -> (&Waypoint, &Waypoint) {
+        (&self.waypoints[leg.from], &self.waypoints[leg.from + 1])
+    }
+}
+
+impl Leg {
+    pub fn nautical_miles(&self) -> NauticalMiles {
+        self.distance.into()
+    }
+
+    /// Time en route in hours at a ground speed in knots.
+    pub fn hours(&self, ground_speed: f64) -> f64 {
+        self.nautical_miles().0 / ground_speed
+    }
+
+    /// Fuel used at a ground speed in knots and a burn in units per hour, in the same units.
+    pub fn fuel(&self, ground_speed: f64, burn: f64) -> f64 {
+        self.hours(ground_speed) * burn
+    }
+}
+
+/// Total distance along a list of waypoints, without building a [`Route`].
+pub fn length(waypoints: &[Waypoint]) -> Meters {
+    waypoints
+        .iter()
+        .pairs()
+        .map(|(left, right)| Meters(left.distance_to(right).meters()))
+        .sum()
+}
+
+/// Breaks up legs longer than `max_leg` nautical miles with stops at airports that have a runway of
+/// at least `min_runway` feet, chosen from `candidates` if given or else from anywhere.
+///
+/// Stops are chosen greedily: from each point we take the reachable airport that leaves the least
+/// distance to the end of the leg. Returns the new route and the stops added to it.
+pub fn insert_fuel_stops(
+    db: &dyn Lookup,
+    route: Vec<Waypoint>,
+    max_leg: f64,
+    min_runway: i32,
+    candidates: Option<&[Airport]>,
+) -> crate::Result<(Vec<Waypoint>, Vec<Airport>)> {
+    const CANDIDATES: usize = 250;
+
+    let range = NauticalMiles(max_leg).to_meters().0;
+    let suitable = |airport: &Airport| {
+        airport.kind.is_airport()
+            && airport.runways.iter().any(|runway| {
+                !runway.is_closed && runway.length.is_some_and(|length| length.0 >= min_runway)
+            })
+    };
+
+    let mut planned = Vec::with_capacity(route.len());
+    let mut stops = Vec::new();
+    let mut waypoints = route.into_iter();
+    let Some(first) = waypoints.next() else {
+        return Ok((planned, stops));
+    };
+    planned.push(first);
+
+    for destination in waypoints {
+        let target = destination.coordinates();
+        loop {
+            let current = planned.last().unwrap().coordinates();
+            let remaining = current.distance_to(&target).meters();
+            if remaining <= range {
+                break;
+            }
+
+            // Look for airports around points progressively closer to where we are, in case
+            // the best-placed ones are out over water or unsuitable.
+            let mut best: Option<(Airport, f64)> = None;
+            for reach in [0.9, 0.7, 0.5, 0.3] {
+                let fraction = range * reach / remaining;
+                let probe = geo::intermediate(&current, &target, fraction);
+
+                let nearby = match candidates {
+                    Some(candidates) => candidates.to_vec(),
+                    None => db.nearest(&probe, CANDIDATES)?,
+                };
+                for airport in nearby {
+                    let position = airport.coordinates;
+                    if !suitable(&airport) || current.distance_to(&position).meters() > range {
+                        continue;
+                    }
+                    let left = position.distance_to(&target).meters();
+                    if left < remaining && best.as_ref().is_none_or(|(_, closest)| left < *closest)
+                    {
+                        best = Some((airport, left));
+                    }
+                }
+
+                if best.is_some() {
+                    break;
+                }
+            }
+
+            let Some((airport, _)) = best else {
+                return Err(Error::NoFuelStop(
+                    planned.last().unwrap().name().to_string(),
+                ));
+            };
+            stops.push(airport.clone());
+            planned.push(Waypoint::Airport(airport));
+        }
+        planned.push(destination);
+    }
+
+    Ok((planned, stops))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::insert_fuel_stops;
+    use crate::{embedded::Embedded, error::Error, golden::airports, waypoint::Waypoint};
+
+    #[test]
+    fn stops_where_legs_are_too_long() {
+        let airports = airports();
+        let data = Embedded::from_airports(airports.clone());
+        let airport = |ident: &str| {
+            let airport = airports.iter().find(|airport| airport.ident == ident);
+            Waypoint::from(airport.unwrap().clone())
+        };
+        let route = vec![airport("KSEA"), airport("K0S9")];
+        let names = |route: &[Waypoint]| {
+            route
+                .iter()
+                .map(|waypoint| waypoint.name().to_string())
+                .collect::<Vec<_>>()
+        };
+
+        // Seattle to Port Townsend is a little over 40 nm, and Paine Field is on the way.
+        let (planned, stops) = insert_fuel_stops(&data, route.clone(), 30.0, 3000, None).unwrap();
+        assert_eq!(names(&planned), ["KSEA", "KPAE", "K0S9"]);
+        assert_eq!(stops.len(), 1);
+
+        let (planned, stops) = insert_fuel_stops(&data, route.clone(), 50.0, 3000, None).unwrap();
+        assert_eq!(names(&planned), ["KSEA", "K0S9"]);
+        assert!(stops.is_empty());
+
+        // No stop within reach, whether the range is short or only Frankfurt sells the fuel.
+        let stranded = insert_fuel_stops(&data, route.clone(), 10.0, 3000, None);
+        assert!(matches!(stranded, Err(Error::NoFuelStop(from)) if from == "KSEA"));
+        let frankfurt = &airports[3..4];
+        let stranded = insert_fuel_stops(&data, route, 30.0, 3000, Some(frankfurt));
+        assert!(matches!(stranded, Err(Error::NoFuelStop(_))));
+    }
+}
@@ -1,67 +1,316 @@
-use std::{fs, io};
+use std::{
+    fs, io,
+    path::{Path, PathBuf},
+    sync::Arc,
+};
 
-use csv::Reader;
-use directories::ProjectDirs;
-use hashbrown::HashMap;
 use tantivy::{
-    directory::MmapDirectory,
+    directory::{
+        error::{DeleteError, LockError, OpenReadError, OpenWriteError},
+        Directory, DirectoryLock, FileHandle, Lock, MmapDirectory, WatchCallback, WatchHandle,
+        WritePtr,
+    },
     doc,
-    schema::{self, Facet, Field, Schema},
-    Index, IndexWriter,
+    schema::{
+        self, document::Value as _, Facet, Field, IndexRecordOption, Schema, TextFieldIndexing,
+        TextOptions,
+    },
+    tokenizer::{
+        AsciiFoldingFilter, LowerCaser, RawTokenizer, RemoveLongFilter, SimpleTokenizer,
+        TextAnalyzer,
+    },
+    DocAddress, Index, IndexWriter, TantivyDocument,
+};
+
+use crate::{
+    data::{self, load_airports},
+    error::Error,
+    model::{Airport, AirportKind},
+    phonetic,
 };
 
-use crate::model::{Airport, Runway, RunwayTemplate};
+/// Tokenizer for free text that folds diacritics away, so "Zurich" finds "Zürich".
+const FOLDED: &str = "folded";
 
-static AIRPORTS: &str = include_str!("../resource/airports.csv");
-static RUNWAYS: &str = include_str!("../resource/runways.csv");
+/// Tokenizer that keeps an identifier whole, only lowercasing it, so `US-0571` is one term rather
+/// than `us` and `0571`.
+const IDENTIFIER: &str = "identifier";
 
 pub struct Fields {
     pub identifier: Field,
+    pub iata: Field,
+    pub gps_code: Field,
+    pub local_code: Field,
     pub description: Field,
+    pub name: Field,
+    pub municipality: Field,
+    pub phonetic: Field,
     pub facet: Field,
+    pub kind: Field,
+
+    /// length in feet of the longest open runway, where any length is known
+    pub longest_runway: Field,
+    pub latitude: Field,
+    pub longitude: Field,
+    pub boost: Field,
     pub object: Field,
 }
 
-pub fn initialize(force: bool) -> tantivy::Result<(Index, Fields)> {
-    initialize_with_source(AIRPORTS, RUNWAYS, force)
+pub fn initialize(path: &Path, force: bool) -> crate::Result<(Index, Fields)> {
+    initialize_with_source(data::AIRPORTS, data::RUNWAYS, path, force)
 }
 
+/// Opens the index in `path`, building it first if it's missing, out of date, or `force` is set.
+///
+/// An up to date index is opened without writing anything, so `path` may be a read-only shared
+/// directory as long as someone with write access has built the index there.
 pub fn initialize_with_source(
     airports: &str,
     runways: &str,
+    path: &Path,
     force: bool,
-) -> tantivy::Result<(Index, Fields)> {
-    let dirs = ProjectDirs::from("org", "Hack Commons", "airdatabase").unwrap();
-    let path = dirs.data_dir();
+) -> crate::Result<(Index, Fields)> {
+    if !path.exists() {
+        fs::create_dir_all(path).map_err(|e| match e.kind() {
+            io::ErrorKind::PermissionDenied => Error::ReadOnlyIndex(path.into()),
+            _ => e.into(),
+        })?;
+    }
+
+    let (schema, fields) = schema();
+    let mmap_dir = MmapDirectory::open(path)?;
+
+    // An index written by an older version of the program won't have the fields we expect, so
+    // treat a schema mismatch the same as a forced rebuild.
+    let current = Index::exists(&mmap_dir)? && Index::open(mmap_dir.clone())?.schema() == schema;
+    let writable = is_writable(path);
+
+    if current && !force {
+        let index = if writable {
+            Index::open(mmap_dir)?
+        } else {
+            Index::open(ReadOnlyDirectory(mmap_dir))?
+        };
+        register_tokenizers(&index);
+        return Ok((index, fields));
+    }
 
+    if !writable {
+        return Err(Error::ReadOnlyIndex(path.into()));
+    }
+
+    // Only the index's own files go; snapshots kept alongside them in subdirectories stay.
+    if Index::exists(&mmap_dir)? {
+        for entry in fs::read_dir(path)? {
+            let entry = entry?;
+            if entry.file_type()?.is_file() {
+                fs::remove_file(entry.path())?;
+            }
+        }
+    }
+
+    const MEGABYTE: usize = 0x100000;
+    const ARENA_SIZE: usize = MEGABYTE * 1000;
+
+    let index = Index::create_in_dir(path, schema)?;
+    register_tokenizers(&index);
+    write_index(airports, runways, &fields, &mut index.writer(ARENA_SIZE)?)?;
+    Ok((index, fields))
+}
+
+/// Opens a snapshot kept by `adb update --keep`, read-only and as it is: a snapshot is never
+/// rebuilt, since rebuilding would replace its data with the current data.
+pub fn open_snapshot(path: &Path) -> crate::Result<(Index, Fields)> {
+    let (schema, fields) = schema();
+    let mmap_dir = MmapDirectory::open(path)?;
+    if !Index::exists(&mmap_dir)? || Index::open(mmap_dir.clone())?.schema() != schema {
+        return Err(Error::OutdatedSnapshot(path.into()));
+    }
+
+    let index = Index::open(ReadOnlyDirectory(mmap_dir))?;
+    register_tokenizers(&index);
+    Ok((index, fields))
+}
+
+/// Opens the index in `path` as it is, without building or updating it, or `None` if there's no
+/// index there or it was written by another version of adb. The index can only be written to if
+/// the directory can.
+pub fn open_existing(path: &Path) -> crate::Result<Option<(Index, Fields)>> {
+    if !path.exists() {
+        return Ok(None);
+    }
+
+    let (schema, fields) = schema();
+    let mmap_dir = MmapDirectory::open(path)?;
+    if !Index::exists(&mmap_dir)? || Index::open(mmap_dir.clone())?.schema() != schema {
+        return Ok(None);
+    }
+
+    let index = if is_writable(path) {
+        Index::open(mmap_dir)?
+    } else {
+        Index::open(ReadOnlyDirectory(mmap_dir))?
+    };
+    register_tokenizers(&index);
+    Ok(Some((index, fields)))
+}
+
+/// Reads every airport record stored in the index at `path`, whatever version of adb wrote it, or
+/// `None` if there's no index there or it has no stored records.
+///
+/// Only the stored `object` field is read, looked up by name in the index's own schema, so unlike
+/// [`open_snapshot`] this doesn't care whether the rest of the schema has changed since.
+pub fn stored_records(path: &Path) -> crate::Result<Option<Vec<serde_json::Value>>> {
     if !path.exists() {
-        fs::create_dir_all(path)?;
+        return Ok(None);
+    }
+
+    let mmap_dir = MmapDirectory::open(path)?;
+    if !Index::exists(&mmap_dir)? {
+        return Ok(None);
     }
 
+    let index = Index::open(ReadOnlyDirectory(mmap_dir))?;
+    let Ok(object) = index.schema().get_field("object") else {
+        return Ok(None);
+    };
+
+    let searcher = index.reader()?.searcher();
+    let mut records = Vec::new();
+    for (ord, segment) in searcher.segment_readers().iter().enumerate() {
+        for doc in segment.doc_ids_alive() {
+            let document: TantivyDocument = searcher.doc(DocAddress::new(ord as u32, doc))?;
+            if let Some(record) = document
+                .get_first(object)
+                .and_then(|value| value.as_str())
+                .and_then(|s| serde_json::from_str(s).ok())
+            {
+                records.push(record);
+            }
+        }
+    }
+    Ok(Some(records))
+}
+
+fn schema() -> (Schema, Fields) {
     let mut builder = Schema::builder();
     let fields = Fields {
-        identifier: builder.add_text_field("identifier", schema::TEXT),
-        description: builder.add_text_field("description", schema::TEXT),
+        identifier: builder.add_text_field(
+            "identifier",
+            text_options(IDENTIFIER, IndexRecordOption::Basic),
+        ),
+        iata: builder.add_text_field("iata", text_options(IDENTIFIER, IndexRecordOption::Basic)),
+        gps_code: builder.add_text_field(
+            "gps_code",
+            text_options(IDENTIFIER, IndexRecordOption::Basic),
+        ),
+        local_code: builder.add_text_field(
+            "local_code",
+            text_options(IDENTIFIER, IndexRecordOption::Basic),
+        ),
+        description: builder.add_text_field(
+            "description",
+            text_options(FOLDED, IndexRecordOption::WithFreqsAndPositions),
+        ),
+        name: builder.add_text_field("name", text_options(IDENTIFIER, IndexRecordOption::Basic)),
+        municipality: builder.add_text_field(
+            "municipality",
+            text_options(IDENTIFIER, IndexRecordOption::Basic),
+        ),
+        phonetic: builder.add_text_field(
+            "phonetic",
+            text_options(FOLDED, IndexRecordOption::WithFreqs),
+        ),
         facet: builder.add_facet_field("facet", schema::INDEXED | schema::STORED),
+        kind: builder.add_text_field("kind", text_options(IDENTIFIER, IndexRecordOption::Basic)),
+        longest_runway: builder.add_i64_field("longest_runway", schema::INDEXED | schema::FAST),
+        latitude: builder.add_f64_field("latitude", schema::FAST),
+        longitude: builder.add_f64_field("longitude", schema::FAST),
+        boost: builder.add_f64_field("boost", schema::FAST),
         object: builder.add_text_field("object", schema::STORED),
     };
-    let schema = builder.build();
-    let mmap_dir = MmapDirectory::open(path)?;
+    (builder.build(), fields)
+}
+
+fn text_options(tokenizer: &str, record: IndexRecordOption) -> TextOptions {
+    let indexing = TextFieldIndexing::default()
+        .set_tokenizer(tokenizer)
+        .set_index_option(record);
+    TextOptions::default().set_indexing_options(indexing)
+}
+
+/// Tokenizers are registered with an open index rather than stored in it, so this has to happen
+/// every time the index is opened.
+fn register_tokenizers(index: &Index) {
+    let folded = TextAnalyzer::builder(SimpleTokenizer::default())
+        .filter(RemoveLongFilter::limit(40))
+        .filter(LowerCaser)
+        .filter(AsciiFoldingFilter)
+        .build();
+    index.tokenizers().register(FOLDED, folded);
+
+    let identifier = TextAnalyzer::builder(RawTokenizer::default())
+        .filter(LowerCaser)
+        .build();
+    index.tokenizers().register(IDENTIFIER, identifier);
+}
 
-    if force && Index::exists(&mmap_dir)? {
-        fs::remove_dir_all(path)?;
-        fs::create_dir_all(path)?;
+fn is_writable(path: &Path) -> bool {
+    let probe = path.join(".adb-write-probe");
+    fs::write(&probe, b"").is_ok() && fs::remove_file(probe).is_ok()
+}
+
+/// An index directory we can't write to, such as a shared index owned by root.
+///
+/// Tantivy takes a lock file even to read an index, which fails in a read-only directory. Nothing
+/// can write to the index through this directory, so handing out a dummy lock is safe.
+#[derive(Clone, Debug)]
+struct ReadOnlyDirectory(MmapDirectory);
+
+impl ReadOnlyDirectory {
+    fn denied(path: &Path) -> (Arc<io::Error>, PathBuf) {
+        let error = io::Error::from(io::ErrorKind::PermissionDenied);
+        (Arc::new(error), path.into())
     }
+}
 
-    if !Index::exists(&mmap_dir)? {
-        const MEGABYTE: usize = 0x100000;
-        const ARENA_SIZE: usize = MEGABYTE * 1000;
+impl Directory for ReadOnlyDirectory {
+    fn get_file_handle(&self, path: &Path) -> Result<Arc<dyn FileHandle>, OpenReadError> {
+        self.0.get_file_handle(path)
+    }
 
-        let index = Index::create_in_dir(path, schema)?;
-        write_index(airports, runways, &fields, &mut index.writer(ARENA_SIZE)?)?;
-        Ok((index, fields))
-    } else {
-        Ok((Index::open(mmap_dir)?, fields))
+    fn delete(&self, path: &Path) -> Result<(), DeleteError> {
+        let (io_error, filepath) = Self::denied(path);
+        Err(DeleteError::IoError { io_error, filepath })
+    }
+
+    fn exists(&self, path: &Path) -> Result<bool, OpenReadError> {
+        self.0.exists(path)
+    }
+
+    fn open_write(&self, path: &Path) -> Result<WritePtr, OpenWriteError> {
+        let (io_error, filepath) = Self::denied(path);
+        Err(OpenWriteError::IoError { io_error, filepath })
+    }
+
+    fn atomic_read(&self, path: &Path) -> Result<Vec<u8>, OpenReadError> {
+        self.0.atomic_read(path)
+    }
+
+    fn atomic_write(&self, _path: &Path, _data: &[u8]) -> io::Result<()> {
+        Err(io::ErrorKind::PermissionDenied.into())
+    }
+
+    fn sync_directory(&self) -> io::Result<()> {
+        Ok(())
+    }
+
+    fn acquire_lock(&self, _lock: &Lock) -> Result<DirectoryLock, LockError> {
+        Ok(DirectoryLock::from(Box::new(())))
+    }
+
+    fn watch(&self, watch_callback: WatchCallback) -> tantivy::Result<WatchHandle> {
+        self.0.watch(watch_callback)
     }
 }
 
@@ -71,45 +320,61 @@ fn write_index(
     fields: &Fields,
     writer: &mut IndexWriter,
 ) -> tantivy::Result<()> {
-    let mut source = airports.as_bytes();
-    let mut reader = Reader::from_reader(&mut source);
-    let mut runways = load_runways(runways).unwrap();
-
-    for airport in reader.deserialize() {
-        let mut airport = Airport::from_template(airport.unwrap()).unwrap();
+    for (airport, keywords) in load_airports(airports, runways) {
         let ident = &airport.ident;
         let name = &airport.name;
         let iso_country = &airport.iso_country;
         let iso_region = &airport.iso_region;
-        let municipality = &airport.municipality;
+        let municipality = airport.municipality.as_deref().unwrap_or_default();
 
-        // For my next trick, when available, I'm going to pull runways for each airport.
-        // ...Since I'm doing it this way, ICAO identifiers better be unique.
-        if let Some(runways) = runways.remove(&airport.ident) {
-            airport.runways = runways;
-        }
-
-        writer.add_document(doc!(
+        let mut document = doc!(
             fields.identifier => ident.to_string(),
-            fields.description => format!("{ident} {name}, {municipality}, {iso_region}, {iso_country}"),
+            fields.description => data::description(&airport, &keywords),
+            fields.name => name.to_string(),
+            fields.phonetic => phonetic::encode(&format!("{name} {municipality}")),
             fields.facet => Facet::from(&format!("/{iso_country}/{iso_region}/{municipality}/{ident}/{name}")),
+            fields.kind => airport.kind.as_str(),
+            fields.latitude => airport.coordinates.latitude,
+            fields.longitude => airport.coordinates.longitude,
+            fields.boost => boost(&airport),
             fields.object => serde_json::to_string(&airport).unwrap(),
-        ))?;
+        );
+        let codes = [
+            (fields.iata, &airport.iata_code),
+            (fields.gps_code, &airport.gps_code),
+            (fields.local_code, &airport.local_code),
+            (fields.municipality, &airport.municipality),
+        ];
+        for (field, code) in codes {
+            if let Some(code) = code {
+                document.add_text(field, code);
+            }
+        }
+        let longest = airport
+            .runways
+            .iter()
+            .filter(|runway| !runway.is_closed)
+            .filter_map(|runway| runway.length)
+            .max();
+        if let Some(longest) = longest {
+            document.add_i64(fields.longest_runway, longest.0.into());
+        }
+        writer.add_document(document)?;
     }
 
     writer.commit()?;
     Ok(())
 }
 
-fn load_runways(runways: &str) -> io::Result<HashMap<String, Vec<Runway>>> {
-    let mut source = runways.as_bytes();
-    let mut reader = Reader::from_reader(&mut source);
-    let mut map: HashMap<_, Vec<_>> = HashMap::new();
-
-    for runway in reader.deserialize::<RunwayTemplate>() {
-        let runway: Runway = runway?.into();
-        map.entry(runway.airport.clone()).or_default().push(runway);
-    }
-
-    Ok(map)
+/// How much to scale an airport's relevance in search, so that "london" finds Heathrow and
+/// Gatwick before strips that just happen to mention London.
+fn boost(airport: &Airport) -> f64 {
+    let kind = match airport.kind {
+        AirportKind::LargeAirport => 3.0,
+        AirportKind::MediumAirport => 1.5,
+        AirportKind::Closed => 0.5,
+        _ => 1.0,
+    };
+    let service = if airport.scheduled_service { 1.5 } else { 1.0 };
+    kind * service
 }
@@ -0,0 +1,147 @@
+//! Dated copies of the index, kept by `adb update --keep` and read back with `--as-of`, for
+//! reproducing a calculation done against an earlier dataset.
+//!
+//! Snapshots live in a `snapshots` directory inside the data directory, one directory per day
+//! named like `2023-06-15`.
+
+use std::{
+    fs, io,
+    path::{Path, PathBuf},
+    time::{SystemTime, UNIX_EPOCH},
+};
+
+use crate::error::Error;
+
+pub const SNAPSHOTS: &str = "snapshots";
+
+pub fn dir(data_dir: &Path) -> PathBuf {
+    data_dir.join(SNAPSHOTS)
+}
+
+/// Copies the index in `data_dir` to a snapshot named for today's date (UTC), replacing any kept
+/// earlier the same day. Returns `None` if there's no index yet to keep.
+pub fn keep(data_dir: &Path) -> crate::Result<Option<PathBuf>> {
+    if !data_dir.join("meta.json").exists() {
+        return Ok(None);
+    }
+
+    let (year, month, day) = today();
+    let target = dir(data_dir).join(format!("{year:04}-{month:02}-{day:02}"));
+    if target.exists() {
+        fs::remove_dir_all(&target)?;
+    }
+    fs::create_dir_all(&target)?;
+
+    // The index is a flat directory. Dotfiles are tantivy's lock files and our write probe.
+    for entry in fs::read_dir(data_dir)? {
+        let entry = entry?;
+        let hidden = entry.file_name().to_string_lossy().starts_with('.');
+        if entry.file_type()?.is_file() && !hidden {
+            fs::copy(entry.path(), target.join(entry.file_name()))?;
+        }
+    }
+
+    Ok(Some(target))
+}
+
+/// Dates of the snapshots kept in `data_dir`, oldest first.
+pub fn list(data_dir: &Path) -> io::Result<Vec<String>> {
+    let dir = dir(data_dir);
+    if !dir.exists() {
+        return Ok(Vec::new());
+    }
+
+    let mut dates = Vec::new();
+    for entry in fs::read_dir(dir)? {
+        if let Ok(name) = entry?.file_name().into_string() {
+            if is_date(&name) {
+                dates.push(name);
+            }
+        }
+    }
+    dates.sort();
+    Ok(dates)
+}
+
+/// The latest snapshot from on or before `as_of`, which may be a day (`2023-06-15`), a month
+/// (`2023-06`) or a year (`2023`); a month or year includes every day in it.
+pub fn find(data_dir: &Path, as_of: &str) -> crate::Result<PathBuf> {
+    list(data_dir)?
+        .into_iter()
+        .rfind(|date| date[..as_of.len().min(date.len())] <= *as_of)
+        .map(|date| dir(data_dir).join(date))
+        .ok_or_else(|| Error::NoSnapshot(as_of.into()))
+}
+
+/// True for a day, month or year written like `2023-06-15`, `2023-06` or `2023`.
+pub fn is_date_prefix(s: &str) -> bool {
+    let template = b"0000-00-00";
+    (s.len() == 4 || s.len() == 7 || s.len() == 10)
+        && s.bytes().zip(template).all(|(c, t)| match t {
+            b'0' => c.is_ascii_digit(),
+            _ => c == *t,
+        })
+}
+
+fn is_date(s: &str) -> bool {
+    s.len() == 10 && is_date_prefix(s)
+}
+
+/// Today's date in UTC as year, month and day.
+pub fn today() -> (i64, u32, u32) {
+    let seconds = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map_or(0, |elapsed| elapsed.as_secs());
+    civil_from_days((seconds / 86_400) as i64)
+}
+
+/// The proleptic Gregorian date some number of days after 1970-01-01, after Howard Hinnant's
+/// `civil_from_days`.
+fn civil_from_days(days: i64) -> (i64, u32, u32) {
+    let z = days + 719_468;
+    let era = z.div_euclid(146_097);
+    let doe = z.rem_euclid(146_097);
+    let yoe = (doe - doe / 1460 + doe / 36_524 - doe / 146_096) / 365;
+    let doy = doe - (365 * yoe + yoe / 4 - yoe / 100);
+    let mp = (5 * doy + 2) / 153;
+    let day = (doy - (153 * mp + 2) / 5 + 1) as u32;
+    let month = if mp < 10 { mp + 3 } else { mp - 9 } as u32;
+    let year = yoe + era * 400 + i64::from(month <= 2);
+    (year, month, day)
+}
+
+#[cfg(test)]
+mod tests {
+    use std::fs;
+
+    use super::{civil_from_days, find, is_date_prefix};
+
+    #[test]
+    fn finds_latest_snapshot_as_of_a_date() {
+        let data_dir = std::env::temp_dir().join(format!("adb-snapshots-{}", std::process::id()));
+        for date in ["2023-05-30", "2023-06-15", "2024-01-02"] {
+            fs::create_dir_all(super::dir(&data_dir).join(date)).unwrap();
+        }
+
+        let name = |as_of| {
+            let path = find(&data_dir, as_of).ok()?;
+            Some(path.file_name()?.to_string_lossy().into_owned())
+        };
+
+        assert_eq!(name("2023-06").as_deref(), Some("2023-06-15"));
+        assert_eq!(name("2023-06-01").as_deref(), Some("2023-05-30"));
+        assert_eq!(name("2023").as_deref(), Some("2023-06-15"));
+        assert_eq!(name("2022-12"), None);
+
+        fs::remove_dir_all(&data_dir).unwrap();
+    }
+
+    #[test]
+    fn reads_dates() {
+        assert_eq!(civil_from_days(0), (1970, 1, 1));
+        assert_eq!(civil_from_days(19_523), (2023, 6, 15));
+        assert!(is_date_prefix("2023-06"));
+        assert!(!is_date_prefix("June 2023"));
+        assert!(!is_date_prefix("2023-6"));
+    }
+}
@@ -0,0 +1,251 @@
+//! An R-tree over airport positions, for nearest, radius and bounding box queries that don't have
+//! to visit every airport.
+//!
+//! Positions are points on the unit sphere, so that straight-line distance between them orders
+//! airports the same way as distance over the earth, with no trouble at the poles or the
+//! antimeridian. The tree is saved beside the index and stamped with its segments, since the
+//! airports are found again by their addresses in those segments.
+
+use std::{fs, io, path::Path};
+
+use rstar::{PointDistance, RTree, RTreeObject, AABB};
+use tantivy::DocAddress;
+
+use crate::{geo::BoundingBox, model::Coords, units::Meters};
+
+/// Mean radius of the earth, as used for distances between airports.
+const EARTH_RADIUS_M: f64 = 6_371_000.0;
+
+/// Bytes per saved position: latitude, longitude, segment and document.
+const RECORD: usize = 24;
+
+struct Point {
+    coords: Coords,
+    xyz: [f64; 3],
+    address: DocAddress,
+}
+
+impl RTreeObject for Point {
+    type Envelope = AABB<[f64; 3]>;
+
+    fn envelope(&self) -> Self::Envelope {
+        AABB::from_point(self.xyz)
+    }
+}
+
+impl PointDistance for Point {
+    fn distance_2(&self, point: &[f64; 3]) -> f64 {
+        chord_2(&self.xyz, point)
+    }
+}
+
+pub(crate) struct SpatialIndex {
+    tree: RTree<Point>,
+}
+
+impl SpatialIndex {
+    pub fn new(positions: impl IntoIterator<Item = (Coords, DocAddress)>) -> Self {
+        let points = positions
+            .into_iter()
+            .map(|(coords, address)| Point {
+                coords,
+                xyz: unit_vector(&coords),
+                address,
+            })
+            .collect();
+        Self {
+            tree: RTree::bulk_load(points),
+        }
+    }
+
+    /// Reads a tree saved by [`save`](Self::save), or `None` if there isn't one or it was saved
+    /// for other segments than `stamp` names.
+    pub fn load(path: &Path, stamp: &str) -> Option<Self> {
+        let bytes = fs::read(path).ok()?;
+        let newline = bytes.iter().position(|&b| b == b'\n')?;
+        if &bytes[..newline] != stamp.as_bytes() {
+            return None;
+        }
+
+        let records = &bytes[newline + 1..];
+        if records.len() % RECORD != 0 {
+            return None;
+        }
+
+        let f64_at =
+            |record: &[u8], at: usize| f64::from_le_bytes(record[at..at + 8].try_into().unwrap());
+        let u32_at =
+            |record: &[u8], at: usize| u32::from_le_bytes(record[at..at + 4].try_into().unwrap());
+        let positions = records.chunks_exact(RECORD).map(|record| {
+            let coords = Coords {
+                latitude: f64_at(record, 0),
+                longitude: f64_at(record, 8),
+            };
+            (
+                coords,
+                DocAddress::new(u32_at(record, 16), u32_at(record, 20)),
+            )
+        });
+        Some(Self::new(positions))
+    }
+
+    pub fn save(&self, path: &Path, stamp: &str) -> io::Result<()> {
+        let mut bytes = Vec::with_capacity(stamp.len() + 1 + self.tree.size() * RECORD);
+        bytes.extend_from_slice(stamp.as_bytes());
+        bytes.push(b'\n');
+        for point in self.tree.iter() {
+            bytes.extend_from_slice(&point.coords.latitude.to_le_bytes());
+            bytes.extend_from_slice(&point.coords.longitude.to_le_bytes());
+            bytes.extend_from_slice(&point.address.segment_ord.to_le_bytes());
+            bytes.extend_from_slice(&point.address.doc_id.to_le_bytes());
+        }
+        fs::write(path, bytes)
+    }
+
+    /// Addresses of the airports closest to a point, nearest first.
+    pub fn nearest(&self, coords: &Coords) -> impl Iterator<Item = DocAddress> + '_ {
+        self.tree
+            .nearest_neighbor_iter(unit_vector(coords))
+            .map(|point| point.address)
+    }
+
+    /// Addresses of the airports within `radius` of a point, nearest first.
+    pub fn within(&self, coords: &Coords, radius: Meters) -> Vec<DocAddress> {
+        // A little extra, so rounding can't drop an airport right at the edge; the exact check
+        // follows.
+        let angle = (radius.0 / EARTH_RADIUS_M).min(std::f64::consts::PI) * 1.001;
+        let chord = 2.0 * (angle / 2.0).sin();
+
+        let mut found: Vec<_> = self
+            .tree
+            .locate_within_distance(unit_vector(coords), chord * chord)
+            .map(|point| (coords.distance_to(&point.coords).meters(), point.address))
+            .filter(|&(distance, _)| distance <= radius.0)
+            .collect();
+        found.sort_by(|a, b| a.0.total_cmp(&b.0));
+        found.into_iter().map(|(_, address)| address).collect()
+    }
+
+    /// Addresses of the airports inside a bounding box, in no particular order.
+    pub fn in_bounds(&self, bounds: &BoundingBox) -> Vec<DocAddress> {
+        self.tree
+            .locate_in_envelope(envelope(bounds))
+            .filter(|point| bounds.contains(&point.coords))
+            .map(|point| point.address)
+            .collect()
+    }
+}
+
+fn unit_vector(coords: &Coords) -> [f64; 3] {
+    let (lat, lon) = (coords.latitude.to_radians(), coords.longitude.to_radians());
+    [lat.cos() * lon.cos(), lat.cos() * lon.sin(), lat.sin()]
+}
+
+fn chord_2(a: &[f64; 3], b: &[f64; 3]) -> f64 {
+    a.iter().zip(b).map(|(a, b)| (a - b) * (a - b)).sum()
+}
+
+/// A box in space holding every point of a latitude/longitude box on the sphere.
+///
+/// Each coordinate is a product of one function of latitude and one of longitude, and the cosine
+/// of a latitude is never negative, so the product's extremes are among the products of each
+/// factor's extremes over its range.
+fn envelope(bounds: &BoundingBox) -> AABB<[f64; 3]> {
+    let (south, north) = (bounds.south.to_radians(), bounds.north.to_radians());
+    let (west, mut east) = (bounds.west.to_radians(), bounds.east.to_radians());
+    if east < west {
+        east += std::f64::consts::TAU;
+    }
+
+    let cos_lat = range(f64::cos, south, north);
+    let cos_lon = range(f64::cos, west, east);
+    let sin_lon = range(f64::sin, west, east);
+    let product = |(a_min, a_max): (f64, f64), (b_min, b_max): (f64, f64)| {
+        let products = [a_min * b_min, a_min * b_max, a_max * b_min, a_max * b_max];
+        (
+            products.into_iter().fold(f64::INFINITY, f64::min),
+            products.into_iter().fold(f64::NEG_INFINITY, f64::max),
+        )
+    };
+
+    let x = product(cos_lat, cos_lon);
+    let y = product(cos_lat, sin_lon);
+    let z = (south.sin(), north.sin());
+
+    // Widened a hair, so that rounding can't leave out a point on the edge.
+    const MARGIN: f64 = 1e-9;
+    AABB::from_corners(
+        [x.0 - MARGIN, y.0 - MARGIN, z.0 - MARGIN],
+        [x.1 + MARGIN, y.1 + MARGIN, z.1 + MARGIN],
+    )
+}
+
+/// The least and greatest values of `sin` or `cos` between two angles, `low <= high`: the values
+/// at either end, or ±1 where a peak falls in between.
+fn range(f: fn(f64) -> f64, low: f64, high: f64) -> (f64, f64) {
+    use std::f64::consts::FRAC_PI_2;
+
+    let mut min = f(low).min(f(high));
+    let mut max = f(low).max(f(high));
+
+    // Peaks of both fall on multiples of half pi; check each between the ends.
+    let mut peak = (low / FRAC_PI_2).ceil() * FRAC_PI_2;
+    while peak <= high {
+        let value = f(peak);
+        min = min.min(value);
+        max = max.max(value);
+        peak += FRAC_PI_2;
+    }
+    (min, max)
+}
+
+#[cfg(test)]
+mod tests {
+    use tantivy::DocAddress;
+
+    use super::SpatialIndex;
+    use crate::{geo::BoundingBox, model::Coords, units::Meters};
+
+    fn coords(latitude: f64, longitude: f64) -> Coords {
+        Coords {
+            latitude,
+            longitude,
+        }
+    }
+
+    #[test]
+    fn answers_across_the_antimeridian() {
+        let positions = [
+            coords(-17.75, 177.44),
+            coords(-13.83, -171.99),
+            coords(51.47, -0.45),
+            coords(-18.04, 178.56),
+        ];
+        let index = SpatialIndex::new(
+            positions
+                .iter()
+                .enumerate()
+                .map(|(doc, &coords)| (coords, DocAddress::new(0, doc as u32))),
+        );
+        let docs = |addresses: Vec<DocAddress>| -> Vec<u32> {
+            addresses.iter().map(|address| address.doc_id).collect()
+        };
+
+        let samoa = coords(-13.8, -172.0);
+        let nearest: Vec<_> = index.nearest(&samoa).take(2).collect();
+        assert_eq!(docs(nearest), [1, 3]);
+
+        let fiji = coords(-18.0, 178.0);
+        assert_eq!(docs(index.within(&fiji, Meters(100_000.0))), [3, 0]);
+
+        let bounds = BoundingBox {
+            south: -20.0,
+            west: 170.0,
+            north: -10.0,
+            east: -170.0,
+        };
+        let mut found = docs(index.in_bounds(&bounds));
+        found.sort();
+        assert_eq!(found, [0, 1, 3]);
+    }
+}
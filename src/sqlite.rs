@@ -0,0 +1,242 @@
+//! Airports in a single SQLite file, for `--backend sqlite`: one file that can be opened with
+//! `sqlite3`, copied and backed up, searched with FTS5.
+//!
+//! The file is built from the data compiled into adb the first time it's opened, again whenever
+//! it was written by another version of adb, whose data or tables may differ, and on `adb update`.
+
+use std::{
+    fs,
+    path::{Path, PathBuf},
+};
+
+use rusqlite::{params, Connection, OptionalExtension};
+
+use crate::{
+    data,
+    lookup::{self, Code, Lookup},
+    model::{Airport, Coords, Country},
+};
+
+/// Stored as the file's `user_version`; bump it whenever the tables change.
+const SCHEMA_VERSION: i32 = 3;
+
+/// The version of adb, and so of the data compiled into it, that the file was built from.
+const DATA_VERSION: &str = env!("CARGO_PKG_VERSION");
+
+/// Where the database lives in a data directory. It has a directory of its own because rebuilding
+/// the tantivy index clears out the files beside it.
+pub fn path(data_dir: &Path) -> PathBuf {
+    data_dir.join("sqlite").join("airports.db")
+}
+
+pub struct SqliteDatabase {
+    connection: Connection,
+
+    /// the country whose airports win codes shared across countries
+    prefer_country: Option<Country>,
+}
+
+impl SqliteDatabase {
+    pub fn open(path: &Path) -> crate::Result<Self> {
+        Self::open_with(path, false)
+    }
+
+    /// Opens the file after building it again from the data compiled into adb.
+    pub fn rebuild(path: &Path) -> crate::Result<Self> {
+        Self::open_with(path, true)
+    }
+
+    fn open_with(path: &Path, force: bool) -> crate::Result<Self> {
+        if let Some(parent) = path.parent() {
+            fs::create_dir_all(parent)?;
+        }
+
+        let mut connection = Connection::open(path)?;
+        if force || !is_current(&connection)? {
+            build(&mut connection)?;
+        }
+        Ok(Self {
+            connection,
+            prefer_country: None,
+        })
+    }
+
+    /// Sets the country whose airports win when a code names airports in more than one.
+    pub fn set_prefer_country(&mut self, country: Option<Country>) {
+        self.prefer_country = country;
+    }
+
+    fn by_id(&self, id: i64) -> crate::Result<Option<Airport>> {
+        let object: Option<String> = self
+            .connection
+            .query_row("SELECT object FROM airports WHERE id = ?1", [id], |row| {
+                row.get(0)
+            })
+            .optional()?;
+        Ok(object
+            .map(|object| serde_json::from_str(&object))
+            .transpose()?)
+    }
+}
+
+impl Lookup for SqliteDatabase {
+    /// Finds an airport by identifier, or failing that by one of its other codes, as every
+    /// backend does; see [`lookup::resolve`].
+    fn by_identifier(&self, identifier: &str) -> crate::Result<Option<Airport>> {
+        lookup::resolve(identifier, self.prefer_country, |kind| {
+            let column = match kind {
+                Code::Identifier => "ident",
+                Code::Gps => "gps_code",
+                Code::Local => "local_code",
+                Code::Iata => "iata_code",
+            };
+            let mut statement = self
+                .connection
+                .prepare_cached(&format!("SELECT object FROM airports WHERE {column} = ?1"))?;
+            let objects = statement.query_map([identifier], |row| row.get::<_, String>(0))?;
+
+            let mut airports = Vec::new();
+            for object in objects {
+                airports.push(serde_json::from_str(&object?)?);
+            }
+            Ok(airports)
+        })
+    }
+
+    fn search(&self, query: &str, limit: usize) -> crate::Result<Vec<Airport>> {
+        let mut statement = self.connection.prepare(
+            "SELECT airports.object FROM airports_fts
+             JOIN airports ON airports.id = airports_fts.rowid
+             WHERE airports_fts MATCH ?1
+             ORDER BY airports_fts.rank
+             LIMIT ?2",
+        )?;
+        let objects = statement
+            .query_map(params![match_expression(query), limit as i64], |row| {
+                row.get::<_, String>(0)
+            })?;
+
+        let mut airports = Vec::new();
+        for object in objects {
+            airports.push(serde_json::from_str(&object?)?);
+        }
+        Ok(airports)
+    }
+
+    fn nearest(&self, coords: &Coords, limit: usize) -> crate::Result<Vec<Airport>> {
+        let mut statement = self
+            .connection
+            .prepare("SELECT id, latitude, longitude FROM airports")?;
+        let rows = statement.query_map([], |row| {
+            let position = Coords {
+                latitude: row.get(1)?,
+                longitude: row.get(2)?,
+            };
+            Ok((row.get::<_, i64>(0)?, position))
+        })?;
+
+        let mut candidates = Vec::new();
+        for row in rows {
+            let (id, position) = row?;
+            candidates.push((coords.distance_to(&position).meters(), id));
+        }
+        candidates.sort_by(|a, b| a.0.total_cmp(&b.0));
+
+        let mut airports = Vec::with_capacity(limit);
+        for (_, id) in candidates.into_iter().take(limit) {
+            airports.extend(self.by_id(id)?);
+        }
+        Ok(airports)
+    }
+}
+
+/// True if the file was built with these tables from this version's data.
+fn is_current(connection: &Connection) -> rusqlite::Result<bool> {
+    let version: i32 = connection.pragma_query_value(None, "user_version", |row| row.get(0))?;
+    if version != SCHEMA_VERSION {
+        return Ok(false);
+    }
+    let data: Option<String> = connection
+        .query_row("SELECT data_version FROM meta", [], |row| row.get(0))
+        .optional()?;
+    Ok(data.as_deref() == Some(DATA_VERSION))
+}
+
+fn build(connection: &mut Connection) -> rusqlite::Result<()> {
+    let transaction = connection.transaction()?;
+    transaction.execute_batch(
+        "DROP TABLE IF EXISTS airports_fts;
+         DROP TABLE IF EXISTS airports;
+         DROP TABLE IF EXISTS meta;
+         CREATE TABLE meta (data_version TEXT NOT NULL);
+         CREATE TABLE airports (
+             id INTEGER PRIMARY KEY,
+             ident TEXT NOT NULL UNIQUE COLLATE NOCASE,
+             gps_code TEXT COLLATE NOCASE,
+             local_code TEXT COLLATE NOCASE,
+             iata_code TEXT COLLATE NOCASE,
+             latitude REAL NOT NULL,
+             longitude REAL NOT NULL,
+             object TEXT NOT NULL
+         );
+         CREATE INDEX airports_gps_code ON airports (gps_code);
+         CREATE INDEX airports_local_code ON airports (local_code);
+         CREATE INDEX airports_iata_code ON airports (iata_code);
+         CREATE VIRTUAL TABLE airports_fts USING fts5(
+             description,
+             tokenize = 'unicode61 remove_diacritics 2'
+         );",
+    )?;
+
+    {
+        let mut insert_airport = transaction.prepare(
+            "INSERT INTO airports (ident, gps_code, local_code, iata_code, latitude, longitude, object)
+             VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7)",
+        )?;
+        let mut insert_description =
+            transaction.prepare("INSERT INTO airports_fts (rowid, description) VALUES (?1, ?2)")?;
+
+        for (airport, keywords) in data::embedded_airports() {
+            let id = insert_airport.insert(params![
+                airport.ident,
+                airport.gps_code,
+                airport.local_code,
+                airport.iata_code,
+                airport.coordinates.latitude,
+                airport.coordinates.longitude,
+                serde_json::to_string(&airport).unwrap(),
+            ])?;
+            insert_description.execute(params![id, data::description(&airport, &keywords)])?;
+        }
+    }
+
+    transaction.execute(
+        "INSERT INTO meta (data_version) VALUES (?1)",
+        [DATA_VERSION],
+    )?;
+    transaction.pragma_update(None, "user_version", SCHEMA_VERSION)?;
+    transaction.commit()
+}
+
+/// Quotes each word of a query as an FTS5 string, so that punctuation like the hyphen in `US-WA`
+/// isn't read as query syntax, and matches any of them, as the tantivy index does.
+fn match_expression(query: &str) -> String {
+    query
+        .split_whitespace()
+        .map(|word| format!("\"{}\"", word.replace('"', "\"\"")))
+        .collect::<Vec<_>>()
+        .join(" OR ")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::match_expression;
+
+    #[test]
+    fn quotes_each_word() {
+        assert_eq!(
+            match_expression("US-WA  \"tower"),
+            r#""US-WA" OR """tower""#
+        );
+    }
+}
@@ -0,0 +1,219 @@
+//! Recorded GPS tracks, read from GPX or KML files.
+//!
+//! Neither format gets a real XML parser; we only need track points, and pulling those out with a
+//! couple of regular expressions copes with everything ForeFlight, Garmin and friends export.
+
+use std::{fs, path::Path};
+
+use regex::Regex;
+
+use crate::{error::Error, model::Coords, units::Meters};
+
+/// Below this ground speed the aircraft is taken to be on the ground.
+const GROUND_SPEED_KT: f64 = 40.0;
+
+/// Time on the ground before a slow stretch counts as a stop rather than a go-around.
+const MIN_STOP_SECONDS: i64 = 60;
+
+#[derive(Clone, Copy, Debug)]
+pub struct TrackPoint {
+    pub coords: Coords,
+
+    /// seconds since the Unix epoch, if the file records times
+    pub time: Option<i64>,
+}
+
+/// Reads the track segments in a GPX or KML file.
+pub fn read_track(path: &Path) -> crate::Result<Vec<Vec<TrackPoint>>> {
+    let text = fs::read_to_string(path)?;
+    let segments = if text.contains("<kml") {
+        parse_kml(&text)
+    } else {
+        parse_gpx(&text)
+    };
+
+    let segments: Vec<_> = segments.into_iter().filter(|s| !s.is_empty()).collect();
+    if segments.is_empty() {
+        return Err(Error::NoTrackPoints(path.into()));
+    }
+    Ok(segments)
+}
+
+fn parse_gpx(text: &str) -> Vec<Vec<TrackPoint>> {
+    let point = Regex::new(r"(?s)<trkpt\b([^>]*?)(?:/>|>(.*?)</trkpt>)").unwrap();
+    let lat = Regex::new(r#"\blat\s*=\s*["']([^"']+)["']"#).unwrap();
+    let lon = Regex::new(r#"\blon\s*=\s*["']([^"']+)["']"#).unwrap();
+    let time = Regex::new(r"<time>\s*([^<]+?)\s*</time>").unwrap();
+
+    text.split("<trkseg")
+        .skip(1)
+        .map(|segment| {
+            point
+                .captures_iter(segment)
+                .filter_map(|captures| {
+                    let attributes = &captures[1];
+                    let latitude = lat.captures(attributes)?[1].parse().ok()?;
+                    let longitude = lon.captures(attributes)?[1].parse().ok()?;
+                    let time = captures
+                        .get(2)
+                        .and_then(|body| time.captures(body.as_str()))
+                        .and_then(|t| parse_timestamp(&t[1]));
+
+                    Some(TrackPoint {
+                        coords: Coords {
+                            latitude,
+                            longitude,
+                        },
+                        time,
+                    })
+                })
+                .collect()
+        })
+        .collect()
+}
+
+fn parse_kml(text: &str) -> Vec<Vec<TrackPoint>> {
+    let line = Regex::new(r"(?s)<coordinates>(.*?)</coordinates>").unwrap();
+    let track = Regex::new(r"(?s)<gx:Track>(.*?)</gx:Track>").unwrap();
+    let when = Regex::new(r"<when>\s*([^<]+?)\s*</when>").unwrap();
+    let coord = Regex::new(r"<gx:coord>\s*([^<]+?)\s*</gx:coord>").unwrap();
+
+    // KML writes longitude first, separated by commas in a LineString and by spaces in gx:Track.
+    let parse_coords = |text: &str, separator: char| {
+        let mut values = text.split(separator).map(str::trim);
+        let longitude = values.next()?.parse().ok()?;
+        let latitude = values.next()?.parse().ok()?;
+        Some(Coords {
+            latitude,
+            longitude,
+        })
+    };
+
+    let tracks = track.captures_iter(text).map(|captures| {
+        let body = &captures[1];
+        let times = when.captures_iter(body).map(|t| parse_timestamp(&t[1]));
+        coord
+            .captures_iter(body)
+            .zip(times)
+            .filter_map(|(c, time)| {
+                let coords = parse_coords(&c[1], ' ')?;
+                Some(TrackPoint { coords, time })
+            })
+            .collect()
+    });
+
+    let lines = line.captures_iter(text).map(|captures| {
+        captures[1]
+            .split_whitespace()
+            .filter_map(|tuple| parse_coords(tuple, ','))
+            .map(|coords| TrackPoint { coords, time: None })
+            .collect()
+    });
+
+    tracks.chain(lines).collect()
+}
+
+/// Parses an ISO 8601 UTC timestamp like `2024-05-01T17:03:22Z`, ignoring fractional seconds.
+fn parse_timestamp(text: &str) -> Option<i64> {
+    let (date, time) = text.split_once('T')?;
+    let mut date = date.splitn(3, '-').map(|part| part.parse::<i64>().ok());
+    let (year, month, day) = (date.next()??, date.next()??, date.next()??);
+
+    let time = time.trim_end_matches('Z');
+    let mut time = time.splitn(3, ':');
+    let hour: i64 = time.next()?.parse().ok()?;
+    let minute: i64 = time.next()?.parse().ok()?;
+    let second: f64 = time.next()?.parse().ok()?;
+
+    // Days since the epoch from a civil date, per Howard Hinnant's `days_from_civil`.
+    let year = if month <= 2 { year - 1 } else { year };
+    let era = year.div_euclid(400);
+    let year_of_era = year - era * 400;
+    let day_of_year = (153 * ((month + 9) % 12) + 2) / 5 + day - 1;
+    let day_of_era = year_of_era * 365 + year_of_era / 4 - year_of_era / 100 + day_of_year;
+    let days = era * 146_097 + day_of_era - 719_468;
+
+    Some(days * 86_400 + hour * 3600 + minute * 60 + second as i64)
+}
+
+/// Indices into a track segment where the aircraft was on the ground: the first and last points,
+/// plus the middle of every long enough slow stretch in between.
+///
+/// Tracks without times only ever yield their ends.
+pub fn stops(points: &[TrackPoint]) -> Vec<usize> {
+    let mut stops = vec![0];
+    let mut slow_since: Option<usize> = None;
+
+    for (idx, pair) in points.windows(2).enumerate() {
+        let (a, b) = (pair[0], pair[1]);
+        let slow = match (a.time, b.time) {
+            (Some(t1), Some(t2)) if t2 > t1 => {
+                let nm = Meters(a.coords.distance_to(&b.coords).meters()).to_nautical_miles();
+                let knots = nm.0 / ((t2 - t1) as f64 / 3600.0);
+                knots < GROUND_SPEED_KT
+            }
+            _ => false,
+        };
+
+        match (slow, slow_since) {
+            (true, None) => slow_since = Some(idx),
+            (false, Some(start)) => {
+                slow_since = None;
+                let on_ground = points[idx].time.zip(points[start].time);
+                let touches_end = start == 0;
+                if !touches_end && on_ground.is_some_and(|(t2, t1)| t2 - t1 >= MIN_STOP_SECONDS) {
+                    stops.push((start + idx) / 2);
+                }
+            }
+            _ => {}
+        }
+    }
+
+    let last = points.len() - 1;
+    if last > 0 {
+        stops.push(last);
+    }
+    stops
+}
+
+/// Distance flown along a stretch of track, in meters.
+pub fn flown_distance(points: &[TrackPoint]) -> f64 {
+    points
+        .windows(2)
+        .map(|pair| pair[0].coords.distance_to(&pair[1].coords).meters())
+        .sum()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{parse_gpx, parse_timestamp, stops};
+
+    #[test]
+    fn can_read_gpx_and_find_stops() {
+        // Taxi, fly, land and sit for two minutes, fly, land.
+        let gpx = r#"<gpx><trk><trkseg>
+            <trkpt lat="47.000" lon="-122.000"><time>2024-05-01T17:00:00Z</time></trkpt>
+            <trkpt lat="47.001" lon="-122.000"><time>2024-05-01T17:01:00Z</time></trkpt>
+            <trkpt lon="-122.000" lat="47.500"><time>2024-05-01T17:16:00Z</time></trkpt>
+            <trkpt lat="47.501" lon="-122.000"><time>2024-05-01T17:17:00Z</time></trkpt>
+            <trkpt lat="47.502" lon="-122.000"><time>2024-05-01T17:19:00Z</time></trkpt>
+            <trkpt lat="48.000" lon="-122.000"><time>2024-05-01T17:34:00Z</time></trkpt>
+            <trkpt lat="48.001" lon="-122.000"/>
+        </trkseg></trk></gpx>"#;
+
+        let segments = parse_gpx(gpx);
+        assert_eq!(segments.len(), 1);
+        assert_eq!(segments[0].len(), 7);
+        assert_eq!(segments[0][2].coords.latitude, 47.5);
+        assert_eq!(stops(&segments[0]), [0, 3, 6]);
+    }
+
+    #[test]
+    fn can_parse_timestamps() {
+        assert_eq!(parse_timestamp("1970-01-01T00:00:00Z"), Some(0));
+        assert_eq!(
+            parse_timestamp("2024-05-01T17:03:22.5Z"),
+            Some(1_714_583_002)
+        );
+    }
+}
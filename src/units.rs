@@ -0,0 +1,189 @@
+//! Distances and heights with their units attached, so that feet are never added to meters.
+//!
+//! Each is a plain newtype; `.0` gets the number back out for formatting or arithmetic the types
+//! don't cover.
+
+use std::{
+    fmt,
+    iter::Sum,
+    ops::{Add, Sub},
+    str::FromStr,
+};
+
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    locale::{format_number, tr, Message},
+    METERS_PER_NAUTICAL_MILE,
+};
+
+pub const METERS_PER_FOOT: f64 = 0.3048;
+pub const METERS_PER_STATUTE_MILE: f64 = 1609.344;
+
+/// Whole feet, as elevations and runway lengths are published.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord, Hash, Deserialize, Serialize)]
+#[serde(transparent)]
+pub struct Feet(pub i32);
+
+#[derive(Clone, Copy, Debug, Default, PartialEq, PartialOrd, Deserialize, Serialize)]
+#[serde(transparent)]
+pub struct Meters(pub f64);
+
+#[derive(Clone, Copy, Debug, Default, PartialEq, PartialOrd, Deserialize, Serialize)]
+#[serde(transparent)]
+pub struct NauticalMiles(pub f64);
+
+impl From<Feet> for Meters {
+    fn from(feet: Feet) -> Self {
+        Meters(f64::from(feet.0) * METERS_PER_FOOT)
+    }
+}
+
+impl From<NauticalMiles> for Meters {
+    fn from(nm: NauticalMiles) -> Self {
+        Meters(nm.0 * METERS_PER_NAUTICAL_MILE)
+    }
+}
+
+impl From<Meters> for NauticalMiles {
+    fn from(meters: Meters) -> Self {
+        NauticalMiles(meters.0 / METERS_PER_NAUTICAL_MILE)
+    }
+}
+
+impl Meters {
+    pub fn to_nautical_miles(self) -> NauticalMiles {
+        self.into()
+    }
+
+    /// Rounded to the nearest foot.
+    pub fn to_feet(self) -> Feet {
+        Feet((self.0 / METERS_PER_FOOT).round() as i32)
+    }
+}
+
+impl Meters {
+    /// This distance in whatever unit the user wants to see, e.g. `1,234.5 km`.
+    pub fn display(self, unit: DistanceUnit) -> String {
+        format!(
+            "{} {}",
+            format_number(unit.from_meters(self), 1),
+            unit.symbol()
+        )
+    }
+}
+
+/// The unit distances are shown in, chosen with `--units` or the `units` setting.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Deserialize)]
+pub enum DistanceUnit {
+    #[default]
+    #[serde(rename = "nm")]
+    NauticalMiles,
+    #[serde(rename = "km")]
+    Kilometers,
+    #[serde(rename = "mi")]
+    StatuteMiles,
+}
+
+impl DistanceUnit {
+    pub fn symbol(self) -> &'static str {
+        match self {
+            DistanceUnit::NauticalMiles => "nm",
+            DistanceUnit::Kilometers => "km",
+            DistanceUnit::StatuteMiles => "mi",
+        }
+    }
+
+    pub fn from_meters(self, meters: Meters) -> f64 {
+        match self {
+            DistanceUnit::NauticalMiles => meters.to_nautical_miles().0,
+            DistanceUnit::Kilometers => meters.0 / 1000.0,
+            DistanceUnit::StatuteMiles => meters.0 / METERS_PER_STATUTE_MILE,
+        }
+    }
+}
+
+impl FromStr for DistanceUnit {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.to_ascii_lowercase().as_str() {
+            "nm" => Ok(DistanceUnit::NauticalMiles),
+            "km" => Ok(DistanceUnit::Kilometers),
+            "mi" => Ok(DistanceUnit::StatuteMiles),
+            _ => Err(format!("{}: {s}", tr(Message::BadUnit))),
+        }
+    }
+}
+
+impl NauticalMiles {
+    pub fn to_meters(self) -> Meters {
+        self.into()
+    }
+}
+
+impl Feet {
+    pub fn to_meters(self) -> Meters {
+        self.into()
+    }
+}
+
+macro_rules! arithmetic {
+    ($unit:ident) => {
+        impl Add for $unit {
+            type Output = $unit;
+
+            fn add(self, other: $unit) -> $unit {
+                $unit(self.0 + other.0)
+            }
+        }
+
+        impl Sub for $unit {
+            type Output = $unit;
+
+            fn sub(self, other: $unit) -> $unit {
+                $unit(self.0 - other.0)
+            }
+        }
+
+        impl Sum for $unit {
+            fn sum<I: Iterator<Item = $unit>>(iter: I) -> $unit {
+                iter.fold($unit::default(), Add::add)
+            }
+        }
+    };
+}
+
+arithmetic!(Meters);
+arithmetic!(NauticalMiles);
+
+/// To a tenth of a mile, e.g. `1,234.5 nm`.
+impl fmt::Display for NauticalMiles {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{} nm", format_number(self.0, 1))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{DistanceUnit, Feet, Meters, NauticalMiles};
+    use crate::locale::Locale;
+
+    #[test]
+    fn can_convert_units() {
+        assert_eq!(Feet(1000).to_meters(), Meters(304.8));
+        assert_eq!(Meters(304.8).to_feet(), Feet(1000));
+        assert!((NauticalMiles(10.0).to_meters().to_nautical_miles().0 - 10.0).abs() < 1e-12);
+
+        let total: Meters = [Meters(1.5), Meters(2.5)].into_iter().sum();
+        assert_eq!(total, Meters(4.0));
+
+        Locale::set_for_thread(Locale::English);
+        let unit: DistanceUnit = "KM".parse().unwrap();
+        assert_eq!(Meters(1_609_344.0).display(unit), "1,609.3 km");
+        assert_eq!(
+            Meters(1_609_344.0).display(DistanceUnit::StatuteMiles),
+            "1,000.0 mi"
+        );
+    }
+}
@@ -1,14 +1,22 @@
-use std::fmt;
+use std::{fmt, fs::File, io::BufReader, path::Path};
 
 use geoutils::Distance;
+use hashbrown::HashMap;
+use serde::Deserialize;
 
-use crate::model::{Airport, Coords};
+use crate::{
+    error::Error,
+    lookup::Lookup,
+    model::{Airport, Coords},
+};
 
 // Practically all instances of Waypoint will be the Airport variant.
 #[allow(clippy::large_enum_variant)]
+#[derive(Clone, Debug)]
 pub enum Waypoint {
     Airport(Airport),
     Coords(Coords),
+    Named(String, Coords),
 }
 
 impl From<Airport> for Waypoint {
@@ -24,24 +32,28 @@ impl From<Coords> for Waypoint {
 }
 
 impl Waypoint {
-    pub fn name(&self) -> WaypointName {
+    /// Resolves an identifier without any user waypoints; see [`WaypointResolver::resolve`].
+    pub fn resolve(db: &dyn Lookup, identifier: &str) -> crate::Result<Waypoint> {
+        WaypointResolver::new(db).resolve(identifier)
+    }
+
+    pub fn name(&self) -> WaypointName<'_> {
         WaypointName { waypoint: self }
     }
 
     pub fn distance_to(&self, other: &Waypoint) -> Distance {
-        let left = self.coordinates().location();
-        let right = other.coordinates().location();
+        self.coordinates().distance_to(&other.coordinates())
+    }
 
-        // I have never, ever, ever seen Vicenty's formula fail to yield a result, but IF IT DOES
-        // we'll fall back to haversine distance.
-        left.distance_to(&right)
-            .unwrap_or_else(|_| left.haversine_distance_to(&right))
+    /// True if both waypoints resolved to exactly the same position, e.g. the same airport twice.
+    pub fn is_same_point(&self, other: &Waypoint) -> bool {
+        self.coordinates() == other.coordinates()
     }
 
-    fn coordinates(&self) -> Coords {
+    pub fn coordinates(&self) -> Coords {
         match self {
             Waypoint::Airport(airport) => airport.coordinates,
-            Waypoint::Coords(coordinates) => *coordinates,
+            Waypoint::Coords(coordinates) | Waypoint::Named(_, coordinates) => *coordinates,
         }
     }
 }
@@ -50,11 +62,201 @@ pub struct WaypointName<'a> {
     waypoint: &'a Waypoint,
 }
 
+/// Turns identifiers as the user writes them into waypoints, so that every command resolves them
+/// the same way.
+pub struct WaypointResolver<'a> {
+    db: &'a dyn Lookup,
+
+    /// keyed by uppercased name
+    user: HashMap<String, (String, Coords)>,
+}
+
+impl<'a> WaypointResolver<'a> {
+    pub fn new(db: &'a dyn Lookup) -> Self {
+        WaypointResolver {
+            db,
+            user: HashMap::new(),
+        }
+    }
+
+    /// Adds named positions of the user's own, such as a home strip missing from the data. These
+    /// take precedence over airports with the same identifier.
+    pub fn with_user_waypoints(
+        mut self,
+        waypoints: impl IntoIterator<Item = (String, Coords)>,
+    ) -> Self {
+        for (name, coords) in waypoints {
+            self.user.insert(name.to_uppercase(), (name, coords));
+        }
+        self
+    }
+
+    /// Resolves, in order of preference:
+    ///
+    /// 1. `NAME=lat lon`, coordinates given a name for display
+    /// 2. a user waypoint, ignoring case
+    /// 3. an airport identifier
+    /// 4. `lat lon` coordinates
+    pub fn resolve(&self, identifier: &str) -> crate::Result<Waypoint> {
+        if let Some(named) = self.named(identifier) {
+            return named;
+        }
+
+        if let Some(airport) = self.db.by_identifier(identifier)? {
+            return Ok(airport.into());
+        }
+
+        Self::parse(identifier).map(Waypoint::from)
+    }
+
+    /// Where an identifier is, resolved in the same order as [`resolve`](Self::resolve) but asking
+    /// the source only for an airport's position; see [`Lookup::coordinates`].
+    pub fn coordinates(&self, identifier: &str) -> crate::Result<Coords> {
+        if let Some(named) = self.named(identifier) {
+            return named.map(|waypoint| waypoint.coordinates());
+        }
+
+        if let Some(coords) = self.db.coordinates(identifier)? {
+            return Ok(coords);
+        }
+
+        Self::parse(identifier)
+    }
+
+    /// The first two steps of resolving: a name given coordinates, or a user waypoint.
+    fn named(&self, identifier: &str) -> Option<crate::Result<Waypoint>> {
+        if let Some((name, coords)) = identifier.split_once('=') {
+            let name = name.trim();
+            return Some(match coords.parse() {
+                Ok(coords) if !name.is_empty() => Ok(Waypoint::Named(name.into(), coords)),
+                _ => Err(Error::from_identifier(identifier)),
+            });
+        }
+
+        let (name, coords) = self.user.get(&identifier.trim().to_uppercase())?;
+        Some(Ok(Waypoint::Named(name.clone(), *coords)))
+    }
+
+    fn parse(identifier: &str) -> crate::Result<Coords> {
+        identifier
+            .parse()
+            .map_err(|_| Error::from_identifier(identifier))
+    }
+}
+
 impl fmt::Display for WaypointName<'_> {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
         match self.waypoint {
             Waypoint::Airport(airport) => airport.ident.fmt(f),
             Waypoint::Coords(coords) => coords.fmt(f),
+            Waypoint::Named(name, _) => name.fmt(f),
         }
     }
 }
+
+/// A single entry in a JSON route file: either an identifier to resolve or explicit coordinates.
+#[derive(Debug, Deserialize)]
+#[serde(untagged)]
+enum RouteEntry {
+    Identifier {
+        ident: String,
+    },
+    Coords {
+        lat: f64,
+        lon: f64,
+        name: Option<String>,
+    },
+}
+
+pub fn read_json_route(path: &Path, resolver: &WaypointResolver) -> crate::Result<Vec<Waypoint>> {
+    let entries: Vec<RouteEntry> = serde_json::from_reader(BufReader::new(File::open(path)?))?;
+    entries
+        .into_iter()
+        .map(|entry| match entry {
+            RouteEntry::Identifier { ident } => resolver.resolve(&ident),
+            RouteEntry::Coords { lat, lon, name } => {
+                let coords = Coords {
+                    latitude: lat,
+                    longitude: lon,
+                };
+                Ok(match name {
+                    Some(name) => Waypoint::Named(name, coords),
+                    None => Waypoint::Coords(coords),
+                })
+            }
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use std::io;
+
+    use super::{Waypoint, WaypointResolver};
+    use crate::{
+        embedded::Embedded,
+        error::Error,
+        golden::airports,
+        lookup::Lookup,
+        model::{Airport, Coords},
+    };
+
+    /// A source that can't be read, as an index on a failing disk.
+    struct Unreadable;
+
+    impl Lookup for Unreadable {
+        fn by_identifier(&self, _: &str) -> crate::Result<Option<Airport>> {
+            Err(io::Error::other("unreadable").into())
+        }
+
+        fn search(&self, _: &str, _: usize) -> crate::Result<Vec<Airport>> {
+            Ok(Vec::new())
+        }
+
+        fn nearest(&self, _: &Coords, _: usize) -> crate::Result<Vec<Airport>> {
+            Ok(Vec::new())
+        }
+    }
+
+    #[test]
+    fn resolves_in_order_of_preference() {
+        let data = Embedded::from_airports(airports());
+        let home = Coords {
+            latitude: 47.5,
+            longitude: -122.3,
+        };
+        let resolver = WaypointResolver::new(&data)
+            .with_user_waypoints([("KPAE".to_string(), home), ("Home".to_string(), home)]);
+
+        let resolve = |identifier| resolver.resolve(identifier).unwrap();
+        assert!(
+            matches!(resolve("KPAE=48 -122"), Waypoint::Named(name, coords)
+            if name == "KPAE" && coords.latitude == 48.0)
+        );
+        assert!(matches!(resolve("kpae"), Waypoint::Named(name, coords)
+            if name == "KPAE" && coords == home));
+        assert!(matches!(resolve("HOME"), Waypoint::Named(name, _) if name == "Home"));
+        assert!(matches!(resolve("KSEA"), Waypoint::Airport(airport) if airport.ident == "KSEA"));
+        assert!(matches!(resolve("47.5 -122.3"), Waypoint::Coords(coords) if coords == home));
+
+        assert!(matches!(
+            resolver.resolve("KXXX"),
+            Err(Error::UnknownIdentifier(ident)) if ident == "KXXX"
+        ));
+        assert!(resolver.resolve("=48 -122").is_err());
+
+        for identifier in ["KPAE=48 -122", "kpae", "HOME", "KSEA", "47.5 -122.3"] {
+            assert_eq!(
+                resolver.coordinates(identifier).unwrap(),
+                resolve(identifier).coordinates()
+            );
+        }
+        assert!(resolver.coordinates("KXXX").is_err());
+    }
+
+    #[test]
+    fn passes_on_errors_from_the_source() {
+        let resolver = WaypointResolver::new(&Unreadable);
+        assert!(matches!(resolver.resolve("KSEA"), Err(Error::IO(_))));
+    }
+}